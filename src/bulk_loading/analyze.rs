@@ -80,11 +80,135 @@ impl ColumnType {
     }
 }
 
+/// Coordinate dimensions stored in a geometry column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "geometry_dimension", rename_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum GeometryDimension {
+    Xy,
+    Xyz,
+    Xym,
+    Xyzm,
+}
+
+impl GeometryDimension {
+    pub fn new(has_z: bool, has_m: bool) -> Self {
+        match (has_z, has_m) {
+            (false, false) => GeometryDimension::Xy,
+            (true, false) => GeometryDimension::Xyz,
+            (false, true) => GeometryDimension::Xym,
+            (true, true) => GeometryDimension::Xyzm,
+        }
+    }
+
+    #[inline]
+    pub fn has_z(&self) -> bool {
+        matches!(self, GeometryDimension::Xyz | GeometryDimension::Xyzm)
+    }
+
+    #[inline]
+    pub fn has_m(&self) -> bool {
+        matches!(self, GeometryDimension::Xym | GeometryDimension::Xyzm)
+    }
+
+    /// Dimension suffix used in WKT geometry tags (e.g. `POINT Z`)
+    pub fn wkt_tag(&self) -> &'static str {
+        match self {
+            GeometryDimension::Xy => "",
+            GeometryDimension::Xyz => "Z",
+            GeometryDimension::Xym => "M",
+            GeometryDimension::Xyzm => "ZM",
+        }
+    }
+}
+
+/// Extra details about a `Geometry` column
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "geometry_metadata")]
+pub struct GeometryMetadata {
+    dimension: GeometryDimension,
+    srid: Option<i32>,
+    #[serde(default)]
+    geometry_types: Vec<String>,
+}
+
+impl GeometryMetadata {
+    pub fn new(dimension: GeometryDimension, srid: Option<u16>) -> Self {
+        Self {
+            dimension,
+            srid: srid.map(i32::from),
+            geometry_types: Vec::new(),
+        }
+    }
+
+    /// Restrict the column to the provided geometry types (e.g. `Polygon`, `MultiPolygon`)
+    pub fn with_geometry_types(mut self, geometry_types: Vec<String>) -> Self {
+        self.geometry_types = geometry_types;
+        self
+    }
+
+    #[inline]
+    pub fn dimension(&self) -> &GeometryDimension {
+        &self.dimension
+    }
+
+    /// SRID of the column's geometries. [None] when the source CRS could not be determined
+    #[inline]
+    pub fn srid(&self) -> Option<i32> {
+        self.srid
+    }
+
+    /// Geometry types allowed in the column. Empty when any geometry type is allowed
+    #[inline]
+    pub fn geometry_types(&self) -> &[String] {
+        &self.geometry_types
+    }
+}
+
+/// Precision and scale of a `Number` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "numeric_metadata")]
+pub struct NumericMetadata {
+    precision: i32,
+    scale: i32,
+}
+
+impl NumericMetadata {
+    /// Maximum precision of a postgresql `numeric` type declared with a precision
+    const MAX_PRECISION: usize = 1000;
+
+    /// Create numeric details for the precision and scale. [None] when the values cannot be
+    /// declared for a postgresql `numeric` column.
+    pub fn new(precision: usize, scale: usize) -> Option<Self> {
+        if precision == 0 || precision > Self::MAX_PRECISION || scale > precision {
+            return None;
+        }
+        Some(Self {
+            precision: precision as i32,
+            scale: scale as i32,
+        })
+    }
+
+    #[inline]
+    pub fn precision(&self) -> i32 {
+        self.precision
+    }
+
+    #[inline]
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+}
+
 #[derive(Debug, sqlx::Type, Serialize, Deserialize)]
 #[sqlx(type_name = "column_metadata")]
 pub struct ColumnMetadata {
     name: String,
     column_type: ColumnType,
+    #[serde(default)]
+    geometry: Option<GeometryMetadata>,
+    #[serde(default)]
+    numeric: Option<NumericMetadata>,
 }
 
 impl ColumnMetadata {
@@ -93,6 +217,8 @@ impl ColumnMetadata {
             return Ok(Self {
                 name: name.to_lowercase(),
                 column_type,
+                geometry: None,
+                numeric: None,
             });
         }
         let Some(column_name) = clean_sql_name(name) else {
@@ -101,9 +227,25 @@ impl ColumnMetadata {
         Ok(Self {
             name: column_name,
             column_type,
+            geometry: None,
+            numeric: None,
         })
     }
 
+    /// Create a `Geometry` column with the provided geometry details
+    pub fn geometry(name: &str, metadata: GeometryMetadata) -> BulkDataResult<Self> {
+        let mut column = Self::new(name, ColumnType::Geometry)?;
+        column.geometry = Some(metadata);
+        Ok(column)
+    }
+
+    /// Create a `Number` column with a fixed precision and scale
+    pub fn numeric(name: &str, metadata: NumericMetadata) -> BulkDataResult<Self> {
+        let mut column = Self::new(name, ColumnType::Number)?;
+        column.numeric = Some(metadata);
+        Ok(column)
+    }
+
     #[inline]
     pub fn name(&self) -> &str {
         &self.name
@@ -113,6 +255,24 @@ impl ColumnMetadata {
     pub fn column_type(&self) -> &ColumnType {
         &self.column_type
     }
+
+    #[inline]
+    pub fn geometry_metadata(&self) -> Option<&GeometryMetadata> {
+        self.geometry.as_ref()
+    }
+
+    #[inline]
+    pub fn numeric_metadata(&self) -> Option<&NumericMetadata> {
+        self.numeric.as_ref()
+    }
+
+    /// Postgresql type of the column used when creating the column's table
+    pub fn pg_type(&self) -> String {
+        match &self.numeric {
+            Some(numeric) => format!("numeric({},{})", numeric.precision, numeric.scale),
+            None => self.column_type.pg_name().to_owned(),
+        }
+    }
 }
 
 impl PgHasArrayType for ColumnMetadata {
@@ -189,7 +349,7 @@ impl Schema {
             &self.table_name,
             self.columns
                 .iter()
-                .map(|c| format!("\"{}\" {}", &c.name, c.column_type.pg_name()))
+                .map(|c| format!("\"{}\" {}", &c.name, c.pg_type()))
                 .join(",")
         )
     }
@@ -203,4 +363,9 @@ impl Schema {
     pub fn columns(&self) -> &[ColumnMetadata] {
         &self.columns
     }
+
+    #[inline]
+    pub fn into_parts(self) -> (String, Vec<ColumnMetadata>) {
+        (self.table_name, self.columns)
+    }
 }
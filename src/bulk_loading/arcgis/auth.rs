@@ -0,0 +1,352 @@
+use crate::bulk_loading::error::BulkDataResult;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use reqwest::{Client, IntoUrl, RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{sync::RwLock, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+
+/// Time before a generated token expires when a new token is requested
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+/// Header used to send API keys to ArcGIS services
+const API_KEY_HEADER: &str = "X-Esri-Authorization";
+/// Prefix of the environment variables that [EnvironmentSecrets] can read, so source data options
+/// cannot reference any other variable of the server's environment, including the server's own
+/// `GEOFLOW_ARCGIS_` settings
+pub const SECRET_VARIABLE_PREFIX: &str = "GEOFLOW_ARCGIS_SECRET_";
+
+lazy_static! {
+    static ref SECRET_PROVIDER: RwLock<Box<dyn SecretProvider>> =
+        RwLock::new(Box::new(EnvironmentSecrets));
+}
+
+/// Source of the credentials referenced by name in [ArcGisAuth]. Keeps credentials out of the
+/// stored source data options.
+pub trait SecretProvider: Send + Sync {
+    fn secret(&self, name: &str) -> BulkDataResult<String>;
+}
+
+/// Default [SecretProvider] that reads credentials from environment variables. Only variables
+/// starting with [SECRET_VARIABLE_PREFIX] can be read.
+pub struct EnvironmentSecrets;
+
+impl SecretProvider for EnvironmentSecrets {
+    fn secret(&self, name: &str) -> BulkDataResult<String> {
+        if !name.starts_with(SECRET_VARIABLE_PREFIX) {
+            return Err(format!(
+                "Secret \"{}\" must start with \"{}\"",
+                name, SECRET_VARIABLE_PREFIX
+            )
+            .into());
+        }
+        match std::env::var(name) {
+            Ok(value) => Ok(value),
+            Err(error) => Err(format!(
+                "Could not read the secret \"{}\" from the environment. {}",
+                name, error
+            )
+            .into()),
+        }
+    }
+}
+
+/// Replace the provider used to resolve ArcGIS credentials
+pub fn set_secret_provider(provider: impl SecretProvider + 'static) -> BulkDataResult<()> {
+    let Ok(mut secret_provider) = SECRET_PROVIDER.write() else {
+        return Err("Secret provider lock was poisoned".into())
+    };
+    *secret_provider = Box::new(provider);
+    Ok(())
+}
+
+fn resolve_secret(name: &str) -> BulkDataResult<String> {
+    let Ok(secret_provider) = SECRET_PROVIDER.read() else {
+        return Err("Secret provider lock was poisoned".into())
+    };
+    secret_provider.secret(name)
+}
+
+fn default_expiration_minutes() -> u32 {
+    60
+}
+
+/// Authentication of a secured ArcGIS service. Each variant holds the names of secrets, resolved
+/// by the current [SecretProvider], rather than the credentials themselves.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArcGisAuth {
+    /// Static token sent as the `token` parameter of each request
+    Token { token_secret: String },
+    /// Credentials exchanged for tokens through `generateToken`. Tokens are refreshed before they
+    /// expire. The token url defaults to the `tokens/generateToken` endpoint of the server and
+    /// must have the same scheme, host and port as the service url.
+    UserPassword {
+        username_secret: String,
+        password_secret: String,
+        #[serde(default)]
+        token_url: Option<Url>,
+        #[serde(default = "default_expiration_minutes")]
+        expiration_minutes: u32,
+    },
+    /// API key sent in the `X-Esri-Authorization` header of each request
+    ApiKey { api_key_secret: String },
+}
+
+impl ArcGisAuth {
+    /// Resolve the credentials for requests to the service url
+    pub fn service_auth(&self, service_url: &Url) -> BulkDataResult<ServiceAuth> {
+        let credentials = match self {
+            Self::Token { token_secret } => Credentials::Token(resolve_secret(token_secret)?),
+            Self::UserPassword {
+                username_secret,
+                password_secret,
+                token_url,
+                expiration_minutes,
+            } => Credentials::UserPassword {
+                username: resolve_secret(username_secret)?,
+                password: resolve_secret(password_secret)?,
+                token_url: match token_url {
+                    Some(url) => check_token_url(url, service_url)?,
+                    None => default_token_url(service_url)?,
+                },
+                expiration_minutes: *expiration_minutes,
+            },
+            Self::ApiKey { api_key_secret } => Credentials::ApiKey(resolve_secret(api_key_secret)?),
+        };
+        Ok(ServiceAuth {
+            credentials,
+            token: Mutex::new(None),
+        })
+    }
+}
+
+/// `generateToken` endpoint of the ArcGIS server hosting the service
+fn default_token_url(service_url: &Url) -> BulkDataResult<Url> {
+    let url = service_url.as_str();
+    let Some(index) = url.find("/rest/services") else {
+        return Err(format!("Could not find the token url of \"{}\". Specify a \"token_url\"", url).into())
+    };
+    let token_url = format!("{}/tokens/generateToken", &url[..index]);
+    Ok(Url::parse(&token_url)?)
+}
+
+/// Credentials are only sent to the server hosting the service so a token url cannot send them
+/// to another host
+fn check_token_url(token_url: &Url, service_url: &Url) -> BulkDataResult<Url> {
+    if token_url.origin() != service_url.origin() {
+        return Err(format!(
+            "Token url \"{}\" must have the same scheme, host and port as the service url \"{}\"",
+            token_url, service_url
+        )
+        .into());
+    }
+    Ok(token_url.clone())
+}
+
+enum Credentials {
+    Token(String),
+    UserPassword {
+        username: String,
+        password: String,
+        token_url: Url,
+        expiration_minutes: u32,
+    },
+    ApiKey(String),
+}
+
+struct GeneratedToken {
+    token: String,
+    refresh_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct GenerateTokenResponse {
+    token: String,
+    /// Expiry as milliseconds since the unix epoch
+    expires: i64,
+}
+
+impl GenerateTokenResponse {
+    fn from_json(json: Value) -> BulkDataResult<Self> {
+        if let Some(error) = json.get("error") {
+            return Err(format!("Could not generate an ArcGIS token. {}", error).into());
+        }
+        Ok(serde_json::from_value(json)?)
+    }
+
+    fn into_generated_token(self) -> GeneratedToken {
+        let remaining = (self.expires - Utc::now().timestamp_millis()).max(0) as u64;
+        let remaining = Duration::from_millis(remaining).saturating_sub(TOKEN_REFRESH_MARGIN);
+        GeneratedToken {
+            token: self.token,
+            refresh_at: Instant::now() + remaining,
+        }
+    }
+}
+
+/// Resolved credentials of a service, adding authentication to each request
+pub struct ServiceAuth {
+    credentials: Credentials,
+    token: Mutex<Option<GeneratedToken>>,
+}
+
+impl ServiceAuth {
+    /// True if new tokens can be requested when the service rejects a token
+    pub fn can_refresh(&self) -> bool {
+        matches!(self.credentials, Credentials::UserPassword { .. })
+    }
+
+    /// Discard the current generated token so the next request generates a new token
+    pub async fn invalidate(&self) {
+        *self.token.lock().await = None;
+    }
+
+    async fn generated_token(&self, client: &Client) -> BulkDataResult<String> {
+        let (username, password, token_url, expiration_minutes) = match &self.credentials {
+            Credentials::UserPassword {
+                username,
+                password,
+                token_url,
+                expiration_minutes,
+            } => (username, password, token_url, expiration_minutes),
+            _ => return Err("Only username and password credentials generate tokens".into()),
+        };
+        let mut token = self.token.lock().await;
+        if let Some(generated) = token.as_ref() {
+            if Instant::now() < generated.refresh_at {
+                return Ok(generated.token.clone());
+            }
+        }
+        let expiration = expiration_minutes.to_string();
+        let params = [
+            ("username", username.as_str()),
+            ("password", password.as_str()),
+            ("client", "requestip"),
+            ("expiration", expiration.as_str()),
+            ("f", "json"),
+        ];
+        let json: Value = client
+            .post(token_url.clone())
+            .form(&params)
+            .send()
+            .await?
+            .json()
+            .await?;
+        let generated = GenerateTokenResponse::from_json(json)?.into_generated_token();
+        let value = generated.token.clone();
+        *token = Some(generated);
+        Ok(value)
+    }
+
+    /// Add the service credentials to the request
+    pub async fn authorize(
+        &self,
+        client: &Client,
+        request: RequestBuilder,
+    ) -> BulkDataResult<RequestBuilder> {
+        Ok(match &self.credentials {
+            Credentials::Token(token) => request.query(&[("token", token)]),
+            Credentials::UserPassword { .. } => {
+                let token = self.generated_token(client).await?;
+                request.query(&[("token", token)])
+            }
+            Credentials::ApiKey(api_key) => {
+                request.header(API_KEY_HEADER, format!("Bearer {}", api_key))
+            }
+        })
+    }
+}
+
+/// GET request to the url, authorized when the service is secured
+pub async fn get_request<U: IntoUrl>(
+    client: &Client,
+    url: U,
+    auth: Option<&ServiceAuth>,
+) -> BulkDataResult<RequestBuilder> {
+    let request = client.get(url);
+    match auth {
+        Some(auth) => auth.authorize(client, request).await,
+        None => Ok(request),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_token_url, default_token_url, ArcGisAuth, EnvironmentSecrets, GenerateTokenResponse,
+        SecretProvider, ServiceAuth,
+    };
+    use crate::bulk_loading::error::BulkDataResult;
+    use reqwest::{Client, Url};
+    use serde_json::json;
+
+    #[test]
+    fn default_token_url_should_use_server_root_when_url_is_rest_service() -> BulkDataResult<()> {
+        let url = Url::parse("https://example.com/arcgis/rest/services/Parcels/FeatureServer/0")?;
+
+        let token_url = default_token_url(&url)?;
+
+        assert_eq!(
+            "https://example.com/arcgis/tokens/generateToken",
+            token_url.as_str()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn check_token_url_should_fail_when_origin_differs_from_service() -> BulkDataResult<()> {
+        let url = Url::parse("https://example.com/arcgis/rest/services/Parcels/FeatureServer/0")?;
+        let portal = Url::parse("https://example.com/portal/sharing/rest/generateToken")?;
+        let other_host = Url::parse("https://tokens.example.org/arcgis/tokens/generateToken")?;
+        let other_scheme = Url::parse("http://example.com/arcgis/tokens/generateToken")?;
+        let other_port = Url::parse("https://example.com:8443/arcgis/tokens/generateToken")?;
+
+        assert_eq!(portal, check_token_url(&portal, &url)?);
+        assert!(check_token_url(&other_host, &url).is_err());
+        assert!(check_token_url(&other_scheme, &url).is_err());
+        assert!(check_token_url(&other_port, &url).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn generate_token_response_should_fail_when_json_is_error() {
+        let json = json!({ "error": { "code": 400, "message": "Invalid credentials" } });
+
+        assert!(GenerateTokenResponse::from_json(json).is_err());
+    }
+
+    #[tokio::test]
+    async fn authorize_should_add_token_parameter_when_auth_is_token() -> BulkDataResult<()> {
+        std::env::set_var("GEOFLOW_ARCGIS_SECRET_AUTH_TEST_TOKEN", "secret-token");
+        let auth: ArcGisAuth = serde_json::from_value(json!({
+            "type": "token",
+            "token_secret": "GEOFLOW_ARCGIS_SECRET_AUTH_TEST_TOKEN",
+        }))?;
+        let url = Url::parse("https://example.com/arcgis/rest/services/Parcels/FeatureServer/0")?;
+        let service_auth: ServiceAuth = auth.service_auth(&url)?;
+        let client = Client::new();
+
+        let request = service_auth
+            .authorize(&client, client.get(url.clone()))
+            .await?
+            .build()?;
+
+        assert_eq!(Some("token=secret-token"), request.url().query());
+        Ok(())
+    }
+
+    #[test]
+    fn environment_secrets_should_fail_when_name_is_not_prefixed() {
+        std::env::set_var("ARCGIS_AUTH_TEST_UNSCOPED", "secret-token");
+
+        std::env::set_var("GEOFLOW_ARCGIS_AUTH_TEST_SETTING", "server-setting");
+
+        assert!(EnvironmentSecrets
+            .secret("ARCGIS_AUTH_TEST_UNSCOPED")
+            .is_err());
+        assert!(EnvironmentSecrets
+            .secret("GEOFLOW_ARCGIS_AUTH_TEST_SETTING")
+            .is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use super::{
+    auth::ServiceAuth,
+    filter::QueryFilter,
+    metadata::{get_service_count, get_service_metadata, RestServiceFieldType},
+    retry::RetryPolicy,
+    scraping::fetch_json,
+};
+use crate::bulk_loading::error::BulkDataResult;
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// Service types with layers that can be scraped
+const CRAWLED_SERVICE_TYPES: [&str; 2] = ["FeatureServer", "MapServer"];
+/// Layer types that contain features or records
+const QUERYABLE_LAYER_TYPES: [&str; 2] = ["Feature Layer", "Table"];
+/// Maximum number of catalog entries requested by a single crawl
+const MAX_CRAWLED_NODES: usize = 5_000;
+
+#[derive(Deserialize)]
+struct CatalogJson {
+    #[serde(default)]
+    folders: Vec<String>,
+    #[serde(default)]
+    services: Vec<CatalogService>,
+}
+
+#[derive(Deserialize)]
+struct CatalogService {
+    name: String,
+    #[serde(rename = "type")]
+    service_type: String,
+}
+
+#[derive(Deserialize)]
+struct ServiceJson {
+    #[serde(default)]
+    layers: Vec<ServiceLayer>,
+    #[serde(default)]
+    tables: Vec<ServiceLayer>,
+}
+
+#[derive(Deserialize)]
+struct ServiceLayer {
+    id: i32,
+    #[serde(default, rename = "type")]
+    layer_type: Option<String>,
+    #[serde(default, rename = "subLayerIds")]
+    sub_layer_ids: Option<Vec<i32>>,
+}
+
+impl ServiceLayer {
+    /// False for group and raster layers that cannot be queried for records
+    fn is_queryable(&self) -> bool {
+        let is_group = matches!(self.sub_layer_ids, Some(ref ids) if !ids.is_empty());
+        let is_queryable_type = match self.layer_type {
+            Some(ref layer_type) => QUERYABLE_LAYER_TYPES.contains(&layer_type.as_str()),
+            None => true,
+        };
+        !is_group && is_queryable_type
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CatalogField {
+    name: String,
+    field_type: RestServiceFieldType,
+}
+
+/// Layer or table found while crawling a service catalog
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CatalogLayer {
+    url: Url,
+    name: String,
+    geometry_type: Option<String>,
+    record_count: i32,
+    fields: Vec<CatalogField>,
+}
+
+impl CatalogLayer {
+    #[inline]
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Catalog entry that could not be read while crawling
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CatalogError {
+    url: Url,
+    message: String,
+}
+
+/// Result of crawling a service catalog. Entries that cannot be read are reported without
+/// stopping the crawl.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CatalogCrawl {
+    layers: Vec<CatalogLayer>,
+    errors: Vec<CatalogError>,
+}
+
+impl CatalogCrawl {
+    #[inline]
+    pub fn layers(&self) -> &[CatalogLayer] {
+        &self.layers
+    }
+
+    #[inline]
+    pub fn errors(&self) -> &[CatalogError] {
+        &self.errors
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum CatalogNode {
+    /// Services root or folder
+    Directory(Url),
+    Service(Url),
+    Layer(Url),
+}
+
+impl CatalogNode {
+    /// Classify the url by the last segments of the path
+    fn from_url(url: &Url) -> Self {
+        let mut url = url.clone();
+        url.set_query(None);
+        url.set_fragment(None);
+        let trimmed = url.as_str().trim_end_matches('/').to_owned();
+        if let Ok(trimmed) = Url::parse(&trimmed) {
+            url = trimmed;
+        }
+        let segments: Vec<&str> = url.path_segments().map(|s| s.collect()).unwrap_or_default();
+        match segments.as_slice() {
+            [.., service_type, layer_id]
+                if CRAWLED_SERVICE_TYPES.contains(service_type)
+                    && layer_id.parse::<i32>().is_ok() =>
+            {
+                Self::Layer(url)
+            }
+            [.., service_type] if CRAWLED_SERVICE_TYPES.contains(service_type) => {
+                Self::Service(url)
+            }
+            _ => Self::Directory(url),
+        }
+    }
+
+    fn url(&self) -> &Url {
+        match self {
+            Self::Directory(url) => url,
+            Self::Service(url) => url,
+            Self::Layer(url) => url,
+        }
+    }
+}
+
+/// `rest/services` url that service and folder names in a catalog are relative to
+fn services_root(url: &Url) -> BulkDataResult<String> {
+    let url = url.as_str();
+    let Some(index) = url.find("/rest/services") else {
+        return Err(format!("Url \"{}\" is not within an ArcGIS REST services directory", url).into())
+    };
+    Ok(url[..index + "/rest/services".len()].to_owned())
+}
+
+/// Client and settings shared by the requests of a catalog crawl
+struct CatalogClient<'a> {
+    client: Client,
+    retry_policy: RetryPolicy,
+    auth: Option<&'a ServiceAuth>,
+}
+
+impl<'a> CatalogClient<'a> {
+    fn new(auth: Option<&'a ServiceAuth>) -> BulkDataResult<Self> {
+        let retry_policy = RetryPolicy::default();
+        let client = Client::builder().timeout(retry_policy.timeout()).build()?;
+        Ok(Self {
+            client,
+            retry_policy,
+            auth,
+        })
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &Url) -> BulkDataResult<T> {
+        let json_url = Url::parse_with_params(url.as_str(), [("f", "json")])?;
+        fetch_json(
+            &self.client,
+            json_url.as_str(),
+            None,
+            &self.retry_policy,
+            self.auth,
+        )
+        .await
+    }
+}
+
+async fn crawl_directory(
+    client: &CatalogClient<'_>,
+    url: &Url,
+) -> BulkDataResult<Vec<CatalogNode>> {
+    let catalog: CatalogJson = client.get_json(url).await?;
+    let root = services_root(url)?;
+    let mut nodes = Vec::new();
+    for folder in catalog.folders {
+        nodes.push(CatalogNode::Directory(Url::parse(&format!(
+            "{}/{}",
+            root, folder
+        ))?));
+    }
+    for service in catalog.services {
+        if !CRAWLED_SERVICE_TYPES.contains(&service.service_type.as_str()) {
+            continue;
+        }
+        nodes.push(CatalogNode::Service(Url::parse(&format!(
+            "{}/{}/{}",
+            root, service.name, service.service_type
+        ))?));
+    }
+    Ok(nodes)
+}
+
+async fn crawl_service(client: &CatalogClient<'_>, url: &Url) -> BulkDataResult<Vec<CatalogNode>> {
+    let service: ServiceJson = client.get_json(url).await?;
+    service
+        .layers
+        .iter()
+        .chain(service.tables.iter())
+        .filter(|layer| layer.is_queryable())
+        .map(|layer| -> BulkDataResult<CatalogNode> {
+            let layer_url = Url::parse(&format!("{}/{}", url, layer.id))?;
+            Ok(CatalogNode::Layer(layer_url))
+        })
+        .collect()
+}
+
+async fn crawl_layer(client: &CatalogClient<'_>, url: &Url) -> BulkDataResult<CatalogLayer> {
+    let (retry_policy, auth) = (&client.retry_policy, client.auth);
+    let metadata = get_service_metadata(&client.client, url, retry_policy, auth).await?;
+    let filter = QueryFilter::default();
+    let count = get_service_count(&client.client, url, &filter, retry_policy, auth).await?;
+    Ok(CatalogLayer {
+        url: url.clone(),
+        name: metadata.name().to_owned(),
+        geometry_type: metadata.geometry_type_name().map(|g| g.to_owned()),
+        record_count: count.count(),
+        fields: metadata
+            .fields()
+            .iter()
+            .map(|f| CatalogField {
+                name: f.name().to_owned(),
+                field_type: f.field_type().clone(),
+            })
+            .collect(),
+    })
+}
+
+/// Walk a services root, folder, MapServer, FeatureServer or layer url and list every layer and
+/// table found. Each entry is visited once and the crawl stops after [MAX_CRAWLED_NODES] entries.
+pub async fn crawl_catalog(url: &Url, auth: Option<&ServiceAuth>) -> BulkDataResult<CatalogCrawl> {
+    let client = CatalogClient::new(auth)?;
+    let mut crawl = CatalogCrawl::default();
+    let root = CatalogNode::from_url(url);
+    let mut visited = HashSet::from([root.url().clone()]);
+    let mut pending = VecDeque::from([root]);
+    let mut crawled_nodes = 0;
+    while let Some(node) = pending.pop_front() {
+        crawled_nodes += 1;
+        if crawled_nodes > MAX_CRAWLED_NODES {
+            crawl.errors.push(CatalogError {
+                url: node.url().clone(),
+                message: format!(
+                    "Crawl stopped after reaching the limit of {} catalog entries",
+                    MAX_CRAWLED_NODES
+                ),
+            });
+            break;
+        }
+        let result = match &node {
+            CatalogNode::Directory(url) => crawl_directory(&client, url).await,
+            CatalogNode::Service(url) => crawl_service(&client, url).await,
+            CatalogNode::Layer(url) => match crawl_layer(&client, url).await {
+                Ok(layer) => {
+                    crawl.layers.push(layer);
+                    Ok(vec![])
+                }
+                Err(error) => Err(error),
+            },
+        };
+        match result {
+            Ok(nodes) => pending.extend(
+                nodes
+                    .into_iter()
+                    .filter(|node| visited.insert(node.url().clone())),
+            ),
+            Err(error) => crawl.errors.push(CatalogError {
+                url: node.url().clone(),
+                message: format!("{}", error),
+            }),
+        }
+    }
+    Ok(crawl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{services_root, CatalogNode, ServiceLayer};
+    use crate::bulk_loading::error::BulkDataResult;
+    use reqwest::Url;
+    use serde_json::json;
+
+    static SERVICES_URL: &str = "https://example.com/arcgis/rest/services";
+
+    #[test]
+    fn from_url_should_classify_node_when_url_has_service_segments() -> BulkDataResult<()> {
+        let directory = Url::parse(&format!("{}/Folder/", SERVICES_URL))?;
+        let service = Url::parse(&format!("{}/Folder/Parcels/MapServer?f=json", SERVICES_URL))?;
+        let layer = Url::parse(&format!("{}/Folder/Parcels/FeatureServer/3", SERVICES_URL))?;
+
+        assert_eq!(
+            CatalogNode::Directory(Url::parse(&format!("{}/Folder", SERVICES_URL))?),
+            CatalogNode::from_url(&directory)
+        );
+        assert_eq!(
+            CatalogNode::Service(Url::parse(&format!(
+                "{}/Folder/Parcels/MapServer",
+                SERVICES_URL
+            ))?),
+            CatalogNode::from_url(&service)
+        );
+        assert_eq!(
+            CatalogNode::Layer(layer.clone()),
+            CatalogNode::from_url(&layer)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn services_root_should_strip_folder_when_url_is_folder() -> BulkDataResult<()> {
+        let url = Url::parse(&format!("{}/Folder", SERVICES_URL))?;
+
+        assert_eq!(SERVICES_URL, services_root(&url)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn is_queryable_should_return_false_when_layer_is_group() -> BulkDataResult<()> {
+        let group: ServiceLayer = serde_json::from_value(json!({
+            "id": 0,
+            "type": "Group Layer",
+            "subLayerIds": [1, 2],
+        }))?;
+        let layer: ServiceLayer = serde_json::from_value(json!({
+            "id": 1,
+            "subLayerIds": null,
+        }))?;
+
+        assert!(!group.is_queryable());
+        assert!(layer.is_queryable());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use crate::bulk_loading::error::BulkDataResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// Files within the directory of a checkpoint
+const STATE_FILE: &str = "checkpoint.json";
+const RECORDS_FILE: &str = "records.jsonl";
+
+/// Environment variable with the parent directory of scrape checkpoints
+const CHECKPOINT_DIRECTORY_VARIABLE: &str = "GEOFLOW_ARCGIS_CHECKPOINT_DIRECTORY";
+
+/// Parent directory of scrape checkpoints configured for the server. Defaults to a directory in the
+/// system temp directory.
+pub fn default_checkpoint_directory() -> PathBuf {
+    match std::env::var_os(CHECKPOINT_DIRECTORY_VARIABLE) {
+        Some(directory) => PathBuf::from(directory),
+        None => std::env::temp_dir().join("geoflow_arcgis_checkpoints"),
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct CheckpointState {
+    /// Options of the scrape. A checkpoint is only resumed by a scrape with the same options
+    options: Value,
+    /// Record count reported by the service when the scrape started
+    source_count: i32,
+    /// Number of queries, in query order, whose records are spooled
+    completed_queries: usize,
+    /// Length of the records file after the last completed query
+    records_length: u64,
+    /// Largest object id requested by the completed queries of an OID scrape. OID scrapes resume
+    /// after the id since the chunks of ids shift when features are added or deleted.
+    #[serde(default)]
+    last_object_id: Option<i32>,
+}
+
+/// Progress of an ArcGIS scrape persisted to disk. Records of each completed query are spooled so
+/// a failed scrape can replay them and continue from the next query.
+pub struct ScrapeCheckpoint {
+    directory: PathBuf,
+    state: CheckpointState,
+}
+
+impl ScrapeCheckpoint {
+    /// Open the checkpoint in the directory. A checkpoint left by a scrape with different options or
+    /// a different source count is discarded.
+    pub fn open(directory: PathBuf, options: Value, source_count: i32) -> BulkDataResult<Self> {
+        fs::create_dir_all(&directory)?;
+        let state_path = directory.join(STATE_FILE);
+        let existing = match fs::read_to_string(&state_path) {
+            Ok(text) => serde_json::from_str::<CheckpointState>(&text).ok(),
+            Err(_) => None,
+        };
+        let state = match existing {
+            Some(state) if state.options == options && state.source_count == source_count => state,
+            _ => CheckpointState {
+                options,
+                source_count,
+                completed_queries: 0,
+                records_length: 0,
+                last_object_id: None,
+            },
+        };
+        // Drop records of a query that was being spooled when the last scrape stopped
+        let records = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(directory.join(RECORDS_FILE))?;
+        records.set_len(state.records_length)?;
+        records.sync_all()?;
+        let checkpoint = Self { directory, state };
+        checkpoint.write_state()?;
+        Ok(checkpoint)
+    }
+
+    #[inline]
+    pub fn completed_queries(&self) -> usize {
+        self.state.completed_queries
+    }
+
+    #[inline]
+    pub fn last_object_id(&self) -> Option<i32> {
+        self.state.last_object_id
+    }
+
+    #[inline]
+    fn records_path(&self) -> PathBuf {
+        self.directory.join(RECORDS_FILE)
+    }
+
+    /// Replace the state file so an interrupted write never leaves a partial state
+    fn write_state(&self) -> BulkDataResult<()> {
+        let temp_path = self.directory.join(format!("{}.tmp", STATE_FILE));
+        let mut file = File::create(&temp_path)?;
+        file.write_all(serde_json::to_string(&self.state)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(temp_path, self.directory.join(STATE_FILE))?;
+        Ok(())
+    }
+
+    /// CSV records of the completed queries, in query order
+    pub fn spooled_records(&self) -> BulkDataResult<impl Iterator<Item = BulkDataResult<String>>> {
+        let reader = BufReader::new(File::open(self.records_path())?);
+        Ok(reader.lines().map(|line| Ok(serde_json::from_str(&line?)?)))
+    }
+
+    /// Spool the CSV records of the next query and mark the query as completed. OID queries pass
+    /// the largest object id they requested.
+    pub fn complete_query(
+        &mut self,
+        records: &[String],
+        last_object_id: Option<i32>,
+    ) -> BulkDataResult<()> {
+        let mut file = OpenOptions::new().append(true).open(self.records_path())?;
+        for record in records {
+            // Records are stored as JSON strings so line breaks within values are escaped
+            writeln!(file, "{}", serde_json::to_string(record)?)?;
+        }
+        file.sync_all()?;
+        self.state.records_length = file.metadata()?.len();
+        self.state.completed_queries += 1;
+        if last_object_id.is_some() {
+            self.state.last_object_id = last_object_id;
+        }
+        self.write_state()
+    }
+
+    /// Delete the checkpoint in the directory, if any, once the scraped records are committed
+    pub fn remove(directory: &Path) -> BulkDataResult<()> {
+        match fs::remove_dir_all(directory) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScrapeCheckpoint, RECORDS_FILE};
+    use crate::bulk_loading::error::BulkDataResult;
+    use serde_json::json;
+    use std::{fs::OpenOptions, io::Write};
+
+    fn records(checkpoint: &ScrapeCheckpoint) -> BulkDataResult<Vec<String>> {
+        checkpoint.spooled_records()?.collect()
+    }
+
+    #[test]
+    fn open_should_resume_completed_queries_when_options_match() -> BulkDataResult<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let directory = temp_dir.path().join("1");
+        let options = json!({ "url": "https://example.com/FeatureServer/0" });
+        let mut checkpoint = ScrapeCheckpoint::open(directory.clone(), options.clone(), 3)?;
+        checkpoint.complete_query(&["1,\"a\nb\"\n".to_owned(), "2,c\n".to_owned()], Some(2))?;
+        // Partial records of a query that did not complete
+        OpenOptions::new()
+            .append(true)
+            .open(directory.join(RECORDS_FILE))?
+            .write_all(b"\"3,d\\n\"\n")?;
+
+        let checkpoint = ScrapeCheckpoint::open(directory, options, 3)?;
+
+        assert_eq!(1, checkpoint.completed_queries());
+        assert_eq!(Some(2), checkpoint.last_object_id());
+        assert_eq!(vec!["1,\"a\nb\"\n", "2,c\n"], records(&checkpoint)?);
+        Ok(())
+    }
+
+    #[test]
+    fn open_should_restart_when_options_differ() -> BulkDataResult<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let directory = temp_dir.path().join("1");
+        let options = json!({ "url": "https://example.com/FeatureServer/0" });
+        let mut checkpoint = ScrapeCheckpoint::open(directory.clone(), options, 3)?;
+        checkpoint.complete_query(&["1,a\n".to_owned()], None)?;
+
+        let changed = json!({ "url": "https://example.com/FeatureServer/1" });
+        let checkpoint = ScrapeCheckpoint::open(directory, changed, 3)?;
+
+        assert_eq!(0, checkpoint.completed_queries());
+        assert_eq!(None, checkpoint.last_object_id());
+        assert!(records(&checkpoint)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn remove_should_succeed_when_checkpoint_does_not_exist() -> BulkDataResult<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let directory = temp_dir.path().join("1");
+        ScrapeCheckpoint::open(directory.clone(), json!({}), 3)?;
+
+        ScrapeCheckpoint::remove(&directory)?;
+        ScrapeCheckpoint::remove(&directory)?;
+
+        assert!(!directory.exists());
+        Ok(())
+    }
+}
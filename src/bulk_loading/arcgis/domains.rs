@@ -0,0 +1,214 @@
+use super::{map_arcgis_value, metadata::ServiceField};
+use crate::bulk_loading::{
+    analyze::{ColumnMetadata, ColumnType},
+    error::BulkDataResult,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Maximum number of out-of-range values kept as a sample for the range domain summary
+const RANGE_VIOLATION_SAMPLE_SIZE: usize = 5;
+
+/// How values of fields with a coded-value domain are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodedValueOutput {
+    /// Codes are written as is
+    #[default]
+    Code,
+    /// Codes are replaced with their description. Codes without a description are written as is.
+    Replace,
+    /// Codes are written as is and their description is written to an extra `<field>_desc` column
+    Description,
+}
+
+impl CodedValueOutput {
+    /// Schema columns for a service field, including the description column when requested
+    pub fn field_columns(&self, field: &ServiceField) -> BulkDataResult<Vec<ColumnMetadata>> {
+        let column_type: ColumnType = field.field_type().try_into()?;
+        if field.coded_values().is_none() {
+            return Ok(vec![ColumnMetadata::new(field.name(), column_type)?]);
+        }
+        Ok(match self {
+            Self::Code => vec![ColumnMetadata::new(field.name(), column_type)?],
+            Self::Replace => vec![ColumnMetadata::new(field.name(), ColumnType::Text)?],
+            Self::Description => vec![
+                ColumnMetadata::new(field.name(), column_type)?,
+                ColumnMetadata::new(&format!("{}_desc", field.name()), ColumnType::Text)?,
+            ],
+        })
+    }
+}
+
+/// Writes the values of a service field, decoding coded values and validating range domains.
+/// Values outside of the range domain are still written but counted for a summary of the load.
+pub struct FieldMapper<'f> {
+    field: &'f ServiceField,
+    coded_values: Option<HashMap<String, String>>,
+    coded_value_output: CodedValueOutput,
+    range: Option<(f64, f64)>,
+    range_violations: Mutex<RangeViolations>,
+}
+
+/// Number of values found outside of a range domain and the first few of those values
+#[derive(Default)]
+struct RangeViolations {
+    count: usize,
+    sample: Vec<f64>,
+}
+
+impl<'f> FieldMapper<'f> {
+    pub fn new(
+        field: &'f ServiceField,
+        coded_value_output: CodedValueOutput,
+        validate_ranges: bool,
+    ) -> Self {
+        Self {
+            field,
+            coded_values: field.coded_values(),
+            coded_value_output,
+            range: if validate_ranges { field.range() } else { None },
+            range_violations: Mutex::new(RangeViolations::default()),
+        }
+    }
+
+    fn check_range(&self, value: &Value) {
+        let (Some((min, max)), Some(number)) = (self.range, value.as_f64()) else {
+            return
+        };
+        if number >= min && number <= max {
+            return;
+        }
+        let Ok(mut violations) = self.range_violations.lock() else {
+            return
+        };
+        violations.count += 1;
+        if violations.sample.len() < RANGE_VIOLATION_SAMPLE_SIZE {
+            violations.sample.push(number);
+        }
+    }
+
+    /// Summary of the values found outside of the field's range domain. None when every value
+    /// was within the range or ranges are not validated.
+    pub fn range_violations(&self) -> Option<String> {
+        let (min, max) = self.range?;
+        let violations = self.range_violations.lock().ok()?;
+        if violations.count == 0 {
+            return None;
+        }
+        let sample: Vec<String> = violations.sample.iter().map(|n| n.to_string()).collect();
+        Some(format!(
+            "{} value(s) of field \"{}\" are outside of the range domain [{}, {}], e.g. {}",
+            violations.count,
+            self.field.name(),
+            min,
+            max,
+            sample.join(", ")
+        ))
+    }
+
+    /// CSV values written for the field value. Contains 2 values when descriptions are written to
+    /// an extra column.
+    pub fn csv_values(&self, value: &Value) -> Vec<BulkDataResult<String>> {
+        self.check_range(value);
+        let code = match map_arcgis_value(value, self.field) {
+            Ok(code) => code,
+            Err(error) => return vec![Err(error)],
+        };
+        let Some(coded_values) = &self.coded_values else {
+            return vec![Ok(code)]
+        };
+        let description = coded_values.get(&code).cloned();
+        match self.coded_value_output {
+            CodedValueOutput::Code => vec![Ok(code)],
+            CodedValueOutput::Replace => vec![Ok(description.unwrap_or(code))],
+            CodedValueOutput::Description => vec![Ok(code), Ok(description.unwrap_or_default())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodedValueOutput, FieldMapper};
+    use crate::bulk_loading::{arcgis::metadata::ServiceField, error::BulkDataResult};
+    use serde_json::{json, Value};
+
+    fn coded_field() -> BulkDataResult<ServiceField> {
+        Ok(serde_json::from_value(json!({
+            "name": "status",
+            "type": "esriFieldTypeSmallInteger",
+            "domain": {
+                "type": "codedValue",
+                "name": "Status",
+                "codedValues": [
+                    { "name": "Active", "code": 3 },
+                    { "name": "Retired", "code": 4 },
+                ],
+            },
+        }))?)
+    }
+
+    fn values(results: Vec<BulkDataResult<String>>) -> BulkDataResult<Vec<String>> {
+        results.into_iter().collect()
+    }
+
+    #[test]
+    fn csv_values_should_replace_code_when_output_is_replace() -> BulkDataResult<()> {
+        let field = coded_field()?;
+        let mapper = FieldMapper::new(&field, CodedValueOutput::Replace, false);
+
+        assert_eq!(vec!["Active"], values(mapper.csv_values(&json!(3)))?);
+        assert_eq!(vec!["5"], values(mapper.csv_values(&json!(5)))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn csv_values_should_add_description_when_output_is_description() -> BulkDataResult<()> {
+        let field = coded_field()?;
+        let mapper = FieldMapper::new(&field, CodedValueOutput::Description, false);
+
+        assert_eq!(vec!["4", "Retired"], values(mapper.csv_values(&json!(4)))?);
+        assert_eq!(vec!["", ""], values(mapper.csv_values(&Value::Null))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn range_violations_should_count_values_when_value_is_outside_range_domain(
+    ) -> BulkDataResult<()> {
+        let field: ServiceField = serde_json::from_value(json!({
+            "name": "lanes",
+            "type": "esriFieldTypeInteger",
+            "domain": { "type": "range", "name": "Lanes", "range": [1, 8] },
+        }))?;
+        let mapper = FieldMapper::new(&field, CodedValueOutput::Code, true);
+
+        assert_eq!(vec!["8"], values(mapper.csv_values(&json!(8)))?);
+        assert_eq!(None, mapper.range_violations());
+        assert_eq!(vec!["9"], values(mapper.csv_values(&json!(9)))?);
+        assert_eq!(vec!["0"], values(mapper.csv_values(&json!(0)))?);
+        assert_eq!(
+            Some(String::from(
+                "2 value(s) of field \"lanes\" are outside of the range domain [1, 8], e.g. 9, 0"
+            )),
+            mapper.range_violations()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_columns_should_add_description_column_when_output_is_description() -> BulkDataResult<()>
+    {
+        let field = coded_field()?;
+
+        let columns = CodedValueOutput::Description.field_columns(&field)?;
+
+        assert_eq!(2, columns.len());
+        assert_eq!("status_desc", columns[1].name());
+
+        Ok(())
+    }
+}
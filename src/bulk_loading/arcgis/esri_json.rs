@@ -0,0 +1,390 @@
+use crate::bulk_loading::{
+    error::BulkDataResult,
+    geometry::{Coordinate, Geometry},
+};
+use serde::{de::Error, Deserialize, Deserializer};
+
+/// Read an Esri JSON number. Esri writes `"NaN"` for missing values, such as the coordinates of an
+/// empty geometry or the M value of a vertex without a measure, so those are read as [None].
+fn esri_number<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum EsriNumber {
+        Number(Option<f64>),
+        Text(String),
+    }
+    match EsriNumber::deserialize(deserializer)? {
+        EsriNumber::Number(number) => Ok(number.filter(|n| !n.is_nan())),
+        EsriNumber::Text(text) if text.eq_ignore_ascii_case("NaN") => Ok(None),
+        EsriNumber::Text(text) => Err(D::Error::custom(format!(
+            "Expected an Esri JSON number. Found \"{}\"",
+            text
+        ))),
+    }
+}
+
+/// Value of an Esri JSON coordinate array
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct EsriValue(#[serde(deserialize_with = "esri_number")] Option<f64>);
+
+/// Esri JSON coordinate array. Values can be null or `"NaN"` when a vertex has no Z or M value.
+type EsriPosition = Vec<EsriValue>;
+
+/// Geometry object of an Esri JSON feature. The geometry type is not included in the object so
+/// the variant is found by the members present.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum EsriGeometry {
+    Multipoint {
+        points: Vec<EsriPosition>,
+        #[serde(default, rename = "hasZ")]
+        has_z: bool,
+        #[serde(default, rename = "hasM")]
+        has_m: bool,
+    },
+    Polyline {
+        paths: Vec<Vec<EsriPosition>>,
+        #[serde(default, rename = "hasZ")]
+        has_z: bool,
+        #[serde(default, rename = "hasM")]
+        has_m: bool,
+    },
+    Polygon {
+        rings: Vec<Vec<EsriPosition>>,
+        #[serde(default, rename = "hasZ")]
+        has_z: bool,
+        #[serde(default, rename = "hasM")]
+        has_m: bool,
+    },
+    Envelope {
+        #[serde(deserialize_with = "esri_number")]
+        xmin: Option<f64>,
+        #[serde(deserialize_with = "esri_number")]
+        ymin: Option<f64>,
+        #[serde(deserialize_with = "esri_number")]
+        xmax: Option<f64>,
+        #[serde(deserialize_with = "esri_number")]
+        ymax: Option<f64>,
+    },
+    Point {
+        #[serde(default, deserialize_with = "esri_number")]
+        x: Option<f64>,
+        #[serde(default, deserialize_with = "esri_number")]
+        y: Option<f64>,
+        #[serde(default, deserialize_with = "esri_number")]
+        z: Option<f64>,
+        #[serde(default, deserialize_with = "esri_number")]
+        m: Option<f64>,
+    },
+}
+
+impl EsriGeometry {
+    /// Convert to a [Geometry]. The `has_z` and `has_m` flags of the query response are used when
+    /// the geometry does not specify its own. [None] when the geometry is empty.
+    pub fn into_geometry(self, has_z: bool, has_m: bool) -> BulkDataResult<Option<Geometry>> {
+        Ok(match self {
+            Self::Point { x, y, z, m } => match (x, y) {
+                (Some(x), Some(y)) => Some(Geometry::Point(Coordinate::new(x, y, z, m))),
+                _ => None,
+            },
+            Self::Multipoint {
+                points,
+                has_z: geometry_has_z,
+                has_m: geometry_has_m,
+            } => {
+                let (has_z, has_m) = (has_z || geometry_has_z, has_m || geometry_has_m);
+                let points = positions_to_coordinates(&points, has_z, has_m)?;
+                if points.is_empty() {
+                    return Ok(None);
+                }
+                Some(Geometry::MultiPoint(points))
+            }
+            Self::Polyline {
+                paths,
+                has_z: geometry_has_z,
+                has_m: geometry_has_m,
+            } => {
+                let (has_z, has_m) = (has_z || geometry_has_z, has_m || geometry_has_m);
+                let mut paths = paths
+                    .iter()
+                    .map(|p| positions_to_coordinates(p, has_z, has_m))
+                    .collect::<BulkDataResult<Vec<_>>>()?;
+                match paths.len() {
+                    0 => None,
+                    1 => paths.pop().map(Geometry::LineString),
+                    _ => Some(Geometry::MultiLineString(paths)),
+                }
+            }
+            Self::Polygon {
+                rings,
+                has_z: geometry_has_z,
+                has_m: geometry_has_m,
+            } => {
+                let (has_z, has_m) = (has_z || geometry_has_z, has_m || geometry_has_m);
+                let rings = rings
+                    .iter()
+                    .map(|r| positions_to_coordinates(r, has_z, has_m))
+                    .collect::<BulkDataResult<Vec<_>>>()?;
+                rings_to_polygons(rings)
+            }
+            Self::Envelope {
+                xmin,
+                ymin,
+                xmax,
+                ymax,
+            } => {
+                let bounds = [xmin, ymin, xmax, ymax];
+                let [Some(xmin), Some(ymin), Some(xmax), Some(ymax)] = bounds else {
+                    return Ok(None)
+                };
+                let ring = [
+                    (xmin, ymin),
+                    (xmin, ymax),
+                    (xmax, ymax),
+                    (xmax, ymin),
+                    (xmin, ymin),
+                ]
+                .into_iter()
+                .map(|(x, y)| Coordinate::new(x, y, None, None))
+                .collect();
+                Some(Geometry::Polygon(vec![ring]))
+            }
+        })
+    }
+}
+
+/// Convert an Esri JSON coordinate array to a [Coordinate]. When only M values are present the
+/// third value is the M value, otherwise the third and fourth values are the Z and M values.
+fn position_to_coordinate(
+    position: &[EsriValue],
+    has_z: bool,
+    has_m: bool,
+) -> BulkDataResult<Coordinate> {
+    let [EsriValue(Some(x)), EsriValue(Some(y)), rest @ ..] = position else {
+        return Err(format!("Esri JSON coordinates must start with 2 numbers. Found {:?}", position).into())
+    };
+    let value = |index: usize| rest.get(index).and_then(|value| value.0);
+    let (z, m) = match (has_z, has_m) {
+        (false, true) => (None, value(0)),
+        (true, false) => (value(0), None),
+        _ => (value(0), value(1)),
+    };
+    Ok(Coordinate::new(*x, *y, z, m))
+}
+
+fn positions_to_coordinates(
+    positions: &[EsriPosition],
+    has_z: bool,
+    has_m: bool,
+) -> BulkDataResult<Vec<Coordinate>> {
+    positions
+        .iter()
+        .map(|p| position_to_coordinate(p, has_z, has_m))
+        .collect()
+}
+
+/// Shoelace formula area of the ring. Negative when the ring is clockwise
+fn signed_area(ring: &[Coordinate]) -> f64 {
+    ring.iter()
+        .zip(ring.iter().skip(1))
+        .map(|(a, b)| a.x() * b.y() - b.x() * a.y())
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Ray casting check for the coordinate being within the ring
+fn ring_contains(ring: &[Coordinate], coordinate: &Coordinate) -> bool {
+    let (x, y) = (coordinate.x(), coordinate.y());
+    let mut inside = false;
+    for (a, b) in ring.iter().zip(ring.iter().skip(1)) {
+        if (a.y() > y) != (b.y() > y) && x < (b.x() - a.x()) * (y - a.y()) / (b.y() - a.y()) + a.x()
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Group Esri polygon rings into polygons. Esri JSON writes outer rings clockwise and holes
+/// counterclockwise, so each clockwise ring starts a new polygon and each hole is added to the
+/// polygon with the smallest outer ring that contains it, since outer rings can be nested within
+/// the holes of other polygons. Holes without an outer ring become their own polygon.
+fn rings_to_polygons(rings: Vec<Vec<Coordinate>>) -> Option<Geometry> {
+    let (outer_rings, holes): (Vec<_>, Vec<_>) = rings
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .partition(|r| signed_area(r) < 0.0);
+    let mut polygons: Vec<Vec<Vec<Coordinate>>> =
+        outer_rings.into_iter().map(|r| vec![r]).collect();
+    for hole in holes {
+        let container = polygons
+            .iter_mut()
+            .filter(|p| ring_contains(&p[0], &hole[0]))
+            .min_by(|a, b| {
+                signed_area(&a[0])
+                    .abs()
+                    .total_cmp(&signed_area(&b[0]).abs())
+            });
+        match container {
+            Some(polygon) => polygon.push(hole),
+            None => polygons.push(vec![hole]),
+        }
+    }
+    match polygons.len() {
+        0 => None,
+        1 => polygons.pop().map(Geometry::Polygon),
+        _ => Some(Geometry::MultiPolygon(polygons)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EsriGeometry;
+    use crate::bulk_loading::{
+        error::BulkDataResult,
+        geometry::{Coordinate, Geometry},
+    };
+    use serde_json::json;
+
+    fn coordinates(values: &[(f64, f64)]) -> Vec<Coordinate> {
+        values
+            .iter()
+            .map(|(x, y)| Coordinate::new(*x, *y, None, None))
+            .collect()
+    }
+
+    #[test]
+    fn into_geometry_should_return_none_when_point_is_empty() -> BulkDataResult<()> {
+        let geometry: EsriGeometry = serde_json::from_value(json!({ "x": null, "y": null }))?;
+
+        assert_eq!(None, geometry.into_geometry(false, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_geometry_should_keep_m_when_only_has_m() -> BulkDataResult<()> {
+        let geometry: EsriGeometry = serde_json::from_value(json!({
+            "hasM": true,
+            "paths": [[[1.0, 2.0, 5.0], [3.0, 4.0, null]]],
+        }))?;
+
+        let expected = Geometry::LineString(vec![
+            Coordinate::new(1.0, 2.0, None, Some(5.0)),
+            Coordinate::new(3.0, 4.0, None, None),
+        ]);
+        assert_eq!(Some(expected), geometry.into_geometry(false, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_geometry_should_group_holes_when_polygon_has_multiple_rings() -> BulkDataResult<()> {
+        let outer = [
+            (0.0, 0.0),
+            (0.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 0.0),
+            (0.0, 0.0),
+        ];
+        let hole = [(2.0, 2.0), (4.0, 2.0), (4.0, 4.0), (2.0, 4.0), (2.0, 2.0)];
+        let second = [
+            (20.0, 0.0),
+            (20.0, 5.0),
+            (25.0, 5.0),
+            (25.0, 0.0),
+            (20.0, 0.0),
+        ];
+        let geometry: EsriGeometry = serde_json::from_value(json!({
+            "rings": [outer, second, hole],
+        }))?;
+
+        let expected = Geometry::MultiPolygon(vec![
+            vec![coordinates(&outer), coordinates(&hole)],
+            vec![coordinates(&second)],
+        ]);
+        assert_eq!(Some(expected), geometry.into_geometry(false, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_geometry_should_add_hole_to_innermost_outer_ring_when_rings_nested(
+    ) -> BulkDataResult<()> {
+        let outer = [
+            (0.0, 0.0),
+            (0.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 0.0),
+            (0.0, 0.0),
+        ];
+        let outer_hole = [(1.0, 1.0), (9.0, 1.0), (9.0, 9.0), (1.0, 9.0), (1.0, 1.0)];
+        let island = [(2.0, 2.0), (2.0, 8.0), (8.0, 8.0), (8.0, 2.0), (2.0, 2.0)];
+        let island_hole = [(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0), (4.0, 4.0)];
+        let geometry: EsriGeometry = serde_json::from_value(json!({
+            "rings": [outer, island, island_hole, outer_hole],
+        }))?;
+
+        let expected = Geometry::MultiPolygon(vec![
+            vec![coordinates(&outer), coordinates(&outer_hole)],
+            vec![coordinates(&island), coordinates(&island_hole)],
+        ]);
+        assert_eq!(Some(expected), geometry.into_geometry(false, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_geometry_should_read_nan_strings_as_missing_values() -> BulkDataResult<()> {
+        let geometry: EsriGeometry = serde_json::from_value(json!({
+            "hasM": true,
+            "paths": [[[1.0, 2.0, "NaN"], [3.0, 4.0, 5.0]]],
+        }))?;
+        let empty: EsriGeometry = serde_json::from_value(json!({ "x": "NaN", "y": "NaN" }))?;
+
+        let expected = Geometry::LineString(vec![
+            Coordinate::new(1.0, 2.0, None, None),
+            Coordinate::new(3.0, 4.0, None, Some(5.0)),
+        ]);
+        assert_eq!(Some(expected), geometry.into_geometry(false, false)?);
+        assert_eq!(None, empty.into_geometry(false, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_geometry_should_return_polygon_when_envelope() -> BulkDataResult<()> {
+        let geometry: EsriGeometry = serde_json::from_value(json!({
+            "xmin": 1.0,
+            "ymin": 2.0,
+            "xmax": 3.0,
+            "ymax": 4.0,
+        }))?;
+
+        let expected = Geometry::Polygon(vec![coordinates(&[
+            (1.0, 2.0),
+            (1.0, 4.0),
+            (3.0, 4.0),
+            (3.0, 2.0),
+            (1.0, 2.0),
+        ])]);
+        assert_eq!(Some(expected), geometry.into_geometry(false, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_geometry_should_use_response_flags_when_geometry_has_none() -> BulkDataResult<()> {
+        let geometry: EsriGeometry = serde_json::from_value(json!({
+            "points": [[1.0, 2.0, 3.0, 4.0]],
+        }))?;
+
+        let expected = Geometry::MultiPoint(vec![Coordinate::new(1.0, 2.0, Some(3.0), None)]);
+        assert_eq!(Some(expected), geometry.into_geometry(true, false)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use crate::bulk_loading::error::BulkDataResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+fn default_filter_srid() -> u16 {
+    4326
+}
+
+/// Spatial relationship between the filter geometry and the features returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpatialRelationship {
+    #[default]
+    Intersects,
+    Contains,
+    Crosses,
+    EnvelopeIntersects,
+    Overlaps,
+    Touches,
+    Within,
+}
+
+impl SpatialRelationship {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Intersects => "esriSpatialRelIntersects",
+            Self::Contains => "esriSpatialRelContains",
+            Self::Crosses => "esriSpatialRelCrosses",
+            Self::EnvelopeIntersects => "esriSpatialRelEnvelopeIntersects",
+            Self::Overlaps => "esriSpatialRelOverlaps",
+            Self::Touches => "esriSpatialRelTouches",
+            Self::Within => "esriSpatialRelWithin",
+        }
+    }
+}
+
+/// Geometry that features are compared against
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FilterGeometry {
+    Envelope {
+        xmin: f64,
+        ymin: f64,
+        xmax: f64,
+        ymax: f64,
+    },
+    Polygon {
+        rings: Vec<Vec<[f64; 2]>>,
+    },
+}
+
+impl FilterGeometry {
+    fn validate(&self) -> BulkDataResult<()> {
+        match self {
+            Self::Envelope {
+                xmin,
+                ymin,
+                xmax,
+                ymax,
+            } => {
+                if xmin > xmax || ymin > ymax {
+                    return Err("Envelope filter minimums must not exceed the maximums".into());
+                }
+            }
+            Self::Polygon { rings } => {
+                if rings.is_empty() || rings.iter().any(|r| r.len() < 4) {
+                    return Err("Polygon filter must have rings of at least 4 positions".into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn geometry_type(&self) -> &'static str {
+        match self {
+            Self::Envelope { .. } => "esriGeometryEnvelope",
+            Self::Polygon { .. } => "esriGeometryPolygon",
+        }
+    }
+
+    /// Esri JSON representation of the geometry
+    fn esri_json(&self, srid: u16) -> Value {
+        let spatial_reference = json!({ "wkid": srid });
+        match self {
+            Self::Envelope {
+                xmin,
+                ymin,
+                xmax,
+                ymax,
+            } => json!({
+                "xmin": xmin,
+                "ymin": ymin,
+                "xmax": xmax,
+                "ymax": ymax,
+                "spatialReference": spatial_reference,
+            }),
+            Self::Polygon { rings } => json!({
+                "rings": rings,
+                "spatialReference": spatial_reference,
+            }),
+        }
+    }
+}
+
+/// Only features with the spatial relationship to the geometry are scraped
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SpatialFilter {
+    geometry: FilterGeometry,
+    #[serde(default)]
+    spatial_rel: SpatialRelationship,
+    /// SRID of the filter geometry's coordinates
+    #[serde(default = "default_filter_srid")]
+    srid: u16,
+}
+
+impl SpatialFilter {
+    pub fn new(geometry: FilterGeometry, spatial_rel: SpatialRelationship, srid: u16) -> Self {
+        Self {
+            geometry,
+            spatial_rel,
+            srid,
+        }
+    }
+}
+
+/// Attribute, spatial and field filters applied to every query of an ArcGIS scrape
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct QueryFilter {
+    #[serde(default, rename = "where")]
+    where_clause: Option<String>,
+    #[serde(default)]
+    spatial: Option<SpatialFilter>,
+    /// Names of the fields to scrape. All fields are scraped when not specified
+    #[serde(default)]
+    out_fields: Option<Vec<String>>,
+}
+
+impl QueryFilter {
+    pub fn with_where(mut self, where_clause: &str) -> Self {
+        self.where_clause = Some(where_clause.to_owned());
+        self
+    }
+
+    pub fn with_spatial(mut self, spatial: SpatialFilter) -> Self {
+        self.spatial = Some(spatial);
+        self
+    }
+
+    pub fn with_out_fields(mut self, out_fields: Vec<String>) -> Self {
+        self.out_fields = Some(out_fields);
+        self
+    }
+
+    pub fn validate(&self) -> BulkDataResult<()> {
+        if let Some(where_clause) = &self.where_clause {
+            if where_clause.trim().is_empty() {
+                return Err("Filter where clause cannot be empty".into());
+            }
+        }
+        if let Some(spatial) = &self.spatial {
+            spatial.geometry.validate()?;
+        }
+        if let Some(out_fields) = &self.out_fields {
+            if out_fields.is_empty() {
+                return Err("Filter out_fields cannot be empty".into());
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn has_spatial(&self) -> bool {
+        self.spatial.is_some()
+    }
+
+    /// Where clause of the filter. Matches all features when not specified
+    pub fn where_clause(&self) -> &str {
+        self.where_clause.as_deref().unwrap_or("1=1")
+    }
+
+    /// Where clause combining the filter with another clause
+    pub fn combined_where(&self, clause: &str) -> String {
+        match &self.where_clause {
+            Some(where_clause) => format!("({}) and {}", where_clause, clause),
+            None => clause.to_owned(),
+        }
+    }
+
+    /// True if the field is selected by the filter's out fields. Names are not case sensitive
+    pub fn includes_field(&self, name: &str) -> bool {
+        match &self.out_fields {
+            Some(out_fields) => out_fields.iter().any(|f| f.eq_ignore_ascii_case(name)),
+            None => true,
+        }
+    }
+
+    /// Out fields that do not match any of the field names
+    pub fn missing_fields<'f>(&'f self, names: &[&str]) -> Vec<&'f str> {
+        let Some(out_fields) = &self.out_fields else {
+            return vec![]
+        };
+        out_fields
+            .iter()
+            .filter(|f| !names.iter().any(|n| f.eq_ignore_ascii_case(n)))
+            .map(|f| f.as_str())
+            .collect()
+    }
+
+    /// Query parameters of the spatial filter
+    pub fn spatial_params(&self) -> Vec<(&'static str, String)> {
+        let Some(spatial) = &self.spatial else {
+            return vec![]
+        };
+        vec![
+            (
+                "geometry",
+                spatial.geometry.esri_json(spatial.srid).to_string(),
+            ),
+            ("geometryType", spatial.geometry.geometry_type().to_owned()),
+            ("spatialRel", spatial.spatial_rel.name().to_owned()),
+            ("inSR", spatial.srid.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilterGeometry, QueryFilter, SpatialFilter, SpatialRelationship};
+    use crate::bulk_loading::error::BulkDataResult;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn combined_where_should_wrap_filter_when_where_is_set() {
+        let filter = QueryFilter::default().with_where("county = 'A' or county = 'B'");
+
+        let where_clause = filter.combined_where("OBJECTID >= 1");
+
+        assert_eq!(
+            "(county = 'A' or county = 'B') and OBJECTID >= 1",
+            where_clause
+        );
+    }
+
+    #[test]
+    fn spatial_params_should_describe_envelope_when_filter_is_envelope() -> BulkDataResult<()> {
+        let geometry = FilterGeometry::Envelope {
+            xmin: -80.0,
+            ymin: 40.0,
+            xmax: -79.0,
+            ymax: 41.0,
+        };
+        let filter = QueryFilter::default().with_spatial(SpatialFilter::new(
+            geometry,
+            SpatialRelationship::Within,
+            4326,
+        ));
+
+        let params = filter.spatial_params();
+
+        assert_eq!(4, params.len());
+        let geometry: Value = serde_json::from_str(&params[0].1)?;
+        assert_eq!(json!(-80.0), geometry["xmin"]);
+        assert_eq!(json!(4326), geometry["spatialReference"]["wkid"]);
+        assert_eq!(
+            ("geometryType", "esriGeometryEnvelope".to_owned()),
+            params[1]
+        );
+        assert_eq!(("spatialRel", "esriSpatialRelWithin".to_owned()), params[2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_fields_should_return_names_when_fields_are_not_found() {
+        let filter =
+            QueryFilter::default().with_out_fields(vec!["name".to_owned(), "status".to_owned()]);
+
+        assert_eq!(vec!["status"], filter.missing_fields(&["OBJECTID", "NAME"]));
+    }
+
+    #[test]
+    fn validate_should_fail_when_polygon_ring_is_too_short() -> BulkDataResult<()> {
+        let filter: QueryFilter = serde_json::from_value(json!({
+            "spatial": {
+                "geometry": { "type": "polygon", "rings": [[[0.0, 0.0], [1.0, 1.0], [0.0, 0.0]]] },
+            },
+        }))?;
+
+        assert!(filter.validate().is_err());
+
+        Ok(())
+    }
+}
@@ -1,16 +1,22 @@
 use itertools::Itertools;
 use reqwest::Url;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_string_from_number;
-use serde_json::json;
 use std::collections::HashMap;
 
 use crate::bulk_loading::{
-    analyze::{ColumnMetadata, ColumnType, Schema},
+    analyze::{ColumnMetadata, ColumnType, GeometryDimension, GeometryMetadata, Schema},
     error::{BulkDataError, BulkDataResult},
+    validation::GeometryValidation,
 };
 
-use super::scraping::QueryFormat;
+use super::{
+    auth::ServiceAuth,
+    domains::CodedValueOutput,
+    filter::QueryFilter,
+    retry::RetryPolicy,
+    scraping::{fetch_json, QueryFormat, RequestLimiter},
+};
 
 #[derive(Deserialize)]
 enum RestServiceGeometryType {
@@ -38,7 +44,7 @@ impl RestServiceGeometryType {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum RestServiceFieldType {
     #[serde(alias = "esriFieldTypeBlob")]
     Blob,
@@ -104,15 +110,14 @@ pub struct CodedValue {
 #[derive(Deserialize)]
 #[serde(tag = "type")]
 pub enum FieldDomain {
-    Range {
-        name: String,
-        range: Vec<i32>,
-    },
+    #[serde(alias = "range")]
+    Range { name: String, range: Vec<f64> },
     #[serde(alias = "codedValue")]
     Coded {
         #[serde(alias = "codedValues")]
         coded_values: Vec<CodedValue>,
     },
+    #[serde(alias = "inherited")]
     Inherited,
 }
 
@@ -157,6 +162,17 @@ impl ServiceField {
             None
         }
     }
+
+    /// Minimum and maximum values of the field's range domain
+    pub fn range(&self) -> Option<(f64, f64)> {
+        let Some(FieldDomain::Range { range, .. }) = &self.domain else {
+            return None
+        };
+        match range.as_slice() {
+            [min, max] => Some((*min, *max)),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -168,6 +184,10 @@ pub struct ArcGisRestJsonMetadata {
     server_type: String,
     #[serde(alias = "geometryType")]
     geo_type: Option<RestServiceGeometryType>,
+    #[serde(default, alias = "hasZ")]
+    has_z: bool,
+    #[serde(default, alias = "hasM")]
+    has_m: bool,
     fields: Vec<ServiceField>,
     #[serde(alias = "objectIdField")]
     oid_field: Option<String>,
@@ -175,13 +195,26 @@ pub struct ArcGisRestJsonMetadata {
     query_formats: String,
     #[serde(alias = "supportsPagination")]
     supports_pagination: Option<bool>,
-    #[serde(alias = "supportsStatistics")]
-    supports_statistics: Option<bool>,
     #[serde(alias = "advancedQueryCapabilities")]
     advanced_query_capabilities: Option<HashMap<String, bool>>,
 }
 
 impl ArcGisRestJsonMetadata {
+    #[inline]
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Esri name of the layer's geometry type. [None] for tables
+    pub(super) fn geometry_type_name(&self) -> Option<&'static str> {
+        self.geo_type.as_ref().map(|g| g.name())
+    }
+
+    #[inline]
+    pub(super) fn fields(&self) -> &[ServiceField] {
+        &self.fields
+    }
+
     fn supports_pagination(&self) -> bool {
         self.supports_pagination.unwrap_or(false)
             || *self
@@ -190,22 +223,41 @@ impl ArcGisRestJsonMetadata {
                 .and_then(|aqc| aqc.get("supportsPagination"))
                 .unwrap_or(&false)
     }
+}
 
-    fn supports_statistics(&self) -> bool {
-        self.supports_statistics.unwrap_or(false)
-            || *self
-                .advanced_query_capabilities
-                .as_ref()
-                .and_then(|aqc| aqc.get("supportsStatistics"))
-                .unwrap_or(&false)
+/// Query of a single page of features
+pub struct PageQuery {
+    url: String,
+    /// Number of features requested by the query. Only the last page of a pagination scrape can
+    /// hold fewer features.
+    page_size: usize,
+    /// Largest object id requested by an OID query
+    last_object_id: Option<i32>,
+}
+
+impl PageQuery {
+    #[inline]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    #[inline]
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    #[inline]
+    pub fn last_object_id(&self) -> Option<i32> {
+        self.last_object_id
     }
 }
 
 pub enum QueryIterator<'m> {
     Oid {
         query_url: Url,
-        oid_field_name: &'m str,
-        min_oid: i32,
+        filter: &'m QueryFilter,
+        /// Sorted object ids of the features to scrape
+        object_ids: &'m [i32],
         scrape_count: i32,
         fields: String,
         url_params: Vec<(&'m str, &'m str)>,
@@ -229,12 +281,17 @@ impl<'m> QueryIterator<'m> {
         let mut url_params = metadata.geometry_options()?;
         let fields = metadata.fields().map(|f| f.name.to_owned()).join(",");
         url_params.push(("f", metadata.query_format.as_str()));
+        url_params.extend(metadata.filter_params.iter().map(|(k, v)| (*k, v.as_str())));
         let query_url = match Url::parse(format!("{}/query", metadata.url).as_str()) {
             Ok(q) => q,
             Err(error) => return Err(error.into()),
         };
         if metadata.supports_pagination() {
-            url_params.push(("where", "1=1"));
+            url_params.push(("where", metadata.filter.where_clause()));
+            // Offsets only address the same features between requests when the order is stable
+            if let Some(ref oid_field) = metadata.json_metadata.oid_field {
+                url_params.push(("orderByFields", oid_field));
+            }
             Ok(Self::Pagination {
                 query_url,
                 scrape_count,
@@ -245,25 +302,54 @@ impl<'m> QueryIterator<'m> {
                 query_index: 0,
             })
         } else {
-            let Some(ref oid_field_name) = metadata.json_metadata.oid_field else {
-                return Err("OID is not found but OID queries are required".into())
-            };
-            let Some((_, min_oid)) = metadata.max_min_oid else {
-                return Err("Min OID is not found but the value is required for scraping".into())
+            if metadata.json_metadata.oid_field.is_none() {
+                return Err("OID is not found but OID queries are required".into());
+            }
+            let Some(ref object_ids) = metadata.object_ids else {
+                return Err("Object ids are not found but the values are required for scraping".into())
             };
             Ok(Self::Oid {
                 query_url,
-                oid_field_name,
-                min_oid,
+                filter: metadata.filter,
+                object_ids,
                 scrape_count,
                 fields,
                 url_params,
-                remaining_records_count: metadata.source_count,
+                remaining_records_count: object_ids.len() as i32,
                 query_index: 0,
             })
         }
     }
 
+    /// Skip the queries completed by a previous scrape. OID scrapes continue after the last
+    /// completed object id so ids added or deleted since then do not shift the chunks of ids.
+    /// Pagination scrapes skip the completed pages, which are ordered by object id.
+    pub fn resume(mut self, completed_queries: usize, last_object_id: Option<i32>) -> Self {
+        match &mut self {
+            Self::Oid {
+                object_ids,
+                remaining_records_count,
+                ..
+            } => {
+                if let Some(last_object_id) = last_object_id {
+                    let ids: &'m [i32] = *object_ids;
+                    *object_ids = &ids[ids.partition_point(|id| *id <= last_object_id)..];
+                    *remaining_records_count = object_ids.len() as i32;
+                }
+            }
+            Self::Pagination {
+                scrape_count,
+                remaining_records_count,
+                query_index,
+                ..
+            } => {
+                *query_index = completed_queries as i32;
+                *remaining_records_count -= completed_queries as i32 * *scrape_count;
+            }
+        }
+        self
+    }
+
     #[inline]
     fn query_url(&self) -> &Url {
         match self {
@@ -342,7 +428,7 @@ impl<'m> QueryIterator<'m> {
 }
 
 impl<'m> Iterator for QueryIterator<'m> {
-    type Item = BulkDataResult<String>;
+    type Item = BulkDataResult<PageQuery>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if *self.remaining_records_count() <= 0 {
@@ -350,24 +436,23 @@ impl<'m> Iterator for QueryIterator<'m> {
         }
         let mut url_params = self.url_params().to_vec();
         url_params.push(("outFields", self.fields()));
-        let url_parse = match self {
+        let (url_parse, page_size, last_object_id) = match &*self {
             Self::Oid {
-                ref oid_field_name,
-                ref min_oid,
+                filter,
+                object_ids,
                 ref scrape_count,
                 ref query_index,
                 ..
             } => {
-                let lower_bound = *min_oid + (*query_index * *scrape_count);
-                let where_clause = format!(
-                    "{} >= {} and {} <= {}",
-                    oid_field_name,
-                    lower_bound,
-                    oid_field_name,
-                    lower_bound + *scrape_count - 1,
-                );
-                url_params.push(("where", &where_clause));
-                Url::parse_with_params(self.query_url().as_str(), url_params)
+                // Each query lists an exact chunk of ids so gaps between ids do not produce empty
+                // or missing pages
+                let start = (*query_index * *scrape_count) as usize;
+                let end = (start + *scrape_count as usize).min(object_ids.len());
+                let ids = object_ids[start..end].iter().join(",");
+                url_params.push(("where", filter.where_clause()));
+                url_params.push(("objectIds", &ids));
+                let url_parse = Url::parse_with_params(self.query_url().as_str(), url_params);
+                (url_parse, end - start, Some(object_ids[end - 1]))
             }
             Self::Pagination {
                 ref scrape_count,
@@ -378,7 +463,8 @@ impl<'m> Iterator for QueryIterator<'m> {
                 let result_offset = format!("{}", *query_index * *scrape_count);
                 url_params.push(("resultOffset", &result_offset));
                 url_params.push(("resultRecordCount", result_count));
-                Url::parse_with_params(self.query_url().as_str(), url_params)
+                let url_parse = Url::parse_with_params(self.query_url().as_str(), url_params);
+                (url_parse, *scrape_count as usize, None)
             }
         };
         let url = match url_parse {
@@ -390,16 +476,23 @@ impl<'m> Iterator for QueryIterator<'m> {
         };
         self.update_query_index();
         self.update_remaining_records_count();
-        Some(Ok(url.to_string()))
+        Some(Ok(PageQuery {
+            url: url.to_string(),
+            page_size,
+            last_object_id,
+        }))
     }
 }
 
 pub struct ArcGisRestMetadata<'u> {
     url: &'u Url,
+    out_sr: String,
+    filter: &'u QueryFilter,
+    filter_params: Vec<(&'static str, String)>,
     json_metadata: ArcGisRestJsonMetadata,
     query_format: QueryFormat,
     source_count: i32,
-    max_min_oid: Option<(i32, i32)>,
+    object_ids: Option<Vec<i32>>,
 }
 
 impl<'u> ArcGisRestMetadata<'u> {
@@ -432,6 +525,11 @@ impl<'u> ArcGisRestMetadata<'u> {
         }
     }
 
+    #[inline]
+    pub fn source_count(&self) -> i32 {
+        self.source_count
+    }
+
     #[inline]
     pub fn query_format(&self) -> &QueryFormat {
         &self.query_format
@@ -442,10 +540,11 @@ impl<'u> ArcGisRestMetadata<'u> {
     }
 
     pub fn fields(&self) -> impl Iterator<Item = &ServiceField> {
-        self.json_metadata
-            .fields
-            .iter()
-            .filter(|f| f.name != "Shape" && f.field_type != RestServiceFieldType::Geometry)
+        self.json_metadata.fields.iter().filter(|f| {
+            f.name != "Shape"
+                && f.field_type != RestServiceFieldType::Geometry
+                && self.filter.includes_field(&f.name)
+        })
     }
 
     fn geometry_options(&self) -> BulkDataResult<Vec<(&str, &str)>> {
@@ -455,13 +554,28 @@ impl<'u> ArcGisRestMetadata<'u> {
             let Some(ref geometry_type) = self.json_metadata.geo_type else {
                 return Err("Service is not a Table but geometry type unavailable".into())
             };
-            Ok(vec![
-                ("geometryType", geometry_type.name()),
-                ("outSR", "4326"),
-            ])
+            let mut options = vec![("outSR", self.out_sr.as_str())];
+            // The geometryType parameter describes the spatial filter's geometry when filtering
+            if !self.filter.has_spatial() {
+                options.push(("geometryType", geometry_type.name()));
+            }
+            if self.json_metadata.has_z {
+                options.push(("returnZ", "true"));
+            }
+            if self.json_metadata.has_m {
+                options.push(("returnM", "true"));
+            }
+            Ok(options)
         }
     }
 
+    /// Scrapes can only resume from a checkpoint when the features have a stable order, i.e. the
+    /// service has an OID field
+    #[inline]
+    pub fn supports_resume(&self) -> bool {
+        self.json_metadata.oid_field.is_some()
+    }
+
     pub fn queries(&self) -> BulkDataResult<QueryIterator> {
         if !self.valid_service() {
             return Err("Service is not valid for scraping. This means either the pagination option is not provided or there is no OID field".into());
@@ -469,10 +583,58 @@ impl<'u> ArcGisRestMetadata<'u> {
         QueryIterator::new(self)
     }
 
-    pub async fn from_url(url: &'u Url) -> BulkDataResult<ArcGisRestMetadata<'u>> {
-        let client = reqwest::Client::new();
-        let source_count = get_service_count(&client, url).await?;
-        let mut json_metadata = get_service_metadata(&client, url).await?;
+    /// SRID requested for the service's geometries
+    #[inline]
+    pub fn srid(&self) -> Option<u16> {
+        self.out_sr.parse().ok()
+    }
+
+    /// Schema of the service's fields, with geometry columns as required by the validation mode
+    /// when the service is not a table
+    pub fn schema(
+        &self,
+        geometry_validation: GeometryValidation,
+        coded_value_output: CodedValueOutput,
+    ) -> BulkDataResult<Schema> {
+        let mut columns: Vec<ColumnMetadata> = Vec::new();
+        for field in self.fields() {
+            columns.extend(coded_value_output.field_columns(field)?);
+        }
+        if !self.is_table() {
+            let dimension =
+                GeometryDimension::new(self.json_metadata.has_z, self.json_metadata.has_m);
+            let metadata = GeometryMetadata::new(dimension, self.srid());
+            columns.extend(geometry_validation.geometry_columns("geometry", metadata)?);
+        }
+        Schema::new(self.name(), columns)
+    }
+
+    pub async fn from_url(
+        url: &'u Url,
+        out_srid: u16,
+        filter: &'u QueryFilter,
+        limiter: Option<&RequestLimiter>,
+        retry_policy: &RetryPolicy,
+        auth: Option<&ServiceAuth>,
+    ) -> BulkDataResult<ArcGisRestMetadata<'u>> {
+        filter.validate()?;
+        retry_policy.validate()?;
+        let client = reqwest::Client::builder()
+            .timeout(retry_policy.timeout())
+            .build()?;
+        let source_count =
+            get_service_count(&client, url, filter, limiter, retry_policy, auth).await?;
+        let mut json_metadata =
+            get_service_metadata(&client, url, limiter, retry_policy, auth).await?;
+        let field_names: Vec<&str> = json_metadata.fields.iter().map(|f| f.name()).collect();
+        let missing_fields = filter.missing_fields(&field_names);
+        if !missing_fields.is_empty() {
+            return Err(format!(
+                "Filter out fields were not found in the service: {}",
+                missing_fields.join(", ")
+            )
+            .into());
+        }
         let mut oid_field = json_metadata.oid_field.take();
 
         if oid_field.is_none() {
@@ -483,16 +645,15 @@ impl<'u> ArcGisRestMetadata<'u> {
                 .map(|field| field.name.to_owned());
         }
 
-        let max_min_oid = match oid_field {
+        let object_ids = match oid_field {
             Some(oid) => {
-                let max_min = if !json_metadata.supports_pagination() {
-                    get_service_max_min(&client, url, &oid, json_metadata.supports_statistics())
-                        .await?
+                let object_ids = if !json_metadata.supports_pagination() {
+                    Some(get_object_ids(&client, url, filter, limiter, retry_policy, auth).await?)
                 } else {
                     None
                 };
                 json_metadata.oid_field = Some(oid);
-                max_min
+                object_ids
             }
             None => None,
         };
@@ -501,150 +662,124 @@ impl<'u> ArcGisRestMetadata<'u> {
 
         let rest_metadata = Self {
             url,
+            out_sr: out_srid.to_string(),
+            filter,
+            filter_params: filter.spatial_params(),
             json_metadata,
             query_format: format,
             source_count: source_count.count,
-            max_min_oid,
+            object_ids,
         };
         Ok(rest_metadata)
     }
 }
 
-impl<'u> TryFrom<ArcGisRestMetadata<'u>> for Schema {
-    type Error = BulkDataError;
+#[derive(Deserialize)]
+pub(super) struct CountQueryResponse {
+    count: i32,
+}
 
-    fn try_from(value: ArcGisRestMetadata<'u>) -> Result<Self, Self::Error> {
-        let mut columns: Vec<ColumnMetadata> = value
-            .fields()
-            .map(|f| -> BulkDataResult<ColumnMetadata> {
-                ColumnMetadata::new(f.name(), f.field_type().try_into()?)
-            })
-            .collect::<BulkDataResult<_>>()?;
-        if !value.is_table() {
-            columns.push(ColumnMetadata::new("geometry", ColumnType::Geometry)?);
-        }
-        Schema::new(value.name(), columns)
+impl CountQueryResponse {
+    #[inline]
+    pub(super) fn count(&self) -> i32 {
+        self.count
     }
 }
 
-#[derive(Deserialize)]
-struct CountQueryResponse {
-    count: i32,
+/// Query parameters that apply the filter's where clause and spatial filter
+fn filter_params(filter: &QueryFilter) -> Vec<(&'static str, String)> {
+    let mut params = vec![("where", filter.where_clause().to_owned())];
+    params.extend(filter.spatial_params());
+    params
 }
 
-async fn get_service_count(
+pub(super) async fn get_service_count(
     client: &reqwest::Client,
     url: &Url,
+    filter: &QueryFilter,
+    limiter: Option<&RequestLimiter>,
+    retry_policy: &RetryPolicy,
+    auth: Option<&ServiceAuth>,
 ) -> BulkDataResult<CountQueryResponse> {
-    let count_url = Url::parse_with_params(
-        format!("{}/query", url).as_str(),
-        [("where", "1=1"), ("returnCountOnly", "true"), ("f", "json")],
-    )?;
-    let count_json: CountQueryResponse = client.get(count_url).send().await?.json().await?;
-    Ok(count_json)
+    let mut params = filter_params(filter);
+    params.push(("returnCountOnly", String::from("true")));
+    params.push(("f", String::from("json")));
+    let count_url = Url::parse_with_params(format!("{}/query", url).as_str(), params)?;
+    fetch_json(client, count_url.as_str(), limiter, retry_policy, auth).await
 }
 
-async fn get_service_metadata(
+pub(super) async fn get_service_metadata(
     client: &reqwest::Client,
     url: &Url,
+    limiter: Option<&RequestLimiter>,
+    retry_policy: &RetryPolicy,
+    auth: Option<&ServiceAuth>,
 ) -> BulkDataResult<ArcGisRestJsonMetadata> {
     let metadata_url = Url::parse_with_params(url.as_str(), [("f", "json")])?;
-    let metadata_json: ArcGisRestJsonMetadata =
-        client.get(metadata_url).send().await?.json().await?;
-    Ok(metadata_json)
+    fetch_json(client, metadata_url.as_str(), limiter, retry_policy, auth).await
 }
 
 #[derive(Deserialize)]
-struct StatisticsResponseAttributes {
-    #[serde(alias = "MAX_VALUE")]
-    max: i32,
-    #[serde(alias = "MIN_VALUE")]
-    min: i32,
-}
-
-#[derive(Deserialize)]
-struct StatisticsResponseFeature {
-    attributes: StatisticsResponseAttributes,
-}
-
-#[derive(Deserialize)]
-struct StatisticsResponse {
-    features: Vec<StatisticsResponseFeature>,
+struct ObjectIdsResponse {
+    /// Services return null when no features match the query
+    #[serde(default, alias = "objectIds")]
+    object_ids: Option<Vec<i32>>,
 }
 
-fn out_statistics_parameter(oid_field_name: &str) -> String {
-    json!([
-        {
-            "statisticType": "max",
-            "onStatisticField": oid_field_name,
-            "outStatisticFieldName": "MAX_VALUE",
-        },
-        {
-            "statisticType": "min",
-            "onStatisticField": oid_field_name,
-            "outStatisticFieldName": "MIN_VALUE",
-        },
-    ])
-    .to_string()
+impl ObjectIdsResponse {
+    /// Object ids in ascending order. Services do not guarantee the order of the ids
+    fn into_sorted_ids(self) -> Vec<i32> {
+        let mut object_ids = self.object_ids.unwrap_or_default();
+        object_ids.sort_unstable();
+        object_ids.dedup();
+        object_ids
+    }
 }
 
-async fn get_service_max_min(
+async fn get_object_ids(
     client: &reqwest::Client,
     url: &Url,
-    oid_field_name: &str,
-    stats_enabled: bool,
-) -> BulkDataResult<Option<(i32, i32)>> {
-    let result = if stats_enabled {
-        get_service_max_min_stats(client, url, oid_field_name).await?
-    } else {
-        get_service_max_min_oid(client, url).await?
-    };
-    Ok(result)
+    filter: &QueryFilter,
+    limiter: Option<&RequestLimiter>,
+    retry_policy: &RetryPolicy,
+    auth: Option<&ServiceAuth>,
+) -> BulkDataResult<Vec<i32>> {
+    let mut params = filter_params(filter);
+    params.push(("returnIdsOnly", String::from("true")));
+    params.push(("f", String::from("json")));
+    let object_ids_url = Url::parse_with_params(format!("{}/query", url).as_str(), params)?;
+    let object_ids_json: ObjectIdsResponse =
+        fetch_json(client, object_ids_url.as_str(), limiter, retry_policy, auth).await?;
+    Ok(object_ids_json.into_sorted_ids())
 }
 
-#[derive(Deserialize)]
-struct ObjectIdsResponse {
-    #[serde(alias = "objectIds")]
-    object_ids: Vec<i32>,
-}
+#[cfg(test)]
+mod tests {
+    use super::ObjectIdsResponse;
+    use crate::bulk_loading::error::BulkDataResult;
+    use serde_json::json;
 
-async fn get_object_ids_response(
-    client: &reqwest::Client,
-    url: &Url,
-) -> BulkDataResult<ObjectIdsResponse> {
-    let max_min_url = Url::parse_with_params(
-        format!("{}/query", url).as_str(),
-        [("where", "1=1"), ("returnIdsOnly", "true"), ("f", "json")],
-    )?;
-    let max_min_json = client.get(max_min_url).send().await?.json().await?;
-    Ok(max_min_json)
-}
+    #[test]
+    fn into_sorted_ids_should_sort_ids_when_response_is_unordered() -> BulkDataResult<()> {
+        let response: ObjectIdsResponse = serde_json::from_value(json!({
+            "objectIdFieldName": "OBJECTID",
+            "objectIds": [9001, 3, 250000, 4],
+        }))?;
 
-async fn get_service_max_min_oid(
-    client: &reqwest::Client,
-    url: &Url,
-) -> BulkDataResult<Option<(i32, i32)>> {
-    let max_min_json = get_object_ids_response(client, url).await?;
-    Ok(Some((
-        max_min_json.object_ids[max_min_json.object_ids.len() - 1],
-        max_min_json.object_ids[0],
-    )))
-}
+        assert_eq!(vec![3, 4, 9001, 250000], response.into_sorted_ids());
 
-async fn get_service_max_min_stats(
-    client: &reqwest::Client,
-    url: &Url,
-    oid_field_name: &str,
-) -> BulkDataResult<Option<(i32, i32)>> {
-    let out_statistics = out_statistics_parameter(oid_field_name);
-    let max_min_url = Url::parse_with_params(
-        format!("{}/query", url).as_str(),
-        [("outStatistics", out_statistics.as_str()), ("f", "json")],
-    )?;
-    let max_min_json: StatisticsResponse = client.get(max_min_url).send().await?.json().await?;
-    if max_min_json.features.is_empty() {
-        return Err("No features in max min response".into());
-    }
-    let feature = &max_min_json.features[0];
-    Ok(Some((feature.attributes.max, feature.attributes.min)))
+        Ok(())
+    }
+
+    #[test]
+    fn into_sorted_ids_should_be_empty_when_ids_are_null() -> BulkDataResult<()> {
+        let response: ObjectIdsResponse = serde_json::from_value(json!({
+            "objectIdFieldName": "OBJECTID",
+            "objectIds": null,
+        }))?;
+
+        assert!(response.into_sorted_ids().is_empty());
+
+        Ok(())
+    }
 }
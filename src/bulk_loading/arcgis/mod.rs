@@ -1,28 +1,168 @@
+mod auth;
+mod catalog;
+mod checkpoint;
+mod domains;
+mod esri_json;
+mod filter;
 pub mod metadata;
+mod retry;
 pub mod scraping;
 
 use self::{
+    auth::ServiceAuth,
+    checkpoint::{default_checkpoint_directory, ScrapeCheckpoint},
+    domains::FieldMapper,
     metadata::{ArcGisRestMetadata, RestServiceFieldType, ServiceField},
-    scraping::fetch_query,
+    retry::RetryPolicy,
+    scraping::{fetch_query, RequestLimiter},
+};
+pub use self::{
+    auth::{set_secret_provider, ArcGisAuth, EnvironmentSecrets, SecretProvider},
+    catalog::{CatalogCrawl, CatalogError, CatalogField, CatalogLayer},
+    domains::CodedValueOutput,
+    filter::{FilterGeometry, QueryFilter, SpatialFilter, SpatialRelationship},
 };
 use super::load::csv_result_iter_to_string;
 use crate::bulk_loading::{
     analyze::Schema,
+    crs::{default_target_srid, Crs, GeometryProjector, DEFAULT_TARGET_SRID},
     error::BulkDataResult,
-    geo_json::feature_geometry_as_wkt,
-    load::{RecordSpoolChannel, RecordSpoolResult},
+    load::{CopyOptions, LoadWarnings, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
     utilities::send_error_message,
+    validation::{GeometryValidation, GeometryWriter},
 };
+use async_trait::async_trait;
 use chrono::{LocalResult, TimeZone, Utc};
+use futures::{stream, StreamExt};
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::collections::HashMap;
+use std::{collections::HashMap, net::IpAddr, path::PathBuf, sync::Arc};
+use tokio::net::lookup_host;
+use url::Host;
 
 #[derive(Deserialize, Serialize)]
 pub struct ArcGisDataOptions {
     url: Url,
+    #[serde(default = "default_target_srid")]
+    target_srid: u16,
+    #[serde(default)]
+    geometry_validation: GeometryValidation,
+    /// Number of query pages fetched at the same time. Records are still emitted in query order
+    #[serde(default = "default_concurrency")]
+    concurrency: usize,
+    /// Maximum number of requests sent to the service's host each second
+    #[serde(default)]
+    requests_per_second: Option<f64>,
+    #[serde(default)]
+    retry: RetryPolicy,
+    /// Credentials of a secured service
+    #[serde(default)]
+    auth: Option<ArcGisAuth>,
+    #[serde(default)]
+    coded_value_output: CodedValueOutput,
+    /// Report values outside of their field's range domain as warnings of the load
+    #[serde(default)]
+    validate_ranges: bool,
+    /// Fail the scrape when the number of records scraped differs from the count reported by the
+    /// service. Otherwise the difference is a warning of the load.
+    #[serde(default)]
+    strict_count: bool,
+    /// Where clause, spatial filter and out fields applied to every query of the scrape
+    #[serde(default)]
+    filter: QueryFilter,
+    /// Parent directory of scrape checkpoints. Only set by the server so source data options
+    /// cannot choose where checkpoints are written or removed.
+    #[serde(skip)]
+    checkpoint_directory: Option<PathBuf>,
+    /// Id of the scrape's checkpoint, usually the `sd_id` of the source data being loaded. Scrapes
+    /// without an id are not checkpointed.
+    #[serde(skip)]
+    checkpoint_id: Option<i64>,
+    /// Issues found by scrapes that do not fail the scrape, such as range domain violations
+    #[serde(skip)]
+    warnings: LoadWarnings,
+}
+
+fn default_concurrency() -> usize {
+    1
+}
+
+/// Environment variable listing the hosts, separated by commas, that ArcGIS requests can be sent
+/// to. When the variable is not set, requests are allowed to any host that only resolves to
+/// public addresses.
+const ALLOWED_HOSTS_VARIABLE: &str = "GEOFLOW_ARCGIS_ALLOWED_HOSTS";
+
+fn is_allowed_host(url: &Url, allowed_hosts: &str) -> bool {
+    let Some(host) = url.host_str() else {
+        return false
+    };
+    allowed_hosts
+        .split(',')
+        .any(|allowed| allowed.trim().eq_ignore_ascii_case(host))
+}
+
+/// False for loopback, private, link-local, shared, unspecified and other non-routable addresses
+/// that would let source data options reach the server's own network
+fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // Shared address space (100.64.0.0/10) of carrier-grade NAT
+            let is_shared = first == 100 && (64..128).contains(&second);
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || is_shared
+                || first == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(mapped));
+            }
+            let first_segment = ip.segments()[0];
+            // Unique local (fc00::/7) and unicast link-local (fe80::/10) addresses
+            let is_unique_local = first_segment & 0xfe00 == 0xfc00;
+            let is_link_local = first_segment & 0xffc0 == 0xfe80;
+            !(ip.is_loopback() || ip.is_unspecified() || is_unique_local || is_link_local)
+        }
+    }
+}
+
+/// Check that the url's host is allowed by the server's [ALLOWED_HOSTS_VARIABLE]. Without an
+/// allow-list, every address the host resolves to must be public.
+async fn check_allowed_host(url: &Url) -> BulkDataResult<()> {
+    if let Ok(allowed_hosts) = std::env::var(ALLOWED_HOSTS_VARIABLE) {
+        if !is_allowed_host(url, &allowed_hosts) {
+            return Err(format!("Requests to the host of \"{}\" are not allowed", url).into());
+        }
+        return Ok(());
+    }
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            lookup_host((domain, port))
+                .await?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => return Err(format!("Url \"{}\" does not have a host", url).into()),
+    };
+    if addresses.is_empty() || !addresses.into_iter().all(is_public_address) {
+        return Err(format!(
+            "Requests to the host of \"{}\" are not allowed. Hosts with private, loopback or link-local addresses must be listed in {}",
+            url, ALLOWED_HOSTS_VARIABLE
+        )
+        .into());
+    }
+    Ok(())
 }
 
 impl DataOptions for ArcGisDataOptions {}
@@ -33,68 +173,304 @@ impl ArcGisDataOptions {
             Ok(url) => url,
             Err(error) => return Err(format!("Url parsing error. {}", error).into()),
         };
-        Ok(Self { url })
+        Ok(Self {
+            url,
+            target_srid: DEFAULT_TARGET_SRID,
+            geometry_validation: GeometryValidation::default(),
+            concurrency: default_concurrency(),
+            requests_per_second: None,
+            retry: RetryPolicy::default(),
+            auth: None,
+            coded_value_output: CodedValueOutput::default(),
+            validate_ranges: false,
+            strict_count: false,
+            filter: QueryFilter::default(),
+            checkpoint_directory: None,
+            checkpoint_id: None,
+            warnings: LoadWarnings::default(),
+        })
+    }
+
+    /// Fetch up to `concurrency` query pages at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Limit the requests sent to the service's host each second
+    pub fn with_requests_per_second(mut self, requests_per_second: f64) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    /// Retry failed query requests using the policy
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Authenticate requests to a secured service
+    pub fn with_auth(mut self, auth: ArcGisAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Write the descriptions of coded values as requested
+    pub fn with_coded_value_output(mut self, coded_value_output: CodedValueOutput) -> Self {
+        self.coded_value_output = coded_value_output;
+        self
+    }
+
+    /// Report values outside of their field's range domain as warnings of the load
+    pub fn with_range_validation(mut self) -> Self {
+        self.validate_ranges = true;
+        self
+    }
+
+    /// Fail the scrape when the number of records scraped differs from the service's count
+    pub fn with_strict_count(mut self) -> Self {
+        self.strict_count = true;
+        self
+    }
+
+    /// Only scrape the features and fields selected by the filter
+    pub fn with_filter(mut self, filter: QueryFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Persist the scrape's progress under the id so a failed scrape resumes from the last
+    /// completed query. Services without an OID field have no stable feature order so their
+    /// scrapes are not checkpointed.
+    pub fn with_checkpoint(mut self, id: i64) -> Self {
+        self.checkpoint_id = Some(id);
+        self
+    }
+
+    /// Store checkpoints within the directory instead of the server's checkpoint directory
+    pub fn with_checkpoint_directory(mut self, directory: PathBuf) -> Self {
+        self.checkpoint_directory = Some(directory);
+        self
+    }
+
+    /// Directory of the scrape's checkpoint. None when the scrape is not checkpointed
+    fn checkpoint_path(&self) -> Option<PathBuf> {
+        let checkpoint_id = self.checkpoint_id?;
+        let directory = self
+            .checkpoint_directory
+            .clone()
+            .unwrap_or_else(default_checkpoint_directory);
+        Some(directory.join(checkpoint_id.to_string()))
+    }
+
+    fn checkpoint(
+        &self,
+        metadata: &ArcGisRestMetadata,
+    ) -> BulkDataResult<Option<ScrapeCheckpoint>> {
+        let Some(directory) = self.checkpoint_path() else {
+            return Ok(None)
+        };
+        if !metadata.supports_resume() {
+            return Ok(None);
+        }
+        let options = serde_json::to_value(self)?;
+        let checkpoint = ScrapeCheckpoint::open(directory, options, metadata.source_count())?;
+        Ok(Some(checkpoint))
+    }
+
+    fn service_auth(&self) -> BulkDataResult<Option<ServiceAuth>> {
+        self.auth
+            .as_ref()
+            .map(|auth| auth.service_auth(&self.url))
+            .transpose()
+    }
+
+    fn request_limiter(&self) -> BulkDataResult<Option<Arc<RequestLimiter>>> {
+        let Some(requests_per_second) = self.requests_per_second else {
+            return Ok(None)
+        };
+        let Some(host) = self.url.host_str() else {
+            return Err(format!("Url \"{}\" does not have a host", self.url).into())
+        };
+        Ok(Some(RequestLimiter::for_host(host, requests_per_second)?))
     }
 
-    async fn metadata(&self) -> BulkDataResult<ArcGisRestMetadata> {
-        ArcGisRestMetadata::from_url(&self.url).await
+    async fn metadata(
+        &self,
+        limiter: Option<&RequestLimiter>,
+        auth: Option<&ServiceAuth>,
+    ) -> BulkDataResult<ArcGisRestMetadata> {
+        check_allowed_host(&self.url).await?;
+        ArcGisRestMetadata::from_url(
+            &self.url,
+            self.target_srid,
+            &self.filter,
+            limiter,
+            &self.retry,
+            auth,
+        )
+        .await
     }
 }
 
+/// Crawl the service catalog at the url for layers and tables that can be loaded
+pub async fn crawl_catalog(url: &str, auth: Option<&ArcGisAuth>) -> BulkDataResult<CatalogCrawl> {
+    let url = Url::parse(url)?;
+    check_allowed_host(&url).await?;
+    let service_auth = auth.map(|a| a.service_auth(&url)).transpose()?;
+    catalog::crawl_catalog(&url, service_auth.as_ref()).await
+}
+
 pub async fn schema(options: &ArcGisDataOptions) -> BulkDataResult<Schema> {
-    let metadata = options.metadata().await?;
-    metadata.try_into()
+    let auth = options.service_auth()?;
+    let limiter = options.request_limiter()?;
+    let metadata = options.metadata(limiter.as_deref(), auth.as_ref()).await?;
+    metadata.schema(options.geometry_validation, options.coded_value_output)
 }
 
 pub async fn spool_records(
     options: &ArcGisDataOptions,
     record_channel: &mut RecordSpoolChannel,
 ) -> RecordSpoolResult {
-    let metadata = match options.metadata().await {
+    let auth = match options.service_auth() {
+        Ok(a) => a,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    // Count, object id and metadata requests share the scrape's rate limit with the queries
+    let limiter = match options.request_limiter() {
+        Ok(l) => l,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    let metadata = match options.metadata(limiter.as_deref(), auth.as_ref()).await {
         Ok(m) => m,
         Err(error) => return send_error_message(record_channel, error).await,
     };
     let query_format = metadata.query_format();
-    let fields: HashMap<String, &ServiceField> = metadata
+    let fields: HashMap<String, FieldMapper> = metadata
         .fields()
-        .map(|f| (f.name().to_owned(), f))
+        .map(|f| {
+            let mapper = FieldMapper::new(f, options.coded_value_output, options.validate_ranges);
+            (f.name().to_owned(), mapper)
+        })
         .collect();
     let queries = match metadata.queries() {
         Ok(q) => q,
         Err(error) => return send_error_message(record_channel, error).await,
     };
-    let client = reqwest::Client::new();
-    for query in queries {
-        let query = match query {
-            Ok(q) => q,
+    // Services project geometries into the requested outSR so no reprojection is required
+    let service_crs = Crs::Epsg(options.target_srid);
+    let projector = match GeometryProjector::new(Some(&service_crs), options.target_srid) {
+        Ok(p) => p,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    let geometry_writer = GeometryWriter::new(projector, options.geometry_validation);
+    if options.concurrency == 0 {
+        return send_error_message(record_channel, "Concurrency must be at least 1").await;
+    }
+    let mut checkpoint = match options.checkpoint(&metadata) {
+        Ok(c) => c,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    let queries = match &checkpoint {
+        Some(c) => queries.resume(c.completed_queries(), c.last_object_id()),
+        None => queries,
+    };
+    let mut record_count = 0;
+    if let Some(checkpoint) = &checkpoint {
+        // Records of queries completed by a previous scrape are replayed before fetching the rest
+        let spooled_records = match checkpoint.spooled_records() {
+            Ok(r) => r,
             Err(error) => return send_error_message(record_channel, error).await,
         };
-        let feature_collection = match fetch_query(&client, &query, query_format).await {
-            Ok(c) => c,
+        for record in spooled_records {
+            let record = match record {
+                Ok(r) => r,
+                Err(error) => return send_error_message(record_channel, error).await,
+            };
+            record_count += 1;
+            let result = record_channel.send(Ok(record)).await;
+            if let Err(error) = result {
+                return Some(error);
+            }
+        }
+    }
+    let client = match reqwest::Client::builder()
+        .timeout(options.retry.timeout())
+        .build()
+    {
+        Ok(c) => c,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    // Pages are fetched concurrently but buffered so features are sent in query order
+    let mut pages = stream::iter(queries)
+        .map(|query| {
+            let client = &client;
+            let limiter = limiter.as_deref();
+            let auth = auth.as_ref();
+            async move {
+                let query = query?;
+                let page = fetch_query(
+                    client,
+                    query.url(),
+                    query_format,
+                    limiter,
+                    &options.retry,
+                    auth,
+                )
+                .await?;
+                let features = page.into_features(query.page_size())?;
+                BulkDataResult::Ok((features, query.last_object_id()))
+            }
+        })
+        .buffered(options.concurrency);
+    while let Some(page) = pages.next().await {
+        let (features, last_object_id) = match page {
+            Ok(p) => p,
             Err(error) => return send_error_message(record_channel, error).await,
         };
-        for feature in feature_collection {
-            let geom = match feature_geometry_as_wkt(&feature) {
+        let mut records = Vec::with_capacity(features.len());
+        for feature in features {
+            let (attributes, geometry) = feature.into_parts();
+            let geom = match geometry_writer.csv_values(geometry) {
                 Ok(g) => g,
                 Err(error) => return send_error_message(record_channel, error).await,
             };
-            let csv_row = match feature.properties {
-                Some(properies) => {
-                    let csv_iter = feature_properties_to_iter(&properies, &fields)
-                        .chain(std::iter::once(Ok(geom)));
-                    match csv_result_iter_to_string(csv_iter) {
-                        Ok(row) => row,
-                        Err(error) => return send_error_message(record_channel, error).await,
-                    }
-                }
-                None => String::new(),
-            };
-            let result = record_channel.send(Ok(csv_row)).await;
+            let csv_iter = feature_properties_to_iter(&attributes, &fields)
+                .chain(geom.into_iter().map(Ok));
+            match csv_result_iter_to_string(csv_iter) {
+                Ok(row) => records.push(row),
+                Err(error) => return send_error_message(record_channel, error).await,
+            }
+        }
+        if let Some(checkpoint) = checkpoint.as_mut() {
+            if let Err(error) = checkpoint.complete_query(&records, last_object_id) {
+                return send_error_message(record_channel, error).await;
+            }
+        }
+        record_count += records.len();
+        for record in records {
+            let result = record_channel.send(Ok(record)).await;
             if let Err(error) = result {
                 return Some(error);
             }
         }
     }
+    for violations in fields.values().filter_map(|f| f.range_violations()) {
+        options.warnings.push(violations);
+    }
+    // Queries that silently skip features leave fewer records than the service reported. Services
+    // also add or remove features during long scrapes so the difference is a warning by default.
+    if record_count != metadata.source_count() as usize {
+        let message = format!(
+            "Scraped {} records but the service reported {} records",
+            record_count,
+            metadata.source_count()
+        );
+        if options.strict_count {
+            return send_error_message(record_channel, message).await;
+        }
+        options.warnings.push(message);
+    }
     None
 }
 
@@ -124,16 +500,44 @@ fn map_arcgis_value(value: &Value, field: &ServiceField) -> BulkDataResult<Strin
 
 fn feature_properties_to_iter<'m, 'f: 'm>(
     properties: &'m Map<String, Value>,
-    fields: &'f HashMap<String, &'f ServiceField>,
+    fields: &'m HashMap<String, FieldMapper<'f>>,
 ) -> impl Iterator<Item = BulkDataResult<String>> + 'm {
-    properties
-        .into_iter()
-        .map(|(key, value)| {
-            let Some(field) = fields.get(key.as_str()) else {
-                return Err(format!("Could not find a key found in a feature's properties: \"{}\"", key).into())
-            };
-            map_arcgis_value(value, field)
-        })
+    properties.into_iter().flat_map(|(key, value)| {
+        let Some(field) = fields.get(key.as_str()) else {
+            return vec![Err(format!("Could not find a key found in a feature's properties: \"{}\"", key).into())]
+        };
+        field.csv_values(value)
+    })
+}
+
+#[async_trait]
+impl SourceLoader for ArcGisDataOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self).await
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+
+    fn enable_checkpoint(&mut self, id: i64) {
+        self.checkpoint_id = Some(id);
+    }
+
+    fn complete_checkpoint(&self) -> BulkDataResult<()> {
+        match self.checkpoint_path() {
+            Some(directory) => ScrapeCheckpoint::remove(&directory),
+            None => Ok(()),
+        }
+    }
+
+    fn take_warnings(&self) -> Vec<String> {
+        self.warnings.take()
+    }
 }
 
 #[cfg(test)]
@@ -142,10 +546,49 @@ mod tests {
 
     use crate::bulk_loading::{arcgis::metadata::ServiceField, error::BulkDataResult};
 
-    use super::{map_arcgis_value, metadata::RestServiceFieldType};
+    use super::{
+        is_allowed_host, is_public_address, map_arcgis_value, metadata::RestServiceFieldType,
+    };
+    use reqwest::Url;
+    use std::net::IpAddr;
 
     static FIELD_NAME: &str = "test";
 
+    #[test]
+    fn is_allowed_host_should_match_listed_hosts() -> BulkDataResult<()> {
+        let url = Url::parse("https://services.example.com/arcgis/rest/services")?;
+        let allowed_hosts = "gis.example.org, SERVICES.example.com";
+
+        assert!(is_allowed_host(&url, allowed_hosts));
+        assert!(!is_allowed_host(&url, "gis.example.org"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn is_public_address_should_reject_internal_addresses() {
+        let internal = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ];
+        for address in internal {
+            let ip: IpAddr = address.parse().unwrap();
+            assert!(!is_public_address(ip), "address = {}", address);
+        }
+        assert!(is_public_address("93.184.216.34".parse().unwrap()));
+        assert!(is_public_address("2606:2800:220:1::1".parse().unwrap()));
+    }
+
     #[test]
     fn map_arcgis_value_should_fail_when_type_is_blob() {
         let typ = RestServiceFieldType::Blob;
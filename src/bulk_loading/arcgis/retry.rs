@@ -0,0 +1,217 @@
+use crate::bulk_loading::error::BulkDataResult;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// Status codes (HTTP or Esri error payload codes) that can succeed when the request is retried
+const TRANSIENT_CODES: [i64; 6] = [408, 429, 500, 502, 503, 504];
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_timeout_seconds() -> u64 {
+    60
+}
+
+/// Retry behaviour of ArcGIS service query requests. Failed attempts are retried with an
+/// exponential backoff and jitter unless the service requests a delay using `Retry-After`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    #[serde(default = "default_max_attempts")]
+    max_attempts: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    max_backoff_ms: u64,
+    #[serde(default = "default_timeout_seconds")]
+    timeout_seconds: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            timeout_seconds: default_timeout_seconds(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(
+        max_attempts: u32,
+        initial_backoff_ms: u64,
+        max_backoff_ms: u64,
+        timeout_seconds: u64,
+    ) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff_ms,
+            max_backoff_ms,
+            timeout_seconds,
+        }
+    }
+
+    pub fn validate(&self) -> BulkDataResult<()> {
+        if self.max_attempts == 0 {
+            return Err("Retry policy max_attempts must be at least 1".into());
+        }
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(format!(
+                "Retry policy initial_backoff_ms ({}) cannot exceed max_backoff_ms ({})",
+                self.initial_backoff_ms, self.max_backoff_ms
+            )
+            .into());
+        }
+        if self.timeout_seconds == 0 {
+            return Err("Retry policy timeout_seconds must be at least 1".into());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Timeout of a single request attempt
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_seconds)
+    }
+
+    /// Upper bound of the backoff after the failed attempt (starting at 1)
+    fn backoff_ceiling(&self, attempt: u32) -> Duration {
+        let factor = 2_u64.saturating_pow(attempt.saturating_sub(1));
+        let millis = self
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms);
+        Duration::from_millis(millis)
+    }
+
+    /// Delay before retrying the failed attempt (starting at 1). The service's requested delay is
+    /// used when available, up to the max backoff, otherwise a random delay between half and all
+    /// of the backoff ceiling.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(Duration::from_millis(self.max_backoff_ms));
+        }
+        let ceiling = self.backoff_ceiling(attempt);
+        ceiling / 2 + ceiling.mul_f64(jitter() / 2.0)
+    }
+}
+
+/// Random value in [0, 1]. Each [RandomState] is seeded differently so no RNG crate is needed
+fn jitter() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    hasher.finish() as f64 / u64::MAX as f64
+}
+
+/// True if a response with the status or Esri error code can succeed when retried
+pub fn is_transient_code(code: i64) -> bool {
+    TRANSIENT_CODES.contains(&code)
+}
+
+/// True if the request failed in a way that can succeed when retried (timeouts, failed
+/// connections and interrupted bodies)
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+}
+
+/// Delay requested by the `Retry-After` header, as either a number of seconds or an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&Utc) - Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_transient_code, retry_after, RetryPolicy};
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use std::time::Duration;
+
+    #[test]
+    fn delay_should_stay_within_backoff_ceiling_when_no_retry_after() {
+        let policy = RetryPolicy::new(5, 100, 300, 60);
+
+        for (attempt, ceiling) in [(1, 100), (2, 200), (3, 300), (4, 300)] {
+            let delay = policy.delay(attempt, None);
+            assert!(delay >= Duration::from_millis(ceiling / 2));
+            assert!(delay <= Duration::from_millis(ceiling));
+        }
+    }
+
+    #[test]
+    fn delay_should_use_retry_after_when_present() {
+        let policy = RetryPolicy::default();
+
+        let delay = policy.delay(1, Some(Duration::from_secs(7)));
+
+        assert_eq!(Duration::from_secs(7), delay);
+    }
+
+    #[test]
+    fn delay_should_cap_retry_after_at_max_backoff() {
+        let policy = RetryPolicy::new(5, 100, 300, 60);
+
+        let delay = policy.delay(1, Some(Duration::from_secs(3600)));
+
+        assert_eq!(Duration::from_millis(300), delay);
+    }
+
+    #[test]
+    fn retry_after_should_parse_seconds_when_header_is_number() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+
+        assert_eq!(Some(Duration::from_secs(12)), retry_after(&headers));
+    }
+
+    #[test]
+    fn retry_after_should_return_zero_when_date_has_passed() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+
+        assert_eq!(Some(Duration::ZERO), retry_after(&headers));
+    }
+
+    #[test]
+    fn is_transient_code_should_return_false_when_client_error() {
+        assert!(is_transient_code(429));
+        assert!(is_transient_code(503));
+        assert!(!is_transient_code(400));
+        assert!(!is_transient_code(498));
+    }
+
+    #[test]
+    fn validate_should_fail_when_max_attempts_is_zero() {
+        let policy = RetryPolicy::new(0, 100, 300, 60);
+
+        assert!(policy.validate().is_err());
+    }
+}
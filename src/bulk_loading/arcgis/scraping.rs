@@ -1,10 +1,114 @@
-use crate::bulk_loading::error::{BulkDataError, BulkDataResult};
-use geojson::{feature::Id, Feature, FeatureCollection, GeoJson, Geometry, Value as GeomValue};
-use reqwest::Client;
-use serde::Deserialize;
+use super::{
+    auth::{get_request, ServiceAuth},
+    esri_json::EsriGeometry,
+    retry::{is_transient_code, is_transient_error, retry_after, RetryPolicy},
+};
+use crate::bulk_loading::{
+    error::{BulkDataError, BulkDataResult},
+    geo_json::feature_geometry,
+    geometry::Geometry,
+};
+use geojson::GeoJson;
+use lazy_static::lazy_static;
+use reqwest::{Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, sleep_until, Instant},
+};
 
-static MAX_RETRY: i32 = 5;
+/// Esri error codes for invalid or expired tokens (498) and missing tokens (499)
+const INVALID_TOKEN_CODES: [i64; 2] = [498, 499];
+
+lazy_static! {
+    static ref HOST_SCHEDULES: StdMutex<HashMap<String, Arc<HostSchedule>>> =
+        StdMutex::new(HashMap::new());
+}
+
+/// Request schedule of a host, shared by every scrape of the host running in this process
+struct HostSchedule {
+    next_request: Mutex<Instant>,
+    /// Intervals of the scrapes currently limiting requests to the host
+    intervals: StdMutex<Vec<Duration>>,
+}
+
+/// Politeness limit on the rate of requests sent to a host by a single scrape. Limiters of the
+/// same host share a schedule so concurrent loads do not exceed the limit together, using the
+/// lowest rate of the active scrapes. The scrape's rate stops applying once its limiter is
+/// dropped.
+pub struct RequestLimiter {
+    schedule: Arc<HostSchedule>,
+    interval: Duration,
+}
+
+impl RequestLimiter {
+    /// Limiter for the host allowing at most `requests_per_second`
+    pub fn for_host(host: &str, requests_per_second: f64) -> BulkDataResult<Arc<Self>> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            return Err(format!(
+                "Requests per second must be a positive number. Found {}",
+                requests_per_second
+            )
+            .into());
+        }
+        let interval = Duration::from_secs_f64(1.0 / requests_per_second);
+        let Ok(mut schedules) = HOST_SCHEDULES.lock() else {
+            return Err("Host request schedules lock was poisoned".into())
+        };
+        let schedule = schedules.entry(host.to_owned()).or_insert_with(|| {
+            Arc::new(HostSchedule {
+                next_request: Mutex::new(Instant::now()),
+                intervals: StdMutex::new(Vec::new()),
+            })
+        });
+        let Ok(mut intervals) = schedule.intervals.lock() else {
+            return Err("Host request intervals lock was poisoned".into())
+        };
+        intervals.push(interval);
+        drop(intervals);
+        Ok(Arc::new(Self {
+            schedule: schedule.clone(),
+            interval,
+        }))
+    }
+
+    /// Longest interval of the active scrapes of the host
+    fn interval(&self) -> Duration {
+        match self.schedule.intervals.lock() {
+            Ok(intervals) => intervals.iter().max().copied().unwrap_or(self.interval),
+            Err(_) => self.interval,
+        }
+    }
+
+    /// Wait until the next request to the host is allowed
+    pub async fn wait(&self) {
+        let interval = self.interval();
+        let request_time = {
+            let mut next_request = self.schedule.next_request.lock().await;
+            let request_time = next_request.max(Instant::now());
+            *next_request = request_time + interval;
+            request_time
+        };
+        sleep_until(request_time).await;
+    }
+}
+
+impl Drop for RequestLimiter {
+    fn drop(&mut self) {
+        if let Ok(mut intervals) = self.schedule.intervals.lock() {
+            if let Some(index) = intervals.iter().position(|i| *i == self.interval) {
+                intervals.swap_remove(index);
+            }
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum QueryFormat {
@@ -22,29 +126,130 @@ impl QueryFormat {
         }
     }
 
-    async fn try_query(&self, client: &Client, query: &str) -> BulkDataResult<FeatureCollection> {
-        let response = client.get(query).send().await?;
-        if response.status() != 200 {
-            return Err((query, response.status()).into());
-        }
-        let feature_collection = match self {
-            Self::GeoJSON => match response.json::<GeoJson>().await? {
-                GeoJson::FeatureCollection(collection) => collection,
+    async fn try_query(
+        &self,
+        client: &Client,
+        query: &str,
+        auth: Option<&ServiceAuth>,
+    ) -> Result<QueryPage, QueryAttemptError> {
+        let body = try_request(client, query, auth).await?;
+        // GeoJSON responses hold the flag within the collection's properties
+        let exceeded_transfer_limit = body
+            .get("exceededTransferLimit")
+            .or_else(|| body.pointer("/properties/exceededTransferLimit"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let features = match self {
+            Self::GeoJSON => match GeoJson::from_json_value(body).map_err(BulkDataError::from)? {
+                GeoJson::FeatureCollection(collection) => collection
+                    .into_iter()
+                    .map(|feature| -> BulkDataResult<QueryFeature> {
+                        Ok(QueryFeature {
+                            geometry: feature_geometry(&feature)?,
+                            attributes: feature.properties.unwrap_or_default(),
+                        })
+                    })
+                    .collect::<BulkDataResult<_>>()?,
                 GeoJson::Geometry(_) => {
-                    return Err("Expected a Feature collect but got Geometry".into())
+                    return Err(
+                        BulkDataError::from("Expected a Feature collect but got Geometry").into(),
+                    )
                 }
                 GeoJson::Feature(_) => {
-                    return Err("Expected a Feature collect but got Feature".into())
+                    return Err(
+                        BulkDataError::from("Expected a Feature collect but got Feature").into(),
+                    )
                 }
             },
-            Self::Json => response.json::<JsonQueryResponse>().await?.into(),
+            Self::Json => serde_json::from_value::<JsonQueryResponse>(body)
+                .map_err(BulkDataError::from)?
+                .into_features()?,
             Self::NotSupported(name) => {
-                return Err(
-                    format!("Cannot read the query response for format \"{}\"", name).into(),
-                )
+                return Err(BulkDataError::from(format!(
+                    "Cannot read the query response for format \"{}\"",
+                    name
+                ))
+                .into())
             }
         };
-        Ok(feature_collection)
+        Ok(QueryPage {
+            features,
+            exceeded_transfer_limit,
+        })
+    }
+}
+
+/// Send a single GET request for a JSON body, classifying failures by whether retrying the
+/// request can succeed
+async fn try_request(
+    client: &Client,
+    url: &str,
+    auth: Option<&ServiceAuth>,
+) -> Result<Value, QueryAttemptError> {
+    let response = get_request(client, url, auth).await?.send().await?;
+    let status = response.status();
+    if status != StatusCode::OK {
+        let error = BulkDataError::from((url, status));
+        if is_transient_code(status.as_u16() as i64) {
+            let delay = retry_after(response.headers());
+            return Err(QueryAttemptError::Transient(error, delay));
+        }
+        return Err(QueryAttemptError::Fatal(error));
+    }
+    let body: Value = response.json().await?;
+    // Services can respond with a 200 status and an error object as the body
+    if let Some(error) = body.get("error") {
+        let error: EsriError =
+            serde_json::from_value(error.clone()).map_err(BulkDataError::from)?;
+        return Err(error.into_attempt_error(url));
+    }
+    Ok(body)
+}
+
+/// Failed query attempt, classified by whether retrying the query can succeed
+enum QueryAttemptError {
+    /// Failure that can succeed when retried, with the delay requested by the service
+    Transient(BulkDataError, Option<Duration>),
+    /// Service rejected the token sent with the request
+    InvalidToken(BulkDataError),
+    Fatal(BulkDataError),
+}
+
+impl From<BulkDataError> for QueryAttemptError {
+    fn from(error: BulkDataError) -> Self {
+        Self::Fatal(error)
+    }
+}
+
+impl From<reqwest::Error> for QueryAttemptError {
+    fn from(error: reqwest::Error) -> Self {
+        if is_transient_error(&error) {
+            Self::Transient(error.into(), None)
+        } else {
+            Self::Fatal(error.into())
+        }
+    }
+}
+
+/// Error object of an Esri JSON response body
+#[derive(Deserialize)]
+struct EsriError {
+    code: i64,
+    #[serde(default)]
+    message: String,
+}
+
+impl EsriError {
+    fn into_attempt_error(self, query: &str) -> QueryAttemptError {
+        let code = self.code;
+        let error = BulkDataError::ArcGisService(query.to_owned(), code, self.message);
+        if is_transient_code(code) {
+            QueryAttemptError::Transient(error, None)
+        } else if INVALID_TOKEN_CODES.contains(&code) {
+            QueryAttemptError::InvalidToken(error)
+        } else {
+            QueryAttemptError::Fatal(error)
+        }
     }
 }
 
@@ -65,91 +270,187 @@ impl From<&str> for QueryFormat {
     }
 }
 
-#[derive(Deserialize)]
-enum JsonQueryGeometry {
-    Point { x: f64, y: f64 },
+/// Features of a single page query
+pub struct QueryPage {
+    features: Vec<QueryFeature>,
+    /// Set by the service when more features match the query than were returned
+    exceeded_transfer_limit: bool,
 }
 
-impl From<JsonQueryGeometry> for Geometry {
-    fn from(geom: JsonQueryGeometry) -> Self {
-        match geom {
-            JsonQueryGeometry::Point { x, y } => Self {
-                bbox: None,
-                value: GeomValue::Point(vec![x, y]),
-                foreign_members: None,
-            },
+impl QueryPage {
+    /// Features of a query that requested `page_size` features. Fails when the service's transfer
+    /// limit cut the page short since the remaining features would not be requested again.
+    pub fn into_features(self, page_size: usize) -> BulkDataResult<Vec<QueryFeature>> {
+        if self.exceeded_transfer_limit && self.features.len() < page_size {
+            return Err(format!(
+                "Service returned {} of {} requested features because its transfer limit was exceeded. Lower the service's page size or max record count",
+                self.features.len(),
+                page_size
+            )
+            .into());
         }
+        Ok(self.features)
+    }
+}
+
+/// Feature returned from a service query
+pub struct QueryFeature {
+    attributes: Map<String, Value>,
+    geometry: Option<Geometry>,
+}
+
+impl QueryFeature {
+    #[inline]
+    pub fn into_parts(self) -> (Map<String, Value>, Option<Geometry>) {
+        (self.attributes, self.geometry)
     }
 }
 
 #[derive(Deserialize)]
 struct JsonQueryFeature {
     attributes: Map<String, Value>,
-    geometry: Option<JsonQueryGeometry>,
+    geometry: Option<EsriGeometry>,
 }
 
 #[derive(Deserialize)]
 struct JsonQueryResponse {
     features: Vec<JsonQueryFeature>,
+    #[serde(default, rename = "hasZ")]
+    has_z: bool,
+    #[serde(default, rename = "hasM")]
+    has_m: bool,
 }
 
-impl From<JsonQueryResponse> for FeatureCollection {
-    fn from(response: JsonQueryResponse) -> Self {
-        Self {
-            bbox: None,
-            features: response
-                .features
-                .into_iter()
-                .enumerate()
-                .map(|(i, feature)| Feature {
-                    bbox: None,
-                    geometry: feature.geometry.map(|g| g.into()),
-                    id: Some(Id::Number(i.into())),
-                    properties: Some(feature.attributes),
-                    foreign_members: None,
+impl JsonQueryResponse {
+    fn into_features(self) -> BulkDataResult<Vec<QueryFeature>> {
+        let (has_z, has_m) = (self.has_z, self.has_m);
+        self.features
+            .into_iter()
+            .map(|feature| -> BulkDataResult<QueryFeature> {
+                let geometry = match feature.geometry {
+                    Some(geometry) => geometry.into_geometry(has_z, has_m)?,
+                    None => None,
+                };
+                Ok(QueryFeature {
+                    attributes: feature.attributes,
+                    geometry,
                 })
-                .collect(),
-            foreign_members: None,
-        }
+            })
+            .collect()
     }
 }
 
-async fn loop_until_successful(
-    client: &Client,
-    query: &str,
-    query_format: &QueryFormat,
-) -> BulkDataResult<FeatureCollection> {
-    let mut attempts = 0;
-    let result = loop {
-        attempts += 1;
-        if attempts > MAX_RETRY {
-            return Err(
-                format!("Exceeded max number of retries for a query ({})", MAX_RETRY).into(),
-            );
+/// Run request attempts until one succeeds, the failure is fatal or the policy's attempts are used
+async fn loop_until_successful<T, F, Fut>(
+    limiter: Option<&RequestLimiter>,
+    retry_policy: &RetryPolicy,
+    auth: Option<&ServiceAuth>,
+    mut try_attempt: F,
+) -> BulkDataResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, QueryAttemptError>>,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        if let Some(limiter) = limiter {
+            limiter.wait().await;
         }
-        match query_format.try_query(client, query).await {
-            Err(error) => match error {
-                BulkDataError::ArcGis(_, _) => continue,
+        let (error, delay) = match try_attempt().await {
+            Ok(value) => return Ok(value),
+            Err(QueryAttemptError::Fatal(error)) => return Err(error),
+            Err(QueryAttemptError::Transient(error, delay)) => (error, delay),
+            Err(QueryAttemptError::InvalidToken(error)) => match auth {
+                // Generated tokens can be revoked before they expire so a new token is requested
+                Some(auth) if auth.can_refresh() => {
+                    auth.invalidate().await;
+                    (error, Some(Duration::ZERO))
+                }
                 _ => return Err(error),
             },
-            Ok(obj) => break obj,
+        };
+        if attempt >= retry_policy.max_attempts() {
+            return Err(format!(
+                "Exceeded max number of attempts for a query ({}). Last error: {}",
+                retry_policy.max_attempts(),
+                error
+            )
+            .into());
         }
-    };
-    Ok(result)
+        sleep(retry_policy.delay(attempt, delay)).await;
+    }
 }
 
 pub async fn fetch_query(
     client: &Client,
     query: &str,
     query_format: &QueryFormat,
-) -> BulkDataResult<FeatureCollection> {
-    let feature_collection = loop_until_successful(client, query, query_format).await?;
-    Ok(feature_collection)
+    limiter: Option<&RequestLimiter>,
+    retry_policy: &RetryPolicy,
+    auth: Option<&ServiceAuth>,
+) -> BulkDataResult<QueryPage> {
+    loop_until_successful(limiter, retry_policy, auth, move || {
+        query_format.try_query(client, query, auth)
+    })
+    .await
+}
+
+/// Fetch and deserialize a JSON response, such as service metadata, with the same rate limit,
+/// retries and Esri error detection as feature queries
+pub async fn fetch_json<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    limiter: Option<&RequestLimiter>,
+    retry_policy: &RetryPolicy,
+    auth: Option<&ServiceAuth>,
+) -> BulkDataResult<T> {
+    loop_until_successful(limiter, retry_policy, auth, move || async move {
+        let body = try_request(client, url, auth).await?;
+        serde_json::from_value(body).map_err(|error| QueryAttemptError::Fatal(error.into()))
+    })
+    .await
 }
 
 #[cfg(test)]
 mod tests {
-    use super::QueryFormat;
+    use super::{QueryFormat, RequestLimiter};
+    use crate::bulk_loading::error::BulkDataResult;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn request_limiter_should_space_requests_when_rate_is_set() -> BulkDataResult<()> {
+        let limiter = RequestLimiter::for_host("limiter.test", 20.0)?;
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+
+        assert!(start.elapsed().as_millis() >= 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn request_limiter_should_use_lowest_rate_of_active_limiters() -> BulkDataResult<()> {
+        let fast = RequestLimiter::for_host("shared.limiter.test", 20.0)?;
+        let slow = RequestLimiter::for_host("shared.limiter.test", 2.0)?;
+
+        assert_eq!(Duration::from_millis(500), fast.interval());
+
+        drop(slow);
+
+        assert_eq!(Duration::from_millis(50), fast.interval());
+
+        Ok(())
+    }
+
+    #[test]
+    fn request_limiter_should_fail_when_rate_is_not_positive() {
+        assert!(RequestLimiter::for_host("limiter.test", 0.0).is_err());
+    }
 
     #[test]
     fn query_format_as_str() {
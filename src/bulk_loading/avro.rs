@@ -1,34 +1,67 @@
 use super::{
-    analyze::{ColumnType, Schema},
+    analyze::{ColumnMetadata, ColumnType, NumericMetadata, Schema},
     error::BulkDataResult,
-    load::{csv_result_iter_to_string, RecordSpoolChannel, RecordSpoolResult},
+    load::{csv_result_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
 };
+use async_trait::async_trait;
 use avro_rs::{
     schema::{RecordField, Schema as AvroSchema, UnionSchema},
     types::Value,
     Duration, Reader,
 };
 use chrono::{LocalResult, NaiveTime, TimeZone, Utc};
+use num_bigint::BigInt;
+use num_traits::Signed;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
-use std::{collections::HashSet, fmt::Write};
+use std::fmt::Write;
 use std::{fs::File, io::BufReader, path::PathBuf};
 
 #[derive(Deserialize, Serialize)]
 pub struct AvroFileOptions {
     file_path: PathBuf,
+    /// Schema used to read the file's records. Allows files written with older versions of a
+    /// schema to be loaded as the current version, filling in defaults for missing fields.
+    #[serde(default)]
+    reader_schema: Option<JsonValue>,
+    /// Number of nested record levels expanded into their own columns. Expanded columns are
+    /// named after the path to the field, joined with underscores (e.g. `address_city`). Records
+    /// nested deeper than this are loaded as a single `Json` column.
+    #[serde(default)]
+    flatten_depth: usize,
 }
 
 impl AvroFileOptions {
     pub fn new(file_path: PathBuf) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            reader_schema: None,
+            flatten_depth: 0,
+        }
+    }
+
+    /// Parse the reader schema option. Accepts the schema as a JSON object or a JSON string
+    /// containing the schema definition.
+    fn reader_schema(&self) -> BulkDataResult<Option<AvroSchema>> {
+        Ok(match &self.reader_schema {
+            Some(JsonValue::String(schema)) => Some(AvroSchema::parse_str(schema)?),
+            Some(schema) => Some(AvroSchema::parse(schema)?),
+            None => None,
+        })
     }
 
-    fn reader(&self) -> BulkDataResult<Reader<BufReader<File>>> {
+    fn reader<'s>(
+        &self,
+        reader_schema: Option<&'s AvroSchema>,
+    ) -> BulkDataResult<Reader<'s, BufReader<File>>> {
         let file = File::open(&self.file_path)?;
         let buf_reader = BufReader::new(file);
-        let reader = Reader::new(buf_reader)?;
+        let reader = match reader_schema {
+            Some(schema) => Reader::with_schema(schema, buf_reader)?,
+            None => Reader::new(buf_reader)?,
+        };
         Ok(reader)
     }
 }
@@ -39,6 +72,32 @@ fn is_nullable_union_schema(schema: &UnionSchema) -> bool {
     schema.variants().len() <= 2 && schema.find_schema(&Value::Null).is_some()
 }
 
+/// Schema of a field's values, unwrapping nullable unions to the non-null variant
+fn field_value_schema(schema: &AvroSchema) -> &AvroSchema {
+    let AvroSchema::Union(union) = schema else {
+        return schema
+    };
+    if !is_nullable_union_schema(union) {
+        return schema;
+    }
+    union
+        .variants()
+        .iter()
+        .find(|v| *v != &AvroSchema::Null)
+        .unwrap_or(schema)
+}
+
+/// Schema of the records read from the file. Uses the reader schema when provided
+fn record_fields<'r>(reader: &'r Reader<'_, BufReader<File>>) -> Option<&'r [RecordField]> {
+    let schema = reader
+        .reader_schema()
+        .unwrap_or_else(|| reader.writer_schema());
+    match schema {
+        AvroSchema::Record { fields, .. } => Some(fields),
+        _ => None,
+    }
+}
+
 fn avro_schema_to_column_type(schema: &AvroSchema) -> BulkDataResult<ColumnType> {
     Ok(match &schema {
         AvroSchema::Null => ColumnType::Text,
@@ -55,58 +114,147 @@ fn avro_schema_to_column_type(schema: &AvroSchema) -> BulkDataResult<ColumnType>
         AvroSchema::Record { .. } => ColumnType::Json,
         AvroSchema::Enum { .. } => ColumnType::Text,
         AvroSchema::Fixed { .. } => ColumnType::SmallIntArray,
-        AvroSchema::Decimal { .. } => ColumnType::SmallIntArray,
+        AvroSchema::Decimal { .. } => ColumnType::Number,
         AvroSchema::Uuid => ColumnType::UUID,
         AvroSchema::Date => ColumnType::Date,
         AvroSchema::TimeMillis => ColumnType::Time,
         AvroSchema::TimeMicros => ColumnType::Time,
         AvroSchema::TimestampMillis => ColumnType::Timestamp,
         AvroSchema::TimestampMicros => ColumnType::Timestamp,
-        AvroSchema::Duration => ColumnType::Json,
+        AvroSchema::Duration => ColumnType::Interval,
     })
 }
 
 fn avro_field_to_column_type(field: &RecordField) -> BulkDataResult<ColumnType> {
     match &field.schema {
         AvroSchema::Array(_) => Ok(ColumnType::Json),
-        AvroSchema::Union(s) => {
-            if is_nullable_union_schema(s) {
-                let Some(schema) = s.variants().iter().find(|v| *v != &AvroSchema::Null) else {
-                    return Ok(ColumnType::Json)
-                };
-                avro_schema_to_column_type(schema)
-            } else {
-                Ok(ColumnType::Json)
+        AvroSchema::Union(s) if !is_nullable_union_schema(s) => Ok(ColumnType::Json),
+        AvroSchema::Null => Err(format!("Found a null schema for field \"{}\"", field.name).into()),
+        schema => avro_schema_to_column_type(field_value_schema(schema)),
+    }
+}
+
+fn avro_field_to_column(name: &str, field: &RecordField) -> BulkDataResult<ColumnMetadata> {
+    if let AvroSchema::Decimal {
+        precision, scale, ..
+    } = field_value_schema(&field.schema)
+    {
+        if let Some(metadata) = NumericMetadata::new(*precision, *scale) {
+            return ColumnMetadata::numeric(name, metadata);
+        }
+    }
+    ColumnMetadata::new(name, avro_field_to_column_type(field)?)
+}
+
+/// Fields of a nested record that should be expanded into columns, given the current nesting
+/// `depth`. [None] when the field is loaded as a single column.
+fn expanded_fields(field: &RecordField, depth: usize, max_depth: usize) -> Option<&[RecordField]> {
+    if depth >= max_depth {
+        return None;
+    }
+    match field_value_schema(&field.schema) {
+        AvroSchema::Record { fields, .. } => Some(fields),
+        _ => None,
+    }
+}
+
+/// Columns for the record's fields, expanding nested records up to `max_depth` levels
+fn flattened_columns(
+    fields: &[RecordField],
+    prefix: &str,
+    depth: usize,
+    max_depth: usize,
+    columns: &mut Vec<ColumnMetadata>,
+) -> BulkDataResult<()> {
+    for field in fields {
+        let name = format!("{}{}", prefix, field.name);
+        match expanded_fields(field, depth, max_depth) {
+            Some(sub_fields) => {
+                let prefix = format!("{}_", name);
+                flattened_columns(sub_fields, &prefix, depth + 1, max_depth, columns)?;
+            }
+            None => columns.push(avro_field_to_column(&name, field)?),
+        }
+    }
+    Ok(())
+}
+
+/// Number of columns produced by the record's fields after expanding nested records
+fn flattened_column_count(fields: &[RecordField], depth: usize, max_depth: usize) -> usize {
+    fields
+        .iter()
+        .map(|field| match expanded_fields(field, depth, max_depth) {
+            Some(sub_fields) => flattened_column_count(sub_fields, depth + 1, max_depth),
+            None => 1,
+        })
+        .sum()
+}
+
+/// Map the record's values to CSV values, matching the columns of [flattened_columns]. A null
+/// nested record produces a null value for each of its expanded columns.
+fn flattened_values(
+    values: Vec<(String, Value)>,
+    fields: &[RecordField],
+    depth: usize,
+    max_depth: usize,
+    csv_values: &mut Vec<BulkDataResult<String>>,
+) -> BulkDataResult<()> {
+    for ((_, value), field) in values.into_iter().zip(fields) {
+        let Some(sub_fields) = expanded_fields(field, depth, max_depth) else {
+            csv_values.push(map_avro_field_value(value, &field.schema));
+            continue;
+        };
+        let value = match value {
+            Value::Union(inner) => *inner,
+            value => value,
+        };
+        match value {
+            Value::Record(sub_values) => {
+                flattened_values(sub_values, sub_fields, depth + 1, max_depth, csv_values)?
+            }
+            Value::Null => {
+                let count = flattened_column_count(sub_fields, depth + 1, max_depth);
+                csv_values.extend((0..count).map(|_| Ok(String::new())));
+            }
+            value => {
+                return Err(format!(
+                    "Expected a record value for field \"{}\". Found {:?}",
+                    field.name, value
+                )
+                .into())
             }
         }
-        AvroSchema::Null => Err(format!("Found a null schema for field \"{}\"", field.name).into()),
-        _ => avro_schema_to_column_type(&field.schema),
     }
+    Ok(())
 }
 
 pub fn schema(options: &AvroFileOptions) -> BulkDataResult<Schema> {
     let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
         return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
     };
-    let reader = options.reader()?;
-    let AvroSchema::Record { fields, .. } = reader.writer_schema() else {
+    let reader_schema = options.reader_schema()?;
+    let reader = options.reader(reader_schema.as_ref())?;
+    let Some(fields) = record_fields(&reader) else {
         return Err(format!("File schema for \"{:?}\" is not a record. Found {:?}", &options.file_path, reader.writer_schema()).into())
     };
-    let columns = fields
-        .iter()
-        .map(|f| -> BulkDataResult<_> { Ok((&f.name, avro_field_to_column_type(f)?)) });
-    Schema::from_result_iter(table_name, columns)
+    let mut columns = Vec::new();
+    flattened_columns(fields, "", 0, options.flatten_depth, &mut columns)?;
+    Schema::new(table_name, columns)
 }
 
 pub async fn spool_records(
     options: &AvroFileOptions,
     record_channel: &mut RecordSpoolChannel,
 ) -> RecordSpoolResult {
-    let reader = match options.reader() {
+    let reader_schema = match options.reader_schema() {
+        Ok(schema) => schema,
+        Err(error) => return record_channel.send(Err(error)).await.err(),
+    };
+    let reader = match options.reader(reader_schema.as_ref()) {
         Ok(reader) => reader,
         Err(error) => return record_channel.send(Err(error)).await.err(),
     };
-    let AvroSchema::Record { fields, .. } = reader.writer_schema() else {
+    let Some(fields) = record_fields(&reader).map(|fields| fields.to_vec()) else {
         return record_channel.send(
             Err(
                 format!(
@@ -120,17 +268,6 @@ pub async fn spool_records(
         .await
         .err()
     };
-    let nullable_union_columns: HashSet<String> = fields
-        .iter()
-        .filter_map(|f| {
-            if let AvroSchema::Union(schema) = &f.schema {
-                if is_nullable_union_schema(schema) {
-                    return Some(f.name.to_owned());
-                }
-            }
-            None
-        })
-        .collect();
     for (i, record) in reader.enumerate() {
         let record = match record {
             Ok(Value::Record(fields)) => fields,
@@ -147,16 +284,14 @@ pub async fn spool_records(
             }
             Err(error) => return record_channel.send(Err(error.into())).await.err(),
         };
-        let csv_iter = record.into_iter().map(|(key, value)| {
-            if nullable_union_columns.contains(&key) {
-                if let Value::Union(union_box) = value {
-                    return map_avro_value(*union_box);
-                }
-            }
-            map_avro_value(value)
-        });
+        let mut csv_values = Vec::with_capacity(fields.len());
+        if let Err(error) =
+            flattened_values(record, &fields, 0, options.flatten_depth, &mut csv_values)
+        {
+            return record_channel.send(Err(error)).await.err();
+        }
         let result = record_channel
-            .send(csv_result_iter_to_string(csv_iter))
+            .send(csv_result_iter_to_string(csv_values.into_iter()))
             .await;
         if let Err(error) = result {
             return Some(error);
@@ -236,16 +371,40 @@ fn union_to_json_value(union: Value) -> BulkDataResult<String> {
 }
 
 #[inline]
-fn duration_to_json_value(duration: Duration) -> String {
+fn duration_to_interval(duration: Duration) -> String {
     let months = u32::from_le_bytes(*duration.months().as_ref());
     let days = u32::from_le_bytes(*duration.days().as_ref());
     let millis = u32::from_le_bytes(*duration.millis().as_ref());
-    json!({
-        "months": months,
-        "days": days,
-        "millis": millis,
-    })
-    .to_string()
+    format!("{} months {} days {} milliseconds", months, days, millis)
+}
+
+/// Format the big-endian two's-complement bytes of an Avro decimal as a numeric literal with
+/// `scale` digits after the decimal point
+fn decimal_literal(bytes: &[u8], scale: usize) -> String {
+    let unscaled = BigInt::from_signed_bytes_be(bytes);
+    let digits = unscaled.abs().to_string();
+    let sign = if unscaled.is_negative() { "-" } else { "" };
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, whole, fraction)
+}
+
+/// Map a record field's value using the field's schema. Required for logical types that need
+/// schema details to decode the value (e.g. the scale of a decimal).
+fn map_avro_field_value(value: Value, schema: &AvroSchema) -> BulkDataResult<String> {
+    match (value, field_value_schema(schema)) {
+        (Value::Union(inner), value_schema) if value_schema != schema => {
+            map_avro_field_value(*inner, value_schema)
+        }
+        (Value::Decimal(ref d), AvroSchema::Decimal { scale, .. }) => {
+            let bytes: Vec<u8> = d.try_into()?;
+            Ok(decimal_literal(&bytes, *scale))
+        }
+        (value, _) => map_avro_value(value),
+    }
 }
 
 fn map_avro_value(value: Value) -> BulkDataResult<String> {
@@ -269,19 +428,40 @@ fn map_avro_value(value: Value) -> BulkDataResult<String> {
             };
             format!("{}", dt.format("%Y-%m-%d"))
         }
-        Value::Decimal(ref d) => small_int_array_literal(d.try_into()?)?,
+        Value::Decimal(ref d) => {
+            let bytes: Vec<u8> = d.try_into()?;
+            decimal_literal(&bytes, 0)
+        }
         Value::TimeMillis(t) => convert_time_nano_secs_to_string(t as i64 * 1_000_000)?,
         Value::TimeMicros(t) => convert_time_nano_secs_to_string(t as i64 * 1_000)?,
         Value::TimestampMillis(t) => convert_timestamp_secs_to_string(t as i64 / 1_000)?,
         Value::TimestampMicros(t) => convert_timestamp_secs_to_string(t as i64 / 1_000_000)?,
-        Value::Duration(d) => duration_to_json_value(d),
+        Value::Duration(d) => duration_to_interval(d),
         Value::Uuid(u) => u.to_string(),
     })
 }
 
+#[async_trait]
+impl SourceLoader for AvroFileOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::avro_field_to_column_type;
+    use super::{
+        avro_field_to_column, avro_field_to_column_type, flattened_columns, flattened_values,
+        map_avro_field_value,
+    };
     use crate::bulk_loading::{analyze::ColumnType, avro::map_avro_value, error::BulkDataResult};
     use avro_rs::{
         schema::{Name, RecordField, RecordFieldOrder},
@@ -289,7 +469,8 @@ mod tests {
         Days, Duration, Millis, Months, Schema as AvroSchema,
     };
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-    use polars::export::num::{BigInt, One};
+    use num_bigint::BigInt;
+    use num_traits::One;
     use serde_json::{json, Value as JsonValue};
     use std::collections::HashMap;
 
@@ -304,6 +485,23 @@ mod tests {
         }
     }
 
+    fn nested_record_fields() -> Vec<RecordField> {
+        let mut id = record_field_for_type(AvroSchema::Int);
+        id.name = String::from("id");
+        let mut city = record_field_for_type(AvroSchema::String);
+        city.name = String::from("city");
+        let mut zip = record_field_for_type(AvroSchema::Long);
+        zip.name = String::from("zip");
+        let mut address = record_field_for_type(AvroSchema::Record {
+            name: Name::new("Address"),
+            doc: None,
+            fields: vec![city, zip],
+            lookup: HashMap::new(),
+        });
+        address.name = String::from("address");
+        vec![id, address]
+    }
+
     #[test]
     fn avro_field_to_column_type_should_fail_when_fail_type() -> BulkDataResult<()> {
         let schema = AvroSchema::Null;
@@ -470,8 +668,7 @@ mod tests {
     }
 
     #[test]
-    fn avro_field_to_column_type_should_return_smallint_array_when_decimal_type(
-    ) -> BulkDataResult<()> {
+    fn avro_field_to_column_type_should_return_number_when_decimal_type() -> BulkDataResult<()> {
         let schema = AvroSchema::Decimal {
             precision: 0,
             scale: 0,
@@ -481,7 +678,25 @@ mod tests {
 
         let column_type = avro_field_to_column_type(&field)?;
 
-        assert_eq!(ColumnType::SmallIntArray, column_type);
+        assert_eq!(ColumnType::Number, column_type);
+
+        Ok(())
+    }
+
+    #[test]
+    fn avro_field_to_column_should_return_numeric_with_precision_when_decimal_type(
+    ) -> BulkDataResult<()> {
+        let schema = AvroSchema::Decimal {
+            precision: 10,
+            scale: 2,
+            inner: Box::new(AvroSchema::Bytes),
+        };
+        let field = record_field_for_type(schema);
+
+        let column = avro_field_to_column("test", &field)?;
+
+        assert_eq!(&ColumnType::Number, column.column_type());
+        assert_eq!("numeric(10,2)", column.pg_type());
 
         Ok(())
     }
@@ -561,13 +776,85 @@ mod tests {
     }
 
     #[test]
-    fn avro_field_to_column_type_should_return_text_when_duration_type() -> BulkDataResult<()> {
+    fn avro_field_to_column_type_should_return_interval_when_duration_type() -> BulkDataResult<()> {
         let schema = AvroSchema::Duration;
         let field = record_field_for_type(schema);
 
         let column_type = avro_field_to_column_type(&field)?;
 
-        assert_eq!(ColumnType::Json, column_type);
+        assert_eq!(ColumnType::Interval, column_type);
+
+        Ok(())
+    }
+
+    #[test]
+    fn flattened_columns_should_expand_nested_record_when_within_depth() -> BulkDataResult<()> {
+        let fields = nested_record_fields();
+        let mut columns = Vec::new();
+
+        flattened_columns(&fields, "", 0, 1, &mut columns)?;
+
+        let columns: Vec<(&str, &ColumnType)> = columns
+            .iter()
+            .map(|c| (c.name(), c.column_type()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("id", &ColumnType::Integer),
+                ("address_city", &ColumnType::Text),
+                ("address_zip", &ColumnType::BigInt),
+            ],
+            columns
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn flattened_columns_should_return_json_column_when_depth_is_zero() -> BulkDataResult<()> {
+        let fields = nested_record_fields();
+        let mut columns = Vec::new();
+
+        flattened_columns(&fields, "", 0, 0, &mut columns)?;
+
+        assert_eq!(2, columns.len());
+        assert_eq!("address", columns[1].name());
+        assert_eq!(&ColumnType::Json, columns[1].column_type());
+
+        Ok(())
+    }
+
+    #[test]
+    fn flattened_values_should_return_nulls_when_nested_record_is_null() -> BulkDataResult<()> {
+        let fields = nested_record_fields();
+        let present = vec![
+            (String::from("id"), Value::Int(1)),
+            (
+                String::from("address"),
+                Value::Record(vec![
+                    (String::from("city"), Value::String(String::from("Toronto"))),
+                    (String::from("zip"), Value::Long(12345)),
+                ]),
+            ),
+        ];
+        let missing = vec![
+            (String::from("id"), Value::Int(2)),
+            (String::from("address"), Value::Null),
+        ];
+        let mut present_values = Vec::new();
+        let mut missing_values = Vec::new();
+
+        flattened_values(present, &fields, 0, 1, &mut present_values)?;
+        flattened_values(missing, &fields, 0, 1, &mut missing_values)?;
+
+        let present_values = present_values
+            .into_iter()
+            .collect::<BulkDataResult<Vec<_>>>()?;
+        let missing_values = missing_values
+            .into_iter()
+            .collect::<BulkDataResult<Vec<_>>>()?;
+        assert_eq!(vec!["1", "Toronto", "12345"], present_values);
+        assert_eq!(vec!["2", "", ""], missing_values);
 
         Ok(())
     }
@@ -806,13 +1093,33 @@ mod tests {
     }
 
     #[test]
-    fn map_avro_value_should_return_array_literal_when_decimal_value() -> BulkDataResult<()> {
+    fn map_avro_value_should_return_integer_literal_when_decimal_value() -> BulkDataResult<()> {
         let decimal = BigInt::one();
         let value = Value::Decimal(decimal.to_signed_bytes_be().into());
 
         let result = map_avro_value(value)?;
 
-        assert_eq!("{1}", result);
+        assert_eq!("1", result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_avro_field_value_should_return_scaled_literal_when_decimal_value() -> BulkDataResult<()>
+    {
+        let schema = AvroSchema::Decimal {
+            precision: 10,
+            scale: 3,
+            inner: Box::new(AvroSchema::Bytes),
+        };
+        let positive = Value::Decimal(BigInt::from(123456).to_signed_bytes_be().into());
+        let negative = Value::Decimal(BigInt::from(-5).to_signed_bytes_be().into());
+
+        let positive_result = map_avro_field_value(positive, &schema)?;
+        let negative_result = map_avro_field_value(negative, &schema)?;
+
+        assert_eq!("123.456", positive_result);
+        assert_eq!("-0.005", negative_result);
 
         Ok(())
     }
@@ -865,7 +1172,7 @@ mod tests {
     }
 
     #[test]
-    fn map_avro_value_should_return_debug_output_when_duration_value() -> BulkDataResult<()> {
+    fn map_avro_value_should_return_interval_literal_when_duration_value() -> BulkDataResult<()> {
         let value = Value::Duration(Duration::new(
             Months::new(1),
             Days::new(5),
@@ -874,7 +1181,7 @@ mod tests {
 
         let result = map_avro_value(value)?;
 
-        assert_eq!(r#"{"months":1,"days":5,"millis":1000}"#, result);
+        assert_eq!("1 months 5 days 1000 milliseconds", result);
 
         Ok(())
     }
@@ -0,0 +1,601 @@
+use super::{
+    error::{BulkDataError, BulkDataResult},
+    geometry::Geometry,
+};
+use lazy_static::lazy_static;
+use proj4rs::{
+    errors::Result as ProjResult,
+    transform::{transform, Transform, TransformClosure},
+    Proj,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::{collections::HashMap, convert::Infallible, iter::Peekable, str::Chars};
+
+/// SRID that geometries are projected into when a loader does not specify a `target_srid`
+pub const DEFAULT_TARGET_SRID: u16 = 4326;
+
+pub fn default_target_srid() -> u16 {
+    DEFAULT_TARGET_SRID
+}
+
+/// Value found within a WKT node's brackets
+#[derive(Debug, PartialEq)]
+enum WktValue {
+    Node(WktNode),
+    Text(String),
+    Number(f64),
+    Keyword(String),
+}
+
+/// Keyword and bracketed values of a WKT CRS definition (e.g. `UNIT["Meter",1.0]`)
+#[derive(Debug, PartialEq)]
+struct WktNode {
+    keyword: String,
+    values: Vec<WktValue>,
+}
+
+impl WktNode {
+    fn child(&self, keyword: &str) -> Option<&WktNode> {
+        self.children(keyword).next()
+    }
+
+    fn children<'n>(&'n self, keyword: &'n str) -> impl Iterator<Item = &'n WktNode> {
+        self.values.iter().filter_map(move |value| match value {
+            WktValue::Node(node) if node.keyword.eq_ignore_ascii_case(keyword) => Some(node),
+            _ => None,
+        })
+    }
+
+    fn text(&self, index: usize) -> Option<&str> {
+        match self.values.get(index) {
+            Some(WktValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn number(&self, index: usize) -> Option<f64> {
+        match self.values.get(index) {
+            Some(WktValue::Number(number)) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// EPSG code of the node's `AUTHORITY` or `ID` child, if present
+    fn epsg_code(&self) -> Option<u16> {
+        let authority = self.child("AUTHORITY").or_else(|| self.child("ID"))?;
+        if !authority.text(0)?.eq_ignore_ascii_case("EPSG") {
+            return None;
+        }
+        match authority.values.get(1)? {
+            WktValue::Text(code) => code.parse().ok(),
+            WktValue::Number(code) if code.fract() == 0.0 => u16::try_from(*code as i64).ok(),
+            _ => None,
+        }
+    }
+}
+
+struct WktParser<'w> {
+    chars: Peekable<Chars<'w>>,
+}
+
+impl<'w> WktParser<'w> {
+    fn new(wkt: &'w str) -> Self {
+        Self {
+            chars: wkt.chars().peekable(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn read_while<F: Fn(&char) -> bool>(&mut self, predicate: F) -> String {
+        let mut value = String::new();
+        while let Some(c) = self.chars.next_if(&predicate) {
+            value.push(c);
+        }
+        value
+    }
+
+    fn read_text(&mut self) -> BulkDataResult<String> {
+        self.chars.next();
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') if self.chars.next_if_eq(&'"').is_some() => text.push('"'),
+                Some('"') => return Ok(text),
+                Some(c) => text.push(c),
+                None => return Err("WKT text value was not terminated".into()),
+            }
+        }
+    }
+
+    fn read_value(&mut self) -> BulkDataResult<WktValue> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('"') => Ok(WktValue::Text(self.read_text()?)),
+            Some(c) if c.is_ascii_digit() || matches!(*c, '-' | '+' | '.') => {
+                let number = self.read_while(|c| {
+                    c.is_ascii_digit() || matches!(*c, '-' | '+' | '.' | 'e' | 'E')
+                });
+                match number.parse() {
+                    Ok(number) => Ok(WktValue::Number(number)),
+                    Err(_) => Err(format!("Could not parse WKT number \"{}\"", number).into()),
+                }
+            }
+            Some(c) if c.is_alphabetic() => {
+                let keyword = self.read_while(|c| c.is_alphanumeric() || *c == '_');
+                self.skip_whitespace();
+                if matches!(self.chars.peek(), Some('[' | '(')) {
+                    Ok(WktValue::Node(self.read_node_values(keyword)?))
+                } else {
+                    Ok(WktValue::Keyword(keyword))
+                }
+            }
+            _ => Err("Unexpected character found in WKT".into()),
+        }
+    }
+
+    fn read_node_values(&mut self, keyword: String) -> BulkDataResult<WktNode> {
+        self.chars.next();
+        let mut values = Vec::new();
+        loop {
+            values.push(self.read_value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']' | ')') => break,
+                _ => return Err(format!("WKT node \"{}\" was not closed", keyword).into()),
+            }
+        }
+        Ok(WktNode { keyword, values })
+    }
+
+    fn parse(mut self) -> BulkDataResult<WktNode> {
+        match self.read_value()? {
+            WktValue::Node(node) => Ok(node),
+            _ => Err("WKT definition must start with a keyword".into()),
+        }
+    }
+}
+
+/// Lowercase a WKT name and strip the separators so ESRI and OGC names can be compared
+fn normalize_name(name: &str) -> String {
+    name.trim_start_matches("D_")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn format_number(value: f64) -> String {
+    format!("{}", value)
+}
+
+/// Round a converted distance so unit conversion noise does not appear in the proj string
+fn round_metres(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+fn datum_parameters(geogcs: &WktNode) -> BulkDataResult<Vec<String>> {
+    let Some(datum) = geogcs.child("DATUM") else {
+        return Err("WKT geographic CRS does not contain a DATUM".into())
+    };
+    let mut parameters = match normalize_name(datum.text(0).unwrap_or_default()).as_str() {
+        "wgs1984" | "wgs84" | "worldgeodeticsystem1984" => vec![String::from("+datum=WGS84")],
+        "northamerican1983" | "northamericandatum1983" | "nad83" => {
+            vec![String::from("+datum=NAD83")]
+        }
+        _ => {
+            let spheroid = datum
+                .child("SPHEROID")
+                .or_else(|| datum.child("ELLIPSOID"));
+            let (Some(a), Some(rf)) = (
+                spheroid.and_then(|s| s.number(1)),
+                spheroid.and_then(|s| s.number(2)),
+            ) else {
+                return Err("WKT DATUM does not contain a valid SPHEROID".into())
+            };
+            let mut parameters = vec![format!("+a={}", format_number(a))];
+            if rf == 0.0 {
+                parameters.push(format!("+b={}", format_number(a)));
+            } else {
+                parameters.push(format!("+rf={}", format_number(rf)));
+            }
+            if let Some(towgs84) = datum.child("TOWGS84") {
+                let values: Vec<String> = towgs84
+                    .values
+                    .iter()
+                    .filter_map(|v| match v {
+                        WktValue::Number(n) => Some(format_number(*n)),
+                        _ => None,
+                    })
+                    .collect();
+                parameters.push(format!("+towgs84={}", values.join(",")));
+            }
+            parameters
+        }
+    };
+    if let Some(prime_meridian) = geogcs.child("PRIMEM").and_then(|p| p.number(1)) {
+        if prime_meridian != 0.0 {
+            parameters.push(format!("+pm={}", format_number(prime_meridian)));
+        }
+    }
+    Ok(parameters)
+}
+
+fn projection_parameters(projcs: &WktNode) -> HashMap<String, f64> {
+    projcs
+        .children("PARAMETER")
+        .filter_map(|p| Some((normalize_name(p.text(0)?), p.number(1)?)))
+        .collect()
+}
+
+fn parameter(parameters: &HashMap<String, f64>, names: &[&str], default: Option<f64>) -> BulkDataResult<f64> {
+    names
+        .iter()
+        .find_map(|name| parameters.get(*name).copied())
+        .or(default)
+        .ok_or_else(|| format!("WKT projection is missing the \"{}\" parameter", names[0]).into())
+}
+
+/// Build a proj string for a WKT `PROJCS` node. False easting and northing are converted into
+/// metres since proj expects them in metres regardless of the linear unit.
+fn projected_proj_string(projcs: &WktNode) -> BulkDataResult<Crs> {
+    let Some(projection) = projcs.child("PROJECTION").and_then(|p| p.text(0)) else {
+        return Err("WKT projected CRS does not contain a PROJECTION".into())
+    };
+    let Some(geogcs) = projcs.child("GEOGCS").or_else(|| projcs.child("BASEGEOGCRS")) else {
+        return Err("WKT projected CRS does not contain a GEOGCS".into())
+    };
+    let to_meter = projcs
+        .child("UNIT")
+        .and_then(|unit| unit.number(1))
+        .unwrap_or(1.0);
+    let params = projection_parameters(projcs);
+    let p = |names: &[&str], default: Option<f64>| parameter(&params, names, default);
+    let false_origin = |name: &str| -> BulkDataResult<String> {
+        Ok(format!(
+            "+proj={} +x_0={} +y_0={}",
+            name,
+            format_number(round_metres(p(&["falseeasting"], Some(0.0))? * to_meter)),
+            format_number(round_metres(p(&["falsenorthing"], Some(0.0))? * to_meter)),
+        ))
+    };
+    let mut parameters = match normalize_name(projection).as_str() {
+        "transversemercator" | "gausskruger" => vec![
+            false_origin("tmerc")?,
+            format!("+lat_0={}", p(&["latitudeoforigin"], Some(0.0))?),
+            format!("+lon_0={}", p(&["centralmeridian"], None)?),
+            format!("+k={}", p(&["scalefactor"], Some(1.0))?),
+        ],
+        "lambertconformalconic" | "lambertconformalconic2sp" | "lambertconformalconic1sp" => {
+            let lat_1 = p(&["standardparallel1", "latitudeoforigin"], None)?;
+            vec![
+                false_origin("lcc")?,
+                format!("+lat_1={}", lat_1),
+                format!("+lat_2={}", p(&["standardparallel2"], Some(lat_1))?),
+                format!("+lat_0={}", p(&["latitudeoforigin"], Some(lat_1))?),
+                format!("+lon_0={}", p(&["centralmeridian"], None)?),
+                format!("+k_0={}", p(&["scalefactor"], Some(1.0))?),
+            ]
+        }
+        "albers" | "albersconicequalarea" => vec![
+            false_origin("aea")?,
+            format!("+lat_1={}", p(&["standardparallel1"], None)?),
+            format!("+lat_2={}", p(&["standardparallel2"], None)?),
+            format!(
+                "+lat_0={}",
+                p(&["latitudeofcenter", "latitudeoforigin"], Some(0.0))?
+            ),
+            format!(
+                "+lon_0={}",
+                p(&["longitudeofcenter", "centralmeridian"], None)?
+            ),
+        ],
+        "mercator" | "mercator1sp" | "mercator2sp" => vec![
+            false_origin("merc")?,
+            format!("+lon_0={}", p(&["centralmeridian"], Some(0.0))?),
+            format!("+lat_ts={}", p(&["standardparallel1"], Some(0.0))?),
+        ],
+        "mercatorauxiliarysphere" | "popularvisualisationpseudomercator" => {
+            return Ok(Crs::Epsg(3857))
+        }
+        "lambertazimuthalequalarea" => vec![
+            false_origin("laea")?,
+            format!(
+                "+lat_0={}",
+                p(&["latitudeofcenter", "latitudeoforigin"], None)?
+            ),
+            format!(
+                "+lon_0={}",
+                p(&["longitudeofcenter", "centralmeridian"], None)?
+            ),
+        ],
+        "obliquestereographic" | "doublestereographic" => vec![
+            false_origin("sterea")?,
+            format!("+lat_0={}", p(&["latitudeoforigin"], None)?),
+            format!("+lon_0={}", p(&["centralmeridian"], None)?),
+            format!("+k={}", p(&["scalefactor"], Some(1.0))?),
+        ],
+        _ => return Err(format!("WKT projection \"{}\" is not supported", projection).into()),
+    };
+    parameters.extend(datum_parameters(geogcs)?);
+    if to_meter == 1.0 {
+        parameters.push(String::from("+units=m"));
+    } else {
+        parameters.push(format!("+to_meter={}", format_number(to_meter)));
+    }
+    parameters.push(String::from("+no_defs"));
+    Ok(Crs::Proj(parameters.join(" ")))
+}
+
+/// Coordinate reference system of a data source. CRS definitions without an EPSG code are kept as
+/// a proj string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Crs {
+    Epsg(u16),
+    Proj(String),
+}
+
+impl Crs {
+    /// Parse a WKT CRS definition, such as the contents of a shapefile's `.prj` file
+    pub fn from_wkt(wkt: &str) -> BulkDataResult<Self> {
+        let root = WktParser::new(wkt.trim()).parse()?;
+        if let Some(code) = root.epsg_code() {
+            return Ok(Crs::Epsg(code));
+        }
+        match root.keyword.to_uppercase().as_str() {
+            "GEOGCS" | "GEOGCRS" | "GEODCRS" => {
+                let parameters = datum_parameters(&root)?;
+                Ok(Crs::Proj(format!("+proj=longlat {} +no_defs", parameters.join(" "))))
+            }
+            "PROJCS" | "PROJCRS" => projected_proj_string(&root),
+            keyword => Err(format!("WKT CRS type \"{}\" is not supported", keyword).into()),
+        }
+    }
+
+    /// Parse a named CRS such as `EPSG:26915`, `urn:ogc:def:crs:EPSG::26915` or `OGC:CRS84`
+    pub fn from_name(name: &str) -> BulkDataResult<Self> {
+        lazy_static! {
+            static ref EPSG_NAME_REGEX: Regex =
+                Regex::new("(?i)^(?:urn:ogc:def:crs:)?EPSG:(?:[^:]*:)?([0-9]+)$").unwrap();
+        }
+        if name.to_uppercase().ends_with("CRS84") {
+            return Ok(Crs::Epsg(4326));
+        }
+        let code = EPSG_NAME_REGEX
+            .captures(name.trim())
+            .and_then(|captures| captures.get(1))
+            .and_then(|code| code.as_str().parse().ok());
+        match code {
+            Some(code) => Ok(Crs::Epsg(code)),
+            None => Err(format!("Could not find an EPSG code in the CRS name \"{}\"", name).into()),
+        }
+    }
+
+    /// Read the CRS from a PROJJSON object, as used in GeoParquet metadata. Strings are treated as
+    /// either a CRS name or WKT.
+    pub fn from_projjson(value: &Value) -> BulkDataResult<Self> {
+        if let Value::String(definition) = value {
+            return Self::from_name(definition).or_else(|_| Self::from_wkt(definition));
+        }
+        let id = value.get("id");
+        let authority = id.and_then(|id| id.get("authority")).and_then(Value::as_str);
+        let code = id.and_then(|id| id.get("code")).and_then(|code| match code {
+            Value::Number(code) => code.as_u64().and_then(|code| u16::try_from(code).ok()),
+            Value::String(code) => code.parse().ok(),
+            _ => None,
+        });
+        match (authority, code) {
+            (Some(authority), Some(code)) if authority.eq_ignore_ascii_case("EPSG") => {
+                Ok(Crs::Epsg(code))
+            }
+            (Some(authority), Some(_)) if authority.eq_ignore_ascii_case("OGC") => {
+                Ok(Crs::Epsg(4326))
+            }
+            _ => Err("PROJJSON CRS must contain an EPSG \"id\"".into()),
+        }
+    }
+
+    fn proj(&self) -> BulkDataResult<Proj> {
+        let proj = match self {
+            Crs::Epsg(code) => Proj::from_epsg_code(*code),
+            Crs::Proj(definition) => Proj::from_proj_string(definition),
+        };
+        proj.map_err(|error| BulkDataError::from(format!("Invalid CRS {:?}. {}", self, error)))
+    }
+}
+
+impl Transform for Geometry {
+    fn transform_coordinates<F: TransformClosure>(&mut self, f: &mut F) -> ProjResult<()> {
+        self.try_for_each_coordinate_mut(&mut |coordinate| coordinate.map_xyz(f))
+    }
+}
+
+fn convert_angles(geometry: &mut Geometry, convert: fn(f64) -> f64) {
+    let _ = geometry.try_for_each_coordinate_mut(&mut |coordinate| {
+        coordinate.map_xyz(&mut |x, y, z| Ok::<_, Infallible>((convert(x), convert(y), z)))
+    });
+}
+
+/// Reprojects geometries from a source CRS into the target SRID before they are written as
+/// EWKT. When the source CRS is unknown, geometries are written unchanged and without an SRID.
+pub struct GeometryProjector {
+    projections: Option<(Proj, Proj)>,
+    srid: Option<u16>,
+}
+
+impl GeometryProjector {
+    pub fn new(source: Option<&Crs>, target_srid: u16) -> BulkDataResult<Self> {
+        let Some(source) = source else {
+            return Ok(Self { projections: None, srid: None })
+        };
+        let target = Crs::Epsg(target_srid);
+        let projections = if *source == target {
+            None
+        } else {
+            Some((source.proj()?, target.proj()?))
+        };
+        Ok(Self {
+            projections,
+            srid: Some(target_srid),
+        })
+    }
+
+    /// SRID of the geometries written by the projector
+    #[inline]
+    pub fn srid(&self) -> Option<u16> {
+        self.srid
+    }
+
+    pub fn project(&self, geometry: &mut Geometry) -> BulkDataResult<()> {
+        let Some((ref source, ref target)) = self.projections else {
+            return Ok(())
+        };
+        if source.is_latlong() {
+            convert_angles(geometry, f64::to_radians);
+        }
+        transform(source, target, geometry)?;
+        if target.is_latlong() {
+            convert_angles(geometry, f64::to_degrees);
+        }
+        Ok(())
+    }
+
+    /// Project the geometry and write it as EWKT, prefixed with the SRID when known
+    pub fn to_ewkt(&self, mut geometry: Geometry) -> BulkDataResult<String> {
+        self.project(&mut geometry)?;
+        Ok(match self.srid {
+            Some(srid) => format!("SRID={};{}", srid, geometry.to_ewkt()),
+            None => geometry.to_ewkt(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bulk_loading::geometry::Coordinate;
+    use serde_json::json;
+
+    static UTM_15N_PRJ: &str = r#"PROJCS["NAD_1983_UTM_Zone_15N",GEOGCS["GCS_North_American_1983",DATUM["D_North_American_1983",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],PARAMETER["False_Easting",500000.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",-93.0],PARAMETER["Scale_Factor",0.9996],PARAMETER["Latitude_Of_Origin",0.0],UNIT["Meter",1.0]]"#;
+
+    #[test]
+    fn from_wkt_should_return_epsg_when_authority_present() -> BulkDataResult<()> {
+        let wkt = r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433],AUTHORITY["EPSG","4326"]]"#;
+
+        let actual = Crs::from_wkt(wkt)?;
+
+        assert_eq!(Crs::Epsg(4326), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn from_wkt_should_return_proj_string_when_esri_projection() -> BulkDataResult<()> {
+        let actual = Crs::from_wkt(UTM_15N_PRJ)?;
+
+        assert_eq!(
+            Crs::Proj(String::from(
+                "+proj=tmerc +x_0=500000 +y_0=0 +lat_0=0 +lon_0=-93 +k=0.9996 +datum=NAD83 +units=m +no_defs"
+            )),
+            actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_wkt_should_convert_false_easting_to_metres_when_feet() -> BulkDataResult<()> {
+        let wkt = r#"PROJCS["NAD_1983_StatePlane_Minnesota_South_FIPS_2203_Feet",GEOGCS["GCS_North_American_1983",DATUM["D_North_American_1983",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Lambert_Conformal_Conic"],PARAMETER["False_Easting",2624666.666666666],PARAMETER["False_Northing",328083.3333333333],PARAMETER["Central_Meridian",-94.0],PARAMETER["Standard_Parallel_1",43.78333333333333],PARAMETER["Standard_Parallel_2",45.21666666666667],PARAMETER["Latitude_Of_Origin",43.0],UNIT["Foot_US",0.3048006096012192]]"#;
+
+        let Crs::Proj(actual) = Crs::from_wkt(wkt)? else {
+            panic!("Expected a proj string")
+        };
+
+        assert!(actual.starts_with("+proj=lcc +x_0=800000 +y_0=100000 "));
+        assert!(actual.ends_with("+datum=NAD83 +to_meter=0.3048006096012192 +no_defs"));
+        Ok(())
+    }
+
+    #[test]
+    fn from_wkt_should_fail_when_projection_not_supported() {
+        let wkt = r#"PROJCS["Test",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]]],PROJECTION["Krovak"],UNIT["Meter",1.0]]"#;
+
+        let actual = Crs::from_wkt(wkt);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn from_wkt_should_ignore_authority_when_code_out_of_range() -> BulkDataResult<()> {
+        let wkt = r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433],AUTHORITY["EPSG",102100]]"#;
+
+        let actual = Crs::from_wkt(wkt)?;
+
+        assert_eq!(
+            Crs::Proj(String::from("+proj=longlat +datum=WGS84 +no_defs")),
+            actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_name_should_return_epsg_when_urn() -> BulkDataResult<()> {
+        assert_eq!(Crs::Epsg(26915), Crs::from_name("urn:ogc:def:crs:EPSG::26915")?);
+        assert_eq!(Crs::Epsg(3857), Crs::from_name("EPSG:3857")?);
+        assert_eq!(Crs::Epsg(4326), Crs::from_name("urn:ogc:def:crs:OGC:1.3:CRS84")?);
+        Ok(())
+    }
+
+    #[test]
+    fn from_projjson_should_return_epsg_when_id_present() -> BulkDataResult<()> {
+        let value = json!({
+            "type": "ProjectedCRS",
+            "name": "NAD83 / UTM zone 15N",
+            "id": {"authority": "EPSG", "code": 26915}
+        });
+
+        let actual = Crs::from_projjson(&value)?;
+
+        assert_eq!(Crs::Epsg(26915), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn projector_should_reproject_utm_to_wgs84() -> BulkDataResult<()> {
+        let projector = GeometryProjector::new(Some(&Crs::from_wkt(UTM_15N_PRJ)?), 4326)?;
+        let mut geometry =
+            Geometry::Point(Coordinate::new(500000.0, 4983000.0, Some(250.0), None));
+
+        projector.project(&mut geometry)?;
+
+        let Geometry::Point(point) = geometry else {
+            panic!("Expected a point")
+        };
+        assert!((point.x() + 93.0).abs() < 1e-6);
+        assert!((point.y() - 45.0).abs() < 0.05);
+        assert_eq!(Some(250.0), point.z());
+        Ok(())
+    }
+
+    #[test]
+    fn projector_should_not_set_srid_when_source_unknown() -> BulkDataResult<()> {
+        let projector = GeometryProjector::new(None, 4326)?;
+
+        let actual = projector.to_ewkt(Geometry::Point(Coordinate::new(1.0, 2.0, None, None)))?;
+
+        assert_eq!("POINT(1 2)", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn projector_should_prefix_srid_when_source_known() -> BulkDataResult<()> {
+        let projector = GeometryProjector::new(Some(&Crs::Epsg(4326)), 4326)?;
+
+        let actual = projector.to_ewkt(Geometry::Point(Coordinate::new(1.0, 2.0, None, None)))?;
+
+        assert_eq!("SRID=4326;POINT(1 2)", actual);
+        Ok(())
+    }
+}
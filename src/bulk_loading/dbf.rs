@@ -0,0 +1,371 @@
+use super::{
+    analyze::{ColumnType, Schema},
+    error::BulkDataResult,
+    load::{csv_result_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
+    options::DataOptions,
+    shape::map_field_value,
+    source::SourceLoader,
+    utilities::send_error_message,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use shapefile::dbase::Reader as DbfReader;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Size in bytes of the DBF file header and of each field descriptor that follows it
+const DBF_BLOCK_SIZE: usize = 32;
+/// Byte that marks the end of the field descriptor array
+const DBF_HEADER_TERMINATOR: u8 = 0x0D;
+
+/// Field descriptor read directly from the DBF header so the declared length and decimal count
+/// are available alongside the field type
+#[derive(Debug, PartialEq, Eq)]
+pub struct DbfFieldDescriptor {
+    name: String,
+    field_type: char,
+    length: u8,
+    decimal_count: u8,
+}
+
+impl DbfFieldDescriptor {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let name = String::from_utf8_lossy(&bytes[0..11])
+            .trim_matches(char::from(0))
+            .trim()
+            .to_owned();
+        Self {
+            name,
+            field_type: bytes[11] as char,
+            length: bytes[16],
+            decimal_count: bytes[17],
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Column type of the field based upon the descriptor's type, length and decimal count.
+    /// Numeric fields without decimals are mapped to the smallest integer type that can hold the
+    /// declared number of digits.
+    pub fn column_type(&self) -> ColumnType {
+        match self.field_type {
+            'N' if self.decimal_count == 0 => match self.length {
+                0..=4 => ColumnType::SmallInt,
+                5..=9 => ColumnType::Integer,
+                10..=18 => ColumnType::BigInt,
+                _ => ColumnType::Number,
+            },
+            'N' => ColumnType::Number,
+            'F' => ColumnType::Real,
+            'L' => ColumnType::Boolean,
+            'D' => ColumnType::Date,
+            'I' => ColumnType::Integer,
+            'Y' => ColumnType::Money,
+            'T' => ColumnType::Timestamp,
+            'B' | 'O' => ColumnType::DoublePrecision,
+            _ => ColumnType::Text,
+        }
+    }
+}
+
+fn parse_field_descriptors(header: &[u8]) -> BulkDataResult<Vec<DbfFieldDescriptor>> {
+    let mut descriptors = Vec::new();
+    let mut offset = DBF_BLOCK_SIZE;
+    loop {
+        match header.get(offset) {
+            Some(&DBF_HEADER_TERMINATOR) => break,
+            Some(_) if offset + DBF_BLOCK_SIZE <= header.len() => {
+                let descriptor_bytes = &header[offset..offset + DBF_BLOCK_SIZE];
+                descriptors.push(DbfFieldDescriptor::from_bytes(descriptor_bytes));
+                offset += DBF_BLOCK_SIZE;
+            }
+            _ => return Err("DBF header ended before the field descriptor terminator".into()),
+        }
+    }
+    Ok(descriptors)
+}
+
+/// Read the header of the DBF file, leaving the reader at the first record
+fn read_header(reader: &mut BufReader<File>) -> BulkDataResult<Vec<u8>> {
+    let mut header = vec![0_u8; DBF_BLOCK_SIZE];
+    reader.read_exact(&mut header)?;
+    let header_length = u16::from_le_bytes([header[8], header[9]]) as usize;
+    if header_length < DBF_BLOCK_SIZE + 1 {
+        return Err(format!("DBF header length of {} is not valid", header_length).into());
+    }
+    header.resize(header_length, 0);
+    reader.read_exact(&mut header[DBF_BLOCK_SIZE..])?;
+    Ok(header)
+}
+
+/// Read the field descriptors from the header of the DBF file at `path`
+pub fn read_field_descriptors<P: AsRef<Path>>(path: P) -> BulkDataResult<Vec<DbfFieldDescriptor>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = read_header(&mut reader)?;
+    parse_field_descriptors(&header)
+}
+
+/// Reads the text of numeric fields declared as [ColumnType::BigInt]. The dbase reader parses
+/// numeric fields as `f64`, which cannot represent every integer of more than 15 digits. Records
+/// are read in file order, including deleted records, so they align with the dbase reader.
+pub struct BigIntFieldReader {
+    reader: BufReader<File>,
+    record: Vec<u8>,
+    fields: Vec<(String, Range<usize>)>,
+}
+
+impl BigIntFieldReader {
+    pub fn new<P: AsRef<Path>>(path: P) -> BulkDataResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let header = read_header(&mut reader)?;
+        let record_length = u16::from_le_bytes([header[10], header[11]]) as usize;
+        let mut fields = Vec::new();
+        // Records start with the deletion flag
+        let mut offset = 1;
+        for descriptor in parse_field_descriptors(&header)? {
+            let end = offset + descriptor.length as usize;
+            if descriptor.column_type() == ColumnType::BigInt {
+                fields.push((descriptor.name, offset..end));
+            }
+            offset = end;
+        }
+        if offset > record_length {
+            return Err(format!(
+                "DBF record length of {} is shorter than its fields",
+                record_length
+            )
+            .into());
+        }
+        Ok(Self {
+            reader,
+            record: vec![0_u8; record_length],
+            fields,
+        })
+    }
+
+    /// Text of the big integer fields in the next record, keyed by field name
+    pub fn next_record(&mut self) -> BulkDataResult<HashMap<String, String>> {
+        if self.fields.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.reader.read_exact(&mut self.record)?;
+        self.fields
+            .iter()
+            .map(|(name, range)| {
+                let text = String::from_utf8_lossy(&self.record[range.clone()]);
+                let text = text.trim();
+                if !text.is_empty() && text.parse::<i64>().is_err() {
+                    return Err(format!(
+                        "Value \"{}\" of field \"{}\" is not an integer",
+                        text, name
+                    )
+                    .into());
+                }
+                Ok((name.to_owned(), text.to_owned()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DbfOptions {
+    file_path: PathBuf,
+}
+
+impl DbfOptions {
+    pub fn new(file_path: PathBuf) -> Self {
+        Self { file_path }
+    }
+
+    fn reader(&self) -> BulkDataResult<DbfReader<BufReader<File>>> {
+        let reader = DbfReader::from_path(&self.file_path)?;
+        Ok(reader)
+    }
+}
+
+impl DataOptions for DbfOptions {}
+
+pub fn schema(options: &DbfOptions) -> BulkDataResult<Schema> {
+    let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
+        return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
+    };
+    let columns = read_field_descriptors(&options.file_path)?
+        .into_iter()
+        .map(|descriptor| {
+            let column_type = descriptor.column_type();
+            (descriptor.name, column_type)
+        });
+    Schema::from_iter(table_name, columns)
+}
+
+pub async fn spool_records(
+    options: &DbfOptions,
+    record_channel: &mut RecordSpoolChannel,
+) -> RecordSpoolResult {
+    let fields = match read_field_descriptors(&options.file_path) {
+        Ok(fields) => fields,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    let mut reader = match options.reader() {
+        Ok(reader) => reader,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    let mut big_int_reader = match BigIntFieldReader::new(&options.file_path) {
+        Ok(reader) => reader,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    for (record_number, record) in reader.iter_records().enumerate() {
+        let Ok(mut record) = record else {
+            let message = format!("Could not obtain record {}", &record_number);
+            return send_error_message(record_channel, message).await;
+        };
+        let mut big_ints = match big_int_reader.next_record() {
+            Ok(values) => values,
+            Err(error) => return send_error_message(record_channel, error).await,
+        };
+        let csv_iter = fields.iter().map(|f| -> BulkDataResult<String> {
+            if let Some(value) = big_ints.remove(f.name()) {
+                return Ok(value);
+            }
+            let Some(field_value) = record.remove(f.name()) else {
+                return Err(format!("Could not find field \"{}\" in record number {}", f.name(), record_number).into())
+            };
+            Ok(map_field_value(field_value))
+        });
+        let result = record_channel
+            .send(csv_result_iter_to_string(csv_iter))
+            .await;
+        if let Err(error) = result {
+            return Some(error);
+        }
+    }
+    None
+}
+
+#[async_trait]
+impl SourceLoader for DbfOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor_bytes(name: &str, field_type: char, length: u8, decimal_count: u8) -> Vec<u8> {
+        let mut bytes = vec![0_u8; DBF_BLOCK_SIZE];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        bytes[11] = field_type as u8;
+        bytes[16] = length;
+        bytes[17] = decimal_count;
+        bytes
+    }
+
+    fn descriptor(field_type: char, length: u8, decimal_count: u8) -> DbfFieldDescriptor {
+        DbfFieldDescriptor {
+            name: String::from("test"),
+            field_type,
+            length,
+            decimal_count,
+        }
+    }
+
+    #[test]
+    fn parse_field_descriptors_should_return_all_descriptors() -> BulkDataResult<()> {
+        let mut header = vec![0_u8; DBF_BLOCK_SIZE];
+        header.extend(descriptor_bytes("NAME", 'C', 50, 0));
+        header.extend(descriptor_bytes("AMOUNT", 'N', 12, 2));
+        header.push(DBF_HEADER_TERMINATOR);
+
+        let actual = parse_field_descriptors(&header)?;
+
+        assert_eq!(
+            vec![
+                DbfFieldDescriptor {
+                    name: String::from("NAME"),
+                    field_type: 'C',
+                    length: 50,
+                    decimal_count: 0,
+                },
+                DbfFieldDescriptor {
+                    name: String::from("AMOUNT"),
+                    field_type: 'N',
+                    length: 12,
+                    decimal_count: 2,
+                },
+            ],
+            actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_field_descriptors_should_fail_when_terminator_missing() {
+        let mut header = vec![0_u8; DBF_BLOCK_SIZE];
+        header.extend(descriptor_bytes("NAME", 'C', 50, 0));
+
+        let actual = parse_field_descriptors(&header);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn column_type_should_return_integer_types_when_numeric_without_decimals() {
+        assert_eq!(ColumnType::SmallInt, descriptor('N', 4, 0).column_type());
+        assert_eq!(ColumnType::Integer, descriptor('N', 9, 0).column_type());
+        assert_eq!(ColumnType::BigInt, descriptor('N', 18, 0).column_type());
+        assert_eq!(ColumnType::Number, descriptor('N', 20, 0).column_type());
+    }
+
+    #[test]
+    fn next_record_should_read_exact_text_when_field_is_big_int() -> BulkDataResult<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("test.dbf");
+        let mut header = vec![0_u8; DBF_BLOCK_SIZE];
+        header[8..10].copy_from_slice(&(3 * DBF_BLOCK_SIZE as u16 + 1).to_le_bytes());
+        header[10..12].copy_from_slice(&24_u16.to_le_bytes());
+        header.extend(descriptor_bytes("NAME", 'C', 5, 0));
+        header.extend(descriptor_bytes("PARCEL_ID", 'N', 18, 0));
+        header.push(DBF_HEADER_TERMINATOR);
+        header.extend(b" Lot 1  9007199254740993");
+        header.extend(b" Lot 2                  ");
+        std::fs::write(&path, header)?;
+
+        let mut reader = BigIntFieldReader::new(&path)?;
+
+        assert_eq!("9007199254740993", reader.next_record()?["PARCEL_ID"]);
+        assert_eq!("", reader.next_record()?["PARCEL_ID"]);
+        Ok(())
+    }
+
+    #[test]
+    fn column_type_should_return_number_when_numeric_with_decimals() {
+        let actual = descriptor('N', 12, 2).column_type();
+
+        assert_eq!(ColumnType::Number, actual);
+    }
+
+    #[test]
+    fn column_type_should_return_text_when_character() {
+        let actual = descriptor('C', 254, 0).column_type();
+
+        assert_eq!(ColumnType::Text, actual);
+    }
+}
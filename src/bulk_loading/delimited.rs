@@ -1,9 +1,11 @@
 use super::{
     analyze::{ColumnType, Schema},
     error::BulkDataResult,
-    load::{RecordSpoolChannel, RecordSpoolResult},
+    load::{CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
 };
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tokio::{
@@ -98,3 +100,18 @@ pub async fn spool_records(
     }
     None
 }
+
+#[async_trait]
+impl SourceLoader for DelimitedDataOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self).await
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
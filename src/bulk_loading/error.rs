@@ -1,4 +1,3 @@
-use polars::prelude::PolarsError;
 use reqwest::StatusCode;
 use std::fmt::Display;
 
@@ -7,7 +6,6 @@ pub type BulkDataResult<T> = Result<T, BulkDataError>;
 #[derive(Debug)]
 pub enum BulkDataError {
     Generic(String),
-    Polars(PolarsError),
     SQL(sqlx::Error),
     Fmt(std::fmt::Error),
     IO(std::io::Error),
@@ -15,12 +13,15 @@ pub enum BulkDataError {
     Shp(shapefile::Error),
     GeoJSON(geojson::Error),
     Parquet(parquet::errors::ParquetError),
-    Wkb(wkb::WKBReadError),
     Avro(avro_rs::Error),
     Json(serde_json::Error),
     Reqwest(reqwest::Error),
     URLParse(url::ParseError),
     ArcGis(String, StatusCode),
+    ArcGisService(String, i64, String),
+    Xml(quick_xml::Error),
+    Projection(proj4rs::errors::Error),
+    Arrow(arrow::error::ArrowError),
 }
 
 impl std::error::Error for BulkDataError {}
@@ -29,7 +30,6 @@ impl Display for BulkDataError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Generic(string) => write!(f, "Loader Error\n{}", string),
-            Self::Polars(error) => write!(f, "Polars Error\n{}", error),
             Self::SQL(error) => write!(f, "Polars Error\n{}", error),
             Self::Fmt(error) => write!(f, "Format Error\n{}", error),
             Self::IO(error) => write!(f, "IO Error\n{}", error),
@@ -37,7 +37,6 @@ impl Display for BulkDataError {
             Self::Shp(error) => write!(f, "Shapefile Error\n{}", error),
             Self::GeoJSON(error) => write!(f, "GeoJSON Error\n{}", error),
             Self::Parquet(error) => write!(f, "Parquet Error\n{}", error),
-            Self::Wkb(error) => write!(f, "WKB Error\n{:?}", error),
             Self::Avro(error) => write!(f, "Avro Error\n{:?}", error),
             Self::Json(error) => write!(f, "JSON Error\n{:?}", error),
             Self::Reqwest(error) => write!(f, "Reqwest Error\n{:?}", error),
@@ -47,16 +46,18 @@ impl Display for BulkDataError {
                 "Error while running query \"{}\", status: {}",
                 query, status_code
             ),
+            Self::ArcGisService(query, code, message) => write!(
+                f,
+                "Service error while running query \"{}\", code: {}, message: {}",
+                query, code, message
+            ),
+            Self::Xml(error) => write!(f, "XML Error\n{}", error),
+            Self::Projection(error) => write!(f, "Projection Error\n{}", error),
+            Self::Arrow(error) => write!(f, "Arrow Error\n{}", error),
         }
     }
 }
 
-impl From<PolarsError> for BulkDataError {
-    fn from(error: PolarsError) -> Self {
-        Self::Polars(error)
-    }
-}
-
 impl From<sqlx::Error> for BulkDataError {
     fn from(error: sqlx::Error) -> Self {
         Self::SQL(error)
@@ -123,12 +124,6 @@ impl From<parquet::errors::ParquetError> for BulkDataError {
     }
 }
 
-impl From<wkb::WKBReadError> for BulkDataError {
-    fn from(error: wkb::WKBReadError) -> Self {
-        Self::Wkb(error)
-    }
-}
-
 impl From<avro_rs::Error> for BulkDataError {
     fn from(error: avro_rs::Error) -> Self {
         Self::Avro(error)
@@ -142,8 +137,10 @@ impl From<serde_json::Error> for BulkDataError {
 }
 
 impl From<reqwest::Error> for BulkDataError {
+    /// The url is removed from the error since request urls can contain credentials, such as the
+    /// `token` parameter of a secured ArcGIS service
     fn from(error: reqwest::Error) -> Self {
-        Self::Reqwest(error)
+        Self::Reqwest(error.without_url())
     }
 }
 
@@ -158,3 +155,21 @@ impl From<(&str, StatusCode)> for BulkDataError {
         Self::ArcGis(tuple.0.to_owned(), tuple.1)
     }
 }
+
+impl From<quick_xml::Error> for BulkDataError {
+    fn from(error: quick_xml::Error) -> Self {
+        Self::Xml(error)
+    }
+}
+
+impl From<proj4rs::errors::Error> for BulkDataError {
+    fn from(error: proj4rs::errors::Error) -> Self {
+        Self::Projection(error)
+    }
+}
+
+impl From<arrow::error::ArrowError> for BulkDataError {
+    fn from(error: arrow::error::ArrowError) -> Self {
+        Self::Arrow(error)
+    }
+}
@@ -1,55 +1,245 @@
 use super::{
     analyze::{ColumnType, Schema},
     error::BulkDataResult,
-    load::{csv_result_iter_to_string, RecordSpoolChannel, RecordSpoolResult},
+    load::{csv_result_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
     utilities::send_error_message,
 };
-use calamine::{open_workbook_auto, DataType, Range, Reader};
+use async_trait::async_trait;
+use calamine::{open_workbook, DataType, Ods, Range, Reader, Sheets, Xls, Xlsb, Xlsx};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+use zip::{result::ZipError, ZipArchive};
+
+/// Workbook format stored as a zip archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipWorkbook {
+    Xlsx,
+    Xlsb,
+    Ods,
+}
 
-#[derive(Deserialize, Serialize)]
+/// Detect the workbook format of a zip archive from its entries. [None] if the file is not a zip
+/// archive or the archive is not a workbook (e.g. a word document or a zipped shapefile)
+pub fn sniff_zip_workbook(file_path: &Path) -> BulkDataResult<Option<ZipWorkbook>> {
+    let mut archive = match ZipArchive::new(File::open(file_path)?) {
+        Ok(archive) => archive,
+        Err(ZipError::Io(error)) => return Err(error.into()),
+        Err(_) => return Ok(None),
+    };
+    if archive.file_names().any(|name| name == "xl/workbook.xml") {
+        return Ok(Some(ZipWorkbook::Xlsx));
+    }
+    if archive.file_names().any(|name| name == "xl/workbook.bin") {
+        return Ok(Some(ZipWorkbook::Xlsb));
+    }
+    let mut mime_type = String::new();
+    match archive.by_name("mimetype") {
+        Ok(mut entry) => {
+            entry.read_to_string(&mut mime_type)?;
+        }
+        Err(ZipError::Io(error)) => return Err(error.into()),
+        Err(_) => return Ok(None),
+    }
+    if mime_type.trim() == "application/vnd.oasis.opendocument.spreadsheet" {
+        return Ok(Some(ZipWorkbook::Ods));
+    }
+    Ok(None)
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct ExcelOptions {
     file_path: PathBuf,
-    sheet_name: String,
+    #[serde(default)]
+    sheet_name: Option<String>,
+    #[serde(default)]
+    sheet_pattern: Option<String>,
+    #[serde(default)]
+    header_row: usize,
+    #[serde(default)]
+    data_range: Option<String>,
+    #[serde(skip)]
+    table_name: Option<String>,
 }
 
 impl ExcelOptions {
     pub fn new(file_path: PathBuf, sheet_name: String) -> Self {
         Self {
             file_path,
-            sheet_name,
+            sheet_name: Some(sheet_name),
+            sheet_pattern: None,
+            header_row: 0,
+            data_range: None,
+            table_name: None,
+        }
+    }
+
+    /// A sheet name selects a single sheet so a sheet pattern cannot also be provided
+    fn validate(&self) -> BulkDataResult<()> {
+        if self.sheet_name.is_some() && self.sheet_pattern.is_some() {
+            return Err(format!(
+                "Excel options for {:?} cannot contain both a sheet_name and a sheet_pattern",
+                &self.file_path
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// True when the options do not name a single sheet and must be expanded into an
+    /// [ExcelOptions] per sheet using [ExcelOptions::sheets] before loading
+    pub fn is_multi_sheet(&self) -> bool {
+        self.sheet_name.is_none()
+    }
+
+    /// Expand the options into a single sheet [ExcelOptions] for each sheet in the workbook that
+    /// matches the `sheet_pattern` (or every sheet when no pattern is provided). Each sheet's
+    /// table name is derived from the file name and the sheet name.
+    pub fn sheets(&self) -> BulkDataResult<Vec<ExcelOptions>> {
+        self.validate()?;
+        if !self.is_multi_sheet() {
+            return Ok(vec![self.clone()]);
+        }
+        let Some(file_stem) = self.file_path.file_stem().and_then(|f| f.to_str()) else {
+            return Err(format!("Could not get filename for \"{:?}\"", &self.file_path).into())
+        };
+        let pattern = match &self.sheet_pattern {
+            Some(pattern) => match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(error) => {
+                    return Err(format!("Invalid sheet pattern \"{}\". {}", pattern, error).into())
+                }
+            },
+            None => None,
+        };
+        let workbook = self.workbook()?;
+        let sheets: Vec<ExcelOptions> = workbook
+            .sheet_names()
+            .iter()
+            .filter(|name| pattern.as_ref().map_or(true, |p| p.is_match(name)))
+            .map(|name| Self {
+                sheet_name: Some(name.to_owned()),
+                sheet_pattern: None,
+                table_name: Some(format!("{}_{}", file_stem, name.replace('.', "_"))),
+                ..self.clone()
+            })
+            .collect();
+        if sheets.is_empty() {
+            return Err(format!("Could not find any matching sheets in {:?}", &self.file_path).into());
         }
+        Ok(sheets)
+    }
+
+    /// Open the workbook with the reader matching the file contents rather than the file
+    /// extension. Files that are not zip archives are read as legacy xls workbooks.
+    fn workbook(&self) -> BulkDataResult<Sheets> {
+        let path = &self.file_path;
+        let workbook = match sniff_zip_workbook(path)? {
+            Some(ZipWorkbook::Xlsx) => {
+                Sheets::Xlsx(open_workbook::<Xlsx<_>, _>(path).map_err(calamine::Error::Xlsx)?)
+            }
+            Some(ZipWorkbook::Xlsb) => {
+                Sheets::Xlsb(open_workbook::<Xlsb<_>, _>(path).map_err(calamine::Error::Xlsb)?)
+            }
+            Some(ZipWorkbook::Ods) => {
+                Sheets::Ods(open_workbook::<Ods<_>, _>(path).map_err(calamine::Error::Ods)?)
+            }
+            None => Sheets::Xls(open_workbook::<Xls<_>, _>(path).map_err(calamine::Error::Xls)?),
+        };
+        Ok(workbook)
+    }
+
+    fn sheet_name(&self) -> BulkDataResult<&str> {
+        let Some(sheet_name) = &self.sheet_name else {
+            return Err(format!(
+                "Excel options for {:?} must be split into individual sheets before reading",
+                &self.file_path
+            )
+            .into())
+        };
+        Ok(sheet_name)
     }
 
     fn sheet(&self) -> BulkDataResult<Range<DataType>> {
-        let mut workbook = open_workbook_auto(&self.file_path)?;
-        let sheet = match workbook.worksheet_range(&self.sheet_name) {
+        self.validate()?;
+        let sheet_name = self.sheet_name()?;
+        let mut workbook = self.workbook()?;
+        let sheet = match workbook.worksheet_range(sheet_name) {
             Some(Ok(sheet)) => sheet,
             _ => {
                 return Err(format!(
                     "Could not find sheet \"{}\" in {:?}",
-                    &self.sheet_name, &self.file_path
+                    sheet_name, &self.file_path
                 )
                 .into())
             }
         };
-        Ok(sheet)
+        match &self.data_range {
+            Some(data_range) => {
+                let (start, end) = parse_data_range(data_range)?;
+                Ok(sheet.range(start, end))
+            }
+            None => Ok(sheet),
+        }
     }
 }
 
 impl DataOptions for ExcelOptions {}
 
+/// Parse a cell reference in A1 notation into a 0-based (row, column) position
+fn parse_cell_reference(cell: &str) -> BulkDataResult<(u32, u32)> {
+    let cell = cell.trim().to_uppercase();
+    let split_index = cell
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(cell.len());
+    let (column_part, row_part) = cell.split_at(split_index);
+    if column_part.is_empty() || !column_part.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!("Invalid column in cell reference \"{}\"", cell).into());
+    }
+    let column = column_part
+        .chars()
+        .fold(0_u32, |acc, c| acc * 26 + (c as u32 - 'A' as u32 + 1));
+    let row = match row_part.parse::<u32>() {
+        Ok(row) if row > 0 => row,
+        _ => return Err(format!("Invalid row in cell reference \"{}\"", cell).into()),
+    };
+    Ok((row - 1, column - 1))
+}
+
+/// Parse a data range in A1 notation (e.g. "B3:H500") into 0-based start and end positions
+fn parse_data_range(data_range: &str) -> BulkDataResult<((u32, u32), (u32, u32))> {
+    let Some((start, end)) = data_range.split_once(':') else {
+        return Err(format!("Data range \"{}\" must be in the format \"A1:B2\"", data_range).into())
+    };
+    let start = parse_cell_reference(start)?;
+    let end = parse_cell_reference(end)?;
+    if start.0 > end.0 || start.1 > end.1 {
+        return Err(format!("Data range \"{}\" must start before it ends", data_range).into());
+    }
+    Ok((start, end))
+}
+
 pub fn schema(options: &ExcelOptions) -> BulkDataResult<Schema> {
-    let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
-        return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
+    let table_name = match &options.table_name {
+        Some(table_name) => table_name.as_str(),
+        None => {
+            let Some(file_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
+                return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
+            };
+            file_name
+        }
     };
     let sheet = options.sheet()?;
-    let Some(header_row) = sheet.rows().next() else {
+    let Some(header_row) = sheet.rows().nth(options.header_row) else {
         return Err(format!(
             "Could not find header row in \"{}\" of {:?}",
-            &options.sheet_name, &options.file_path
+            options.sheet_name()?, &options.file_path
         ).into())
     };
     let columns = header_row.iter().map(|field| {
@@ -86,7 +276,7 @@ pub async fn spool_records(
         Ok(sheet) => sheet,
         Err(error) => return record_channel.send(Err(error)).await.err(),
     };
-    let mut rows = sheet.rows();
+    let mut rows = sheet.rows().skip(options.header_row);
     let header = match rows.next() {
         Some(row) => row,
         None => {
@@ -128,6 +318,34 @@ pub async fn spool_records(
     None
 }
 
+#[async_trait]
+impl SourceLoader for ExcelOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+
+    fn split(&self) -> BulkDataResult<Option<Vec<Box<dyn SourceLoader>>>> {
+        self.validate()?;
+        if !self.is_multi_sheet() {
+            return Ok(None);
+        }
+        let sheets = self
+            .sheets()?
+            .into_iter()
+            .map(|sheet| Box::new(sheet) as Box<dyn SourceLoader>)
+            .collect();
+        Ok(Some(sheets))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use calamine::DataType;
@@ -136,6 +354,55 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn parse_cell_reference_should_return_zero_based_position() -> BulkDataResult<()> {
+        let actual = parse_cell_reference("B3")?;
+
+        assert_eq!((2, 1), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cell_reference_should_return_position_when_multi_letter_column(
+    ) -> BulkDataResult<()> {
+        let actual = parse_cell_reference("aa10")?;
+
+        assert_eq!((9, 26), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cell_reference_should_fail_when_row_missing() {
+        let actual = parse_cell_reference("C");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn parse_data_range_should_return_start_and_end() -> BulkDataResult<()> {
+        let actual = parse_data_range("B3:H500")?;
+
+        assert_eq!(((2, 1), (499, 7)), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_data_range_should_fail_when_end_before_start() {
+        let actual = parse_data_range("H500:B3");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn validate_should_fail_when_sheet_name_and_sheet_pattern_provided() {
+        let mut options = ExcelOptions::new(PathBuf::from("test.xlsx"), String::from("Sheet1"));
+        options.sheet_pattern = Some(String::from("^Sheet"));
+
+        let actual = options.validate();
+
+        assert!(actual.is_err());
+    }
+
     #[test]
     fn map_excel_value_should_return_integer_string_when_int() -> BulkDataResult<()> {
         let value = DataType::Int(25_i64);
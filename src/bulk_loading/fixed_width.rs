@@ -0,0 +1,275 @@
+use super::{
+    analyze::{ColumnType, Schema},
+    error::{BulkDataError, BulkDataResult},
+    load::{csv_result_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
+    options::DataOptions,
+    source::SourceLoader,
+    utilities::send_error_message,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::{
+    fs::File as TkFile,
+    io::{AsyncBufReadExt, BufReader as TkBufReader, Lines as TkLines},
+};
+
+/// Layout of a single fixed-width column. `start` is the 1-based character position of the
+/// column's first character. When `implied_decimal` is provided the column is numeric and the
+/// last `implied_decimal` digits of the value are the fractional part.
+#[derive(Deserialize, Serialize)]
+pub struct FixedWidthColumn {
+    name: String,
+    start: usize,
+    length: usize,
+    #[serde(default)]
+    implied_decimal: Option<usize>,
+}
+
+impl FixedWidthColumn {
+    pub fn new(name: &str, start: usize, length: usize, implied_decimal: Option<usize>) -> Self {
+        Self {
+            name: name.to_owned(),
+            start,
+            length,
+            implied_decimal,
+        }
+    }
+
+    fn column_type(&self) -> ColumnType {
+        if self.implied_decimal.is_some() {
+            ColumnType::Number
+        } else {
+            ColumnType::Text
+        }
+    }
+
+    fn extract_value(&self, line: &[char]) -> BulkDataResult<String> {
+        let start = self.start - 1;
+        if start >= line.len() {
+            return Ok(String::new());
+        }
+        let end = (start + self.length).min(line.len());
+        let value: String = line[start..end].iter().collect();
+        let value = value.trim();
+        match self.implied_decimal {
+            Some(_) if value.is_empty() => Ok(String::new()),
+            Some(decimals) => apply_implied_decimal(value, decimals),
+            None => Ok(value.to_owned()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct FixedWidthOptions {
+    file_path: PathBuf,
+    columns: Vec<FixedWidthColumn>,
+}
+
+impl FixedWidthOptions {
+    pub fn new(file_path: PathBuf, columns: Vec<FixedWidthColumn>) -> Self {
+        Self { file_path, columns }
+    }
+
+    fn validate(&self) -> BulkDataResult<()> {
+        if self.columns.is_empty() {
+            return Err("Fixed width options must contain at least 1 column".into());
+        }
+        if let Some(column) = self.columns.iter().find(|c| c.start == 0 || c.length == 0) {
+            return Err(format!(
+                "Fixed width column \"{}\" must have a start and length of at least 1",
+                column.name
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn async_lines(&self) -> BulkDataResult<TkLines<TkBufReader<TkFile>>> {
+        let file = TkFile::open(&self.file_path).await?;
+        let reader = TkBufReader::new(file);
+        Ok(reader.lines())
+    }
+}
+
+impl DataOptions for FixedWidthOptions {}
+
+/// Insert the decimal point into a numeric value whose last `decimals` digits are implied to be
+/// the fractional part. Values that already contain a decimal point are returned unchanged.
+fn apply_implied_decimal(value: &str, decimals: usize) -> BulkDataResult<String> {
+    let (sign, digits) = match value.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", value.strip_prefix('+').unwrap_or(value)),
+    };
+    if digits.contains('.') || decimals == 0 {
+        return Ok(format!("{}{}", sign, digits));
+    }
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Value \"{}\" is not a valid implied decimal number", value).into());
+    }
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    Ok(format!("{}{}.{}", sign, whole, fraction))
+}
+
+pub async fn schema(options: &FixedWidthOptions) -> BulkDataResult<Schema> {
+    let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
+        return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
+    };
+    options.validate()?;
+    let columns = options
+        .columns
+        .iter()
+        .map(|column| (&column.name, column.column_type()));
+    Schema::from_iter(table_name, columns)
+}
+
+pub async fn spool_records(
+    options: &FixedWidthOptions,
+    record_channel: &mut RecordSpoolChannel,
+) -> RecordSpoolResult {
+    if let Err(error) = options.validate() {
+        return send_error_message(record_channel, error).await;
+    }
+    let file_path = &options.file_path;
+    let Ok(mut lines) = options.async_lines().await else {
+        let message = format!("Could not open fixed width data file, {:?}", file_path);
+        return send_error_message(record_channel, message).await;
+    };
+    let mut line_number = 1;
+    loop {
+        let Ok(line_option) = lines.next_line().await else {
+            let message = format!("Could not read line {}", &line_number);
+            return send_error_message(record_channel, message).await;
+        };
+        let Some(line) = line_option else {
+            break;
+        };
+        if line.trim().is_empty() {
+            line_number += 1;
+            continue;
+        }
+        let line: Vec<char> = line.chars().collect();
+        let csv_iter = options.columns.iter().map(|column| {
+            column.extract_value(&line).map_err(|error| {
+                BulkDataError::from(format!(
+                    "Error reading column \"{}\" on line {}. {}",
+                    column.name, line_number, error
+                ))
+            })
+        });
+        let result = record_channel
+            .send(csv_result_iter_to_string(csv_iter))
+            .await;
+        if let Err(error) = result {
+            return Some(error);
+        }
+        line_number += 1;
+    }
+    None
+}
+
+#[async_trait]
+impl SourceLoader for FixedWidthOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self).await
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_implied_decimal_should_insert_decimal_point() -> BulkDataResult<()> {
+        let actual = apply_implied_decimal("12345", 2)?;
+
+        assert_eq!("123.45", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_implied_decimal_should_pad_with_zeros_when_value_shorter_than_decimals(
+    ) -> BulkDataResult<()> {
+        let actual = apply_implied_decimal("5", 3)?;
+
+        assert_eq!("0.005", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_implied_decimal_should_keep_sign_when_negative() -> BulkDataResult<()> {
+        let actual = apply_implied_decimal("-0750", 2)?;
+
+        assert_eq!("-07.50", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_implied_decimal_should_return_same_value_when_decimal_point_present(
+    ) -> BulkDataResult<()> {
+        let actual = apply_implied_decimal("12.5", 2)?;
+
+        assert_eq!("12.5", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_implied_decimal_should_fail_when_not_numeric() {
+        let actual = apply_implied_decimal("12A5", 2);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn extract_value_should_trim_padding() -> BulkDataResult<()> {
+        let column = FixedWidthColumn::new("test", 4, 6, None);
+        let line: Vec<char> = "ABC  XY  DEF".chars().collect();
+
+        let actual = column.extract_value(&line)?;
+
+        assert_eq!("XY", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn extract_value_should_return_empty_string_when_blank() -> BulkDataResult<()> {
+        let column = FixedWidthColumn::new("test", 4, 3, Some(2));
+        let line: Vec<char> = "ABC   DEF".chars().collect();
+
+        let actual = column.extract_value(&line)?;
+
+        assert_eq!("", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn extract_value_should_return_empty_string_when_line_too_short() -> BulkDataResult<()> {
+        let column = FixedWidthColumn::new("test", 10, 3, None);
+        let line: Vec<char> = "ABC".chars().collect();
+
+        let actual = column.extract_value(&line)?;
+
+        assert_eq!("", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn extract_value_should_apply_implied_decimal() -> BulkDataResult<()> {
+        let column = FixedWidthColumn::new("test", 1, 5, Some(2));
+        let line: Vec<char> = "01050XYZ".chars().collect();
+
+        let actual = column.extract_value(&line)?;
+
+        assert_eq!("010.50", actual);
+        Ok(())
+    }
+}
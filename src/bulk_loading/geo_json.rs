@@ -1,16 +1,28 @@
 use super::{
-    analyze::{ColumnType, Schema},
+    analyze::{ColumnMetadata, ColumnType, GeometryDimension, GeometryMetadata, Schema},
+    crs::{default_target_srid, Crs, GeometryProjector, DEFAULT_TARGET_SRID},
     error::BulkDataResult,
-    load::{csv_iter_to_string, RecordSpoolChannel, RecordSpoolResult},
+    geometry::{Coordinate, Geometry},
+    load::{csv_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
     utilities::send_error_message,
+    validation::{GeometryValidation, GeometryWriter},
 };
-use geo_types::Geometry;
-use geojson::{Feature, FeatureReader, JsonValue};
+use async_trait::async_trait;
+use geojson::{Feature, FeatureReader, JsonValue, Position};
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::{fs::File, io::BufReader, path::PathBuf};
-use wkt::ToWkt;
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::PathBuf,
+};
+
+/// Maximum number of bytes read from the start of a GeoJSON file when looking for a `crs` member
+const CRS_SEARCH_LENGTH: u64 = 64 * 1024;
 
 fn column_type_from_value(value: &JsonValue) -> Option<ColumnType> {
     match value {
@@ -26,25 +38,93 @@ fn column_type_from_value(value: &JsonValue) -> Option<ColumnType> {
 fn collect_columns_into_schema(
     table_name: &str,
     columns: Vec<(String, Option<ColumnType>)>,
+    geometry_metadata: GeometryMetadata,
+    geometry_validation: GeometryValidation,
 ) -> BulkDataResult<Schema> {
-    let columns = columns
+    let mut columns = columns
         .into_iter()
-        .map(|(field, typ)| (field, typ.unwrap_or(ColumnType::Text)))
-        .chain(std::iter::once((
-            String::from("geometry"),
-            ColumnType::Geometry,
-        )));
-    Schema::from_iter(table_name, columns)
+        .map(|(field, typ)| ColumnMetadata::new(&field, typ.unwrap_or(ColumnType::Text)))
+        .collect::<BulkDataResult<Vec<_>>>()?;
+    columns.extend(geometry_validation.geometry_columns("geometry", geometry_metadata)?);
+    Schema::new(table_name, columns)
+}
+
+/// Find the name of a `crs` member within the start of a GeoJSON document. Only members that
+/// appear before the `features` array are found.
+fn find_crs_name(header: &str) -> Option<&str> {
+    lazy_static! {
+        static ref CRS_NAME_REGEX: Regex =
+            Regex::new(r#"(?s)"crs"\s*:\s*\{.*?"name"\s*:\s*"([^"]+)""#).unwrap();
+    }
+    let header = match header.find("\"features\"") {
+        Some(index) => &header[..index],
+        None => header,
+    };
+    CRS_NAME_REGEX
+        .captures(header)
+        .and_then(|captures| captures.get(1))
+        .map(|name| name.as_str())
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct GeoJsonOptions {
     file_path: PathBuf,
+    #[serde(default = "default_target_srid")]
+    target_srid: u16,
+    #[serde(default)]
+    geometry_validation: GeometryValidation,
+    /// SRID of the file's coordinates, used instead of the `crs` member
+    #[serde(default)]
+    source_srid: Option<u16>,
 }
 
 impl GeoJsonOptions {
     pub fn new(file_path: PathBuf) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            target_srid: DEFAULT_TARGET_SRID,
+            geometry_validation: GeometryValidation::default(),
+            source_srid: None,
+        }
+    }
+
+    /// Read the coordinates as the SRID instead of the CRS named in the file
+    pub fn with_source_srid(mut self, source_srid: u16) -> Self {
+        self.source_srid = Some(source_srid);
+        self
+    }
+
+    /// CRS of the `source_srid` option or named in the file's `crs` member. Files without the
+    /// member are WGS84 as per RFC 7946. Fails when the named CRS is not recognised.
+    fn crs(&self) -> BulkDataResult<Crs> {
+        if let Some(srid) = self.source_srid {
+            return Ok(Crs::Epsg(srid));
+        }
+        let mut header = Vec::new();
+        File::open(&self.file_path)?
+            .take(CRS_SEARCH_LENGTH)
+            .read_to_end(&mut header)?;
+        let Some(name) = find_crs_name(&String::from_utf8_lossy(&header)) else {
+            return Ok(Crs::Epsg(4326))
+        };
+        Crs::from_name(name).map_err(|error| {
+            format!(
+                "Could not read the CRS of {:?}. Set \"source_srid\" to load the file\n{}",
+                self.file_path, error
+            )
+            .into()
+        })
+    }
+
+    fn projector(&self) -> BulkDataResult<GeometryProjector> {
+        GeometryProjector::new(Some(&self.crs()?), self.target_srid)
+    }
+
+    fn geometry_writer(&self) -> BulkDataResult<GeometryWriter> {
+        Ok(GeometryWriter::new(
+            self.projector()?,
+            self.geometry_validation,
+        ))
     }
 
     fn reader(&self) -> BulkDataResult<FeatureReader<BufReader<File>>> {
@@ -68,6 +148,11 @@ pub fn schema(options: &GeoJsonOptions) -> BulkDataResult<Schema> {
         Some(Err(error)) => return Err(error.into()),
         None => return Schema::new(table_name, vec![]),
     };
+    let dimension = match feature_geometry(&first_feature)? {
+        Some(geometry) => geometry.dimension(),
+        None => GeometryDimension::Xy,
+    };
+    let geometry_metadata = GeometryMetadata::new(dimension, options.projector()?.srid());
     let mut columns: Vec<(String, Option<ColumnType>)> = first_feature
         .properties_iter()
         .map(|(field, value)| {
@@ -78,7 +163,12 @@ pub fn schema(options: &GeoJsonOptions) -> BulkDataResult<Schema> {
         .collect();
 
     if !undefined_type {
-        return collect_columns_into_schema(table_name, columns);
+        return collect_columns_into_schema(
+            table_name,
+            columns,
+            geometry_metadata,
+            options.geometry_validation,
+        );
     }
 
     for feature in features {
@@ -105,7 +195,12 @@ pub fn schema(options: &GeoJsonOptions) -> BulkDataResult<Schema> {
         }
         undefined_type = false;
     }
-    collect_columns_into_schema(table_name, columns)
+    collect_columns_into_schema(
+        table_name,
+        columns,
+        geometry_metadata,
+        options.geometry_validation,
+    )
 }
 
 pub fn map_json_value(value: &JsonValue) -> String {
@@ -118,17 +213,70 @@ pub fn map_json_value(value: &JsonValue) -> String {
     }
 }
 
-#[inline]
-pub fn feature_geometry_as_wkt(feature: &Feature) -> BulkDataResult<String> {
-    let Some(ref geom) = feature.geometry else {
-        return Ok(String::new())
-    };
-    match Geometry::<f64>::try_from(geom) {
-        Ok(g) => Ok(g.wkt_string()),
-        Err(error) => Err(error.into()),
+/// Convert a GeoJSON position to a [Coordinate]. The third and fourth values of the position are
+/// kept as the Z and M values.
+fn position_to_coordinate(position: &Position) -> BulkDataResult<Coordinate> {
+    match position[..] {
+        [x, y] => Ok(Coordinate::new(x, y, None, None)),
+        [x, y, z] => Ok(Coordinate::new(x, y, Some(z), None)),
+        [x, y, z, m, ..] => Ok(Coordinate::new(x, y, Some(z), Some(m))),
+        _ => Err(format!("GeoJSON position must contain at least 2 values. Found {:?}", position).into()),
     }
 }
 
+fn positions_to_coordinates(positions: &[Position]) -> BulkDataResult<Vec<Coordinate>> {
+    positions.iter().map(position_to_coordinate).collect()
+}
+
+fn rings_to_coordinates(rings: &[Vec<Position>]) -> BulkDataResult<Vec<Vec<Coordinate>>> {
+    rings.iter().map(|r| positions_to_coordinates(r)).collect()
+}
+
+fn geojson_to_geometry(value: &geojson::Value) -> BulkDataResult<Geometry> {
+    Ok(match value {
+        geojson::Value::Point(position) => Geometry::Point(position_to_coordinate(position)?),
+        geojson::Value::MultiPoint(positions) => {
+            Geometry::MultiPoint(positions_to_coordinates(positions)?)
+        }
+        geojson::Value::LineString(positions) => {
+            Geometry::LineString(positions_to_coordinates(positions)?)
+        }
+        geojson::Value::MultiLineString(lines) => {
+            Geometry::MultiLineString(rings_to_coordinates(lines)?)
+        }
+        geojson::Value::Polygon(rings) => Geometry::Polygon(rings_to_coordinates(rings)?),
+        geojson::Value::MultiPolygon(polygons) => Geometry::MultiPolygon(
+            polygons
+                .iter()
+                .map(|p| rings_to_coordinates(p))
+                .collect::<BulkDataResult<_>>()?,
+        ),
+        geojson::Value::GeometryCollection(geometries) => Geometry::GeometryCollection(
+            geometries
+                .iter()
+                .map(|g| geojson_to_geometry(&g.value))
+                .collect::<BulkDataResult<_>>()?,
+        ),
+    })
+}
+
+pub fn feature_geometry(feature: &Feature) -> BulkDataResult<Option<Geometry>> {
+    match feature.geometry {
+        Some(ref geom) => geojson_to_geometry(&geom.value).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Feature's geometry CSV values as written by the [GeometryWriter], preserving any Z and M values
+/// of the positions
+#[inline]
+pub fn feature_geometry_csv_values(
+    feature: &Feature,
+    geometry_writer: &GeometryWriter,
+) -> BulkDataResult<Vec<String>> {
+    geometry_writer.csv_values(feature_geometry(feature)?)
+}
+
 fn feature_properties_to_iter(
     properties: &Map<String, Value>,
 ) -> impl Iterator<Item = String> + '_ {
@@ -145,18 +293,22 @@ pub async fn spool_records(
         Ok(r) => r,
         Err(error) => return send_error_message(record_channel, error).await,
     };
+    let geometry_writer = match options.geometry_writer() {
+        Ok(w) => w,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
     for feature in reader.features() {
         let feature = match feature {
             Ok(f) => f,
             Err(error) => return send_error_message(record_channel, error).await,
         };
-        let geom = match feature_geometry_as_wkt(&feature) {
+        let geom = match feature_geometry_csv_values(&feature, &geometry_writer) {
             Ok(g) => g,
             Err(error) => return send_error_message(record_channel, error).await,
         };
         let csv_row = match feature.properties {
             Some(properies) => {
-                let csv_iter = feature_properties_to_iter(&properies).chain(std::iter::once(geom));
+                let csv_iter = feature_properties_to_iter(&properies).chain(geom);
                 csv_iter_to_string(csv_iter)
             }
             None => String::new(),
@@ -169,6 +321,21 @@ pub async fn spool_records(
     None
 }
 
+#[async_trait]
+impl SourceLoader for GeoJsonOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use geojson::JsonValue;
@@ -242,4 +409,51 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn find_crs_name_should_return_name_when_crs_before_features() {
+        let header = r#"{"type": "FeatureCollection", "crs": {"type": "name", "properties": {"name": "urn:ogc:def:crs:EPSG::26915"}}, "features": []}"#;
+
+        let actual = find_crs_name(header);
+
+        assert_eq!(Some("urn:ogc:def:crs:EPSG::26915"), actual);
+    }
+
+    #[test]
+    fn find_crs_name_should_return_none_when_crs_only_in_features() {
+        let header = r#"{"type": "FeatureCollection", "features": [{"properties": {"crs": {"name": "test"}}}]}"#;
+
+        let actual = find_crs_name(header);
+
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn geojson_to_geometry_should_keep_z_when_positions_have_3_values() -> BulkDataResult<()> {
+        let value = geojson::Value::LineString(vec![vec![1.0, 2.0, 10.5], vec![3.0, 4.0, 11.0]]);
+
+        let actual = geojson_to_geometry(&value)?.to_ewkt();
+
+        assert_eq!("LINESTRING Z (1 2 10.5,3 4 11)", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn geojson_to_geometry_should_write_2d_when_positions_have_2_values() -> BulkDataResult<()> {
+        let value = geojson::Value::Point(vec![1.5, 2.0]);
+
+        let actual = geojson_to_geometry(&value)?.to_ewkt();
+
+        assert_eq!("POINT(1.5 2)", actual);
+        Ok(())
+    }
+
+    #[test]
+    fn geojson_to_geometry_should_fail_when_position_has_1_value() {
+        let value = geojson::Value::Point(vec![1.5]);
+
+        let actual = geojson_to_geometry(&value);
+
+        assert!(actual.is_err());
+    }
 }
@@ -0,0 +1,561 @@
+use super::{analyze::GeometryDimension, error::BulkDataResult};
+use std::fmt::Write;
+
+/// EWKB flags stored in the high bits of the geometry type
+const EWKB_Z_FLAG: u32 = 0x8000_0000;
+const EWKB_M_FLAG: u32 = 0x4000_0000;
+const EWKB_SRID_FLAG: u32 = 0x2000_0000;
+
+/// Single vertex of a geometry. Unlike `geo_types` the optional Z and M values are kept so they
+/// can be written to the database.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    x: f64,
+    y: f64,
+    z: Option<f64>,
+    m: Option<f64>,
+}
+
+impl Coordinate {
+    pub fn new(x: f64, y: f64, z: Option<f64>, m: Option<f64>) -> Self {
+        Self { x, y, z, m }
+    }
+
+    #[inline]
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    #[inline]
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    #[inline]
+    pub fn z(&self) -> Option<f64> {
+        self.z
+    }
+
+    /// Replace the coordinate's position with the result of `f`, called with the X, Y and Z values.
+    /// A missing Z value is passed as 0 and remains missing after the update.
+    pub fn map_xyz<E, F>(&mut self, f: &mut F) -> Result<(), E>
+    where
+        F: FnMut(f64, f64, f64) -> Result<(f64, f64, f64), E>,
+    {
+        let (x, y, z) = f(self.x, self.y, self.z.unwrap_or_default())?;
+        self.x = x;
+        self.y = y;
+        if self.z.is_some() {
+            self.z = Some(z);
+        }
+        Ok(())
+    }
+
+    fn write_wkt(&self, wkt: &mut String, dimension: &GeometryDimension) {
+        let _ = write!(wkt, "{} {}", self.x, self.y);
+        if dimension.has_z() {
+            let _ = write!(wkt, " {}", self.z.unwrap_or_default());
+        }
+        if dimension.has_m() {
+            let _ = write!(wkt, " {}", self.m.unwrap_or_default());
+        }
+    }
+}
+
+/// Dimension preserving geometry used when spooling spatial data as EWKT
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Coordinate),
+    MultiPoint(Vec<Coordinate>),
+    LineString(Vec<Coordinate>),
+    MultiLineString(Vec<Vec<Coordinate>>),
+    Polygon(Vec<Vec<Coordinate>>),
+    MultiPolygon(Vec<Vec<Vec<Coordinate>>>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+fn write_coordinates(wkt: &mut String, coordinates: &[Coordinate], dimension: &GeometryDimension) {
+    wkt.push('(');
+    for (i, coordinate) in coordinates.iter().enumerate() {
+        if i > 0 {
+            wkt.push(',');
+        }
+        coordinate.write_wkt(wkt, dimension);
+    }
+    wkt.push(')');
+}
+
+fn write_rings(wkt: &mut String, rings: &[Vec<Coordinate>], dimension: &GeometryDimension) {
+    wkt.push('(');
+    for (i, ring) in rings.iter().enumerate() {
+        if i > 0 {
+            wkt.push(',');
+        }
+        write_coordinates(wkt, ring, dimension);
+    }
+    wkt.push(')');
+}
+
+impl Geometry {
+    fn wkt_name(&self) -> &'static str {
+        match self {
+            Geometry::Point(_) => "POINT",
+            Geometry::MultiPoint(_) => "MULTIPOINT",
+            Geometry::LineString(_) => "LINESTRING",
+            Geometry::MultiLineString(_) => "MULTILINESTRING",
+            Geometry::Polygon(_) => "POLYGON",
+            Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+            Geometry::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+        }
+    }
+
+    pub fn for_each_coordinate<F: FnMut(&Coordinate)>(&self, f: &mut F) {
+        match self {
+            Geometry::Point(point) => f(point),
+            Geometry::MultiPoint(points) | Geometry::LineString(points) => points.iter().for_each(f),
+            Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => {
+                lines.iter().flatten().for_each(f)
+            }
+            Geometry::MultiPolygon(polygons) => polygons.iter().flatten().flatten().for_each(f),
+            Geometry::GeometryCollection(geometries) => {
+                geometries.iter().for_each(|g| g.for_each_coordinate(f))
+            }
+        }
+    }
+
+    pub fn try_for_each_coordinate_mut<E, F>(&mut self, f: &mut F) -> Result<(), E>
+    where
+        F: FnMut(&mut Coordinate) -> Result<(), E>,
+    {
+        match self {
+            Geometry::Point(point) => f(point),
+            Geometry::MultiPoint(points) | Geometry::LineString(points) => {
+                points.iter_mut().try_for_each(f)
+            }
+            Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => {
+                lines.iter_mut().flatten().try_for_each(f)
+            }
+            Geometry::MultiPolygon(polygons) => {
+                polygons.iter_mut().flatten().flatten().try_for_each(f)
+            }
+            Geometry::GeometryCollection(geometries) => geometries
+                .iter_mut()
+                .try_for_each(|g| g.try_for_each_coordinate_mut(f)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let mut empty = true;
+        self.for_each_coordinate(&mut |_| empty = false);
+        empty
+    }
+
+    /// Dimension of the geometry. Z or M values are only included when every coordinate of the
+    /// geometry has a value for that ordinate.
+    pub fn dimension(&self) -> GeometryDimension {
+        let mut has_coordinates = false;
+        let mut has_z = true;
+        let mut has_m = true;
+        self.for_each_coordinate(&mut |coordinate| {
+            has_coordinates = true;
+            has_z = has_z && coordinate.z.is_some();
+            has_m = has_m && coordinate.m.is_some();
+        });
+        GeometryDimension::new(has_coordinates && has_z, has_coordinates && has_m)
+    }
+
+    fn write_ewkt(&self, wkt: &mut String, dimension: &GeometryDimension) {
+        wkt.push_str(self.wkt_name());
+        if self.is_empty() {
+            wkt.push_str(" EMPTY");
+            return;
+        }
+        let tag = dimension.wkt_tag();
+        if !tag.is_empty() {
+            let _ = write!(wkt, " {} ", tag);
+        }
+        match self {
+            Geometry::Point(point) => write_coordinates(wkt, &[*point], dimension),
+            Geometry::MultiPoint(points) => {
+                wkt.push('(');
+                for (i, point) in points.iter().enumerate() {
+                    if i > 0 {
+                        wkt.push(',');
+                    }
+                    write_coordinates(wkt, &[*point], dimension);
+                }
+                wkt.push(')');
+            }
+            Geometry::LineString(points) => write_coordinates(wkt, points, dimension),
+            Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => {
+                write_rings(wkt, lines, dimension)
+            }
+            Geometry::MultiPolygon(polygons) => {
+                wkt.push('(');
+                for (i, polygon) in polygons.iter().enumerate() {
+                    if i > 0 {
+                        wkt.push(',');
+                    }
+                    write_rings(wkt, polygon, dimension);
+                }
+                wkt.push(')');
+            }
+            Geometry::GeometryCollection(geometries) => {
+                wkt.push('(');
+                for (i, geometry) in geometries.iter().enumerate() {
+                    if i > 0 {
+                        wkt.push(',');
+                    }
+                    geometry.write_ewkt(wkt, dimension);
+                }
+                wkt.push(')');
+            }
+        }
+    }
+
+    /// Write the geometry as EWKT, tagging the geometry with Z/M/ZM when those ordinates are
+    /// present (e.g. `POINT Z (1 2 3)`)
+    pub fn to_ewkt(&self) -> String {
+        let mut wkt = String::new();
+        self.write_ewkt(&mut wkt, &self.dimension());
+        wkt
+    }
+}
+
+/// Reader of OGC WKB, ISO WKB and PostGIS EWKB geometries that keeps any Z and M values
+struct WkbReader<'b> {
+    bytes: &'b [u8],
+    offset: usize,
+}
+
+/// Byte order and ordinates of the WKB geometry being read
+#[derive(Clone, Copy)]
+struct WkbLayout {
+    little_endian: bool,
+    has_z: bool,
+    has_m: bool,
+}
+
+impl<'b> WkbReader<'b> {
+    fn take<const N: usize>(&mut self) -> BulkDataResult<[u8; N]> {
+        let Some(bytes) = self.bytes.get(self.offset..self.offset + N) else {
+            return Err(format!("WKB ended unexpectedly at byte {}", self.offset).into())
+        };
+        self.offset += N;
+        let mut array = [0; N];
+        array.copy_from_slice(bytes);
+        Ok(array)
+    }
+
+    fn read_u32(&mut self, little_endian: bool) -> BulkDataResult<u32> {
+        let bytes = self.take()?;
+        Ok(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_f64(&mut self, little_endian: bool) -> BulkDataResult<f64> {
+        let bytes = self.take()?;
+        Ok(if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_coordinate(&mut self, layout: WkbLayout) -> BulkDataResult<Coordinate> {
+        let x = self.read_f64(layout.little_endian)?;
+        let y = self.read_f64(layout.little_endian)?;
+        let z = match layout.has_z {
+            true => Some(self.read_f64(layout.little_endian)?),
+            false => None,
+        };
+        let m = match layout.has_m {
+            true => Some(self.read_f64(layout.little_endian)?),
+            false => None,
+        };
+        Ok(Coordinate::new(x, y, z, m))
+    }
+
+    fn read_coordinates(&mut self, layout: WkbLayout) -> BulkDataResult<Vec<Coordinate>> {
+        let count = self.read_u32(layout.little_endian)?;
+        (0..count).map(|_| self.read_coordinate(layout)).collect()
+    }
+
+    fn read_rings(&mut self, layout: WkbLayout) -> BulkDataResult<Vec<Vec<Coordinate>>> {
+        let count = self.read_u32(layout.little_endian)?;
+        (0..count).map(|_| self.read_coordinates(layout)).collect()
+    }
+
+    /// Read the geometries of a multi geometry or collection
+    fn read_geometries(&mut self, layout: WkbLayout) -> BulkDataResult<Vec<Geometry>> {
+        let count = self.read_u32(layout.little_endian)?;
+        (0..count).map(|_| self.read_geometry()).collect()
+    }
+
+    fn read_geometry(&mut self) -> BulkDataResult<Geometry> {
+        let little_endian = match self.take::<1>()? {
+            [0] => false,
+            [1] => true,
+            [byte_order] => {
+                return Err(format!("WKB byte order {} is not valid", byte_order).into())
+            }
+        };
+        let type_code = self.read_u32(little_endian)?;
+        if type_code & EWKB_SRID_FLAG != 0 {
+            self.read_u32(little_endian)?;
+        }
+        // ISO WKB adds 1000 for Z, 2000 for M and 3000 for ZM to the geometry type
+        let iso_code = type_code & 0x0FFF_FFFF;
+        let layout = WkbLayout {
+            little_endian,
+            has_z: type_code & EWKB_Z_FLAG != 0 || matches!(iso_code / 1000, 1 | 3),
+            has_m: type_code & EWKB_M_FLAG != 0 || matches!(iso_code / 1000, 2 | 3),
+        };
+        Ok(match iso_code % 1000 {
+            1 => Geometry::Point(self.read_coordinate(layout)?),
+            2 => Geometry::LineString(self.read_coordinates(layout)?),
+            3 => Geometry::Polygon(self.read_rings(layout)?),
+            4 => Geometry::MultiPoint(
+                self.read_geometries(layout)?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::Point(point) => Ok(point),
+                        _ => Err("WKB multipoint must only contain points".into()),
+                    })
+                    .collect::<BulkDataResult<_>>()?,
+            ),
+            5 => Geometry::MultiLineString(
+                self.read_geometries(layout)?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::LineString(line) => Ok(line),
+                        _ => Err("WKB multilinestring must only contain linestrings".into()),
+                    })
+                    .collect::<BulkDataResult<_>>()?,
+            ),
+            6 => Geometry::MultiPolygon(
+                self.read_geometries(layout)?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::Polygon(rings) => Ok(rings),
+                        _ => Err("WKB multipolygon must only contain polygons".into()),
+                    })
+                    .collect::<BulkDataResult<_>>()?,
+            ),
+            7 => Geometry::GeometryCollection(self.read_geometries(layout)?),
+            _ => return Err(format!("WKB geometry type {} is not supported", type_code).into()),
+        })
+    }
+}
+
+impl Geometry {
+    /// Decode a WKB, ISO WKB or EWKB geometry, keeping any Z and M values. An EWKB SRID is
+    /// ignored.
+    pub fn from_wkb(bytes: &[u8]) -> BulkDataResult<Self> {
+        let mut reader = WkbReader { bytes, offset: 0 };
+        reader.read_geometry()
+    }
+}
+
+impl From<geo_types::Coordinate<f64>> for Coordinate {
+    fn from(coordinate: geo_types::Coordinate<f64>) -> Self {
+        Coordinate::new(coordinate.x, coordinate.y, None, None)
+    }
+}
+
+fn line_string_coordinates(line_string: geo_types::LineString<f64>) -> Vec<Coordinate> {
+    line_string.0.into_iter().map(Coordinate::from).collect()
+}
+
+fn polygon_rings(polygon: geo_types::Polygon<f64>) -> Vec<Vec<Coordinate>> {
+    let (exterior, interiors) = polygon.into_inner();
+    std::iter::once(exterior)
+        .chain(interiors)
+        .map(line_string_coordinates)
+        .collect()
+}
+
+impl From<geo_types::Geometry<f64>> for Geometry {
+    fn from(geometry: geo_types::Geometry<f64>) -> Self {
+        match geometry {
+            geo_types::Geometry::Point(point) => Geometry::Point(point.0.into()),
+            geo_types::Geometry::Line(line) => {
+                Geometry::LineString(vec![line.start.into(), line.end.into()])
+            }
+            geo_types::Geometry::LineString(line_string) => {
+                Geometry::LineString(line_string_coordinates(line_string))
+            }
+            geo_types::Geometry::Polygon(polygon) => Geometry::Polygon(polygon_rings(polygon)),
+            geo_types::Geometry::MultiPoint(points) => {
+                Geometry::MultiPoint(points.0.into_iter().map(|p| p.0.into()).collect())
+            }
+            geo_types::Geometry::MultiLineString(lines) => Geometry::MultiLineString(
+                lines.0.into_iter().map(line_string_coordinates).collect(),
+            ),
+            geo_types::Geometry::MultiPolygon(polygons) => {
+                Geometry::MultiPolygon(polygons.0.into_iter().map(polygon_rings).collect())
+            }
+            geo_types::Geometry::GeometryCollection(geometries) => Geometry::GeometryCollection(
+                geometries.0.into_iter().map(Geometry::from).collect(),
+            ),
+            geo_types::Geometry::Rect(rect) => Geometry::Polygon(polygon_rings(rect.to_polygon())),
+            geo_types::Geometry::Triangle(triangle) => {
+                Geometry::Polygon(polygon_rings(triangle.to_polygon()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xy(x: f64, y: f64) -> Coordinate {
+        Coordinate::new(x, y, None, None)
+    }
+
+    fn xyz(x: f64, y: f64, z: f64) -> Coordinate {
+        Coordinate::new(x, y, Some(z), None)
+    }
+
+    /// Little endian WKB header followed by the values as little endian doubles
+    fn wkb(type_code: u32, counts: &[u32], values: &[f64]) -> Vec<u8> {
+        let mut bytes = vec![1];
+        bytes.extend(type_code.to_le_bytes());
+        for count in counts {
+            bytes.extend(count.to_le_bytes());
+        }
+        for value in values {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn from_wkb_should_keep_z_when_iso_point_z() -> BulkDataResult<()> {
+        let bytes = wkb(1001, &[], &[1.0, 2.0, 3.0]);
+
+        let actual = Geometry::from_wkb(&bytes)?;
+
+        assert_eq!(Geometry::Point(xyz(1.0, 2.0, 3.0)), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn from_wkb_should_keep_m_when_ewkb_has_m_flag_and_srid() -> BulkDataResult<()> {
+        let mut bytes = vec![0];
+        bytes.extend((2 | EWKB_M_FLAG | EWKB_SRID_FLAG).to_be_bytes());
+        bytes.extend(4326_u32.to_be_bytes());
+        bytes.extend(2_u32.to_be_bytes());
+        for value in [1.0_f64, 2.0, 0.5, 3.0, 4.0, 1.5] {
+            bytes.extend(value.to_be_bytes());
+        }
+
+        let actual = Geometry::from_wkb(&bytes)?;
+
+        assert_eq!("LINESTRING M (1 2 0.5,3 4 1.5)", actual.to_ewkt());
+        Ok(())
+    }
+
+    #[test]
+    fn from_wkb_should_read_parts_when_multipolygon_zm() -> BulkDataResult<()> {
+        let mut bytes = wkb(3006, &[1], &[]);
+        bytes.extend(wkb(3003, &[1, 4], &[]));
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)] {
+            for value in [x, y, 5.0, 6.0] {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+
+        let actual = Geometry::from_wkb(&bytes)?;
+
+        assert_eq!(
+            "MULTIPOLYGON ZM (((0 0 5 6,1 0 5 6,1 1 5 6,0 0 5 6)))",
+            actual.to_ewkt()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_wkb_should_fail_when_bytes_truncated() {
+        let bytes = wkb(1001, &[], &[1.0, 2.0]);
+
+        let actual = Geometry::from_wkb(&bytes);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn to_ewkt_should_write_2d_point_without_tag() {
+        let geometry = Geometry::Point(xy(1.0, 2.5));
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("POINT(1 2.5)", actual);
+    }
+
+    #[test]
+    fn to_ewkt_should_write_z_tag_when_all_coordinates_have_z() {
+        let geometry = Geometry::LineString(vec![xyz(1.0, 2.0, 3.0), xyz(4.0, 5.0, 6.5)]);
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("LINESTRING Z (1 2 3,4 5 6.5)", actual);
+    }
+
+    #[test]
+    fn to_ewkt_should_write_zm_tag_when_all_coordinates_have_z_and_m() {
+        let geometry = Geometry::Point(Coordinate::new(1.0, 2.0, Some(3.0), Some(4.0)));
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("POINT ZM (1 2 3 4)", actual);
+    }
+
+    #[test]
+    fn to_ewkt_should_write_m_tag_when_only_m_present() {
+        let geometry = Geometry::MultiPoint(vec![
+            Coordinate::new(1.0, 2.0, None, Some(0.5)),
+            Coordinate::new(3.0, 4.0, None, Some(1.5)),
+        ]);
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("MULTIPOINT M ((1 2 0.5),(3 4 1.5))", actual);
+    }
+
+    #[test]
+    fn to_ewkt_should_drop_z_when_any_coordinate_missing_z() {
+        let geometry = Geometry::LineString(vec![xyz(1.0, 2.0, 3.0), xy(4.0, 5.0)]);
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("LINESTRING(1 2,4 5)", actual);
+    }
+
+    #[test]
+    fn to_ewkt_should_write_polygon_rings() {
+        let geometry = Geometry::MultiPolygon(vec![vec![vec![
+            xyz(0.0, 0.0, 1.0),
+            xyz(1.0, 0.0, 1.0),
+            xyz(1.0, 1.0, 1.0),
+            xyz(0.0, 0.0, 1.0),
+        ]]]);
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("MULTIPOLYGON Z (((0 0 1,1 0 1,1 1 1,0 0 1)))", actual);
+    }
+
+    #[test]
+    fn to_ewkt_should_write_empty_when_no_coordinates() {
+        let geometry = Geometry::GeometryCollection(vec![]);
+
+        let actual = geometry.to_ewkt();
+
+        assert_eq!("GEOMETRYCOLLECTION EMPTY", actual);
+    }
+}
@@ -1,27 +1,90 @@
 use super::{
-    analyze::Schema,
+    analyze::{ColumnMetadata, Schema},
     error::BulkDataResult,
-    load::{RecordSpoolChannel, RecordSpoolResult},
+    load::{csv_result_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
-    utilities::{schema_from_dataframe, spool_dataframe_records},
+    source::SourceLoader,
+    utilities::map_arrow_value,
 };
-use polars::prelude::{DataFrame, IpcReader, SerReader};
+use arrow::{
+    datatypes::Schema as ArrowSchema,
+    ipc::reader::{FileReader, StreamReader},
+    record_batch::RecordBatchReader,
+};
+use async_trait::async_trait;
+use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, path::PathBuf};
+use std::{
+    fs::File,
+    io::{BufReader, Cursor, ErrorKind, Read},
+    path::PathBuf,
+};
+
+/// Magic bytes at the start of an Arrow IPC file. Files without them are read as an Arrow IPC
+/// stream.
+const IPC_FILE_MAGIC: &[u8; 6] = b"ARROW1";
+
+type IpcBatchReader = Box<dyn RecordBatchReader + Send>;
 
 #[derive(Deserialize, Serialize)]
 pub struct IpcFileOptions {
     file_path: PathBuf,
+    #[serde(default)]
+    columns: Option<Vec<String>>,
 }
 
 impl IpcFileOptions {
     pub fn new(file_path: PathBuf) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            columns: None,
+        }
     }
 
-    pub fn dataframe(&self) -> BulkDataResult<DataFrame> {
+    fn is_stream(&self) -> BulkDataResult<bool> {
+        let mut magic = [0_u8; 6];
+        match File::open(&self.file_path)?.read_exact(&mut magic) {
+            Ok(_) => Ok(&magic != IPC_FILE_MAGIC),
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(true),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Reader of the file's record batches. The file is memory-mapped when possible so batches
+    /// are paged in as they are read, falling back to buffered reads otherwise.
+    fn batch_reader(&self, projection: Option<Vec<usize>>) -> BulkDataResult<IpcBatchReader> {
+        let is_stream = self.is_stream()?;
         let file = File::open(&self.file_path)?;
-        Ok(IpcReader::new(file).finish()?)
+        // Safety: the file is only read and is not expected to change while it is loaded
+        let mmap = unsafe { Mmap::map(&file) };
+        let reader: IpcBatchReader = match (is_stream, mmap) {
+            (false, Ok(mmap)) => Box::new(FileReader::try_new(Cursor::new(mmap), projection)?),
+            (false, Err(_)) => Box::new(FileReader::try_new(BufReader::new(file), projection)?),
+            (true, Ok(mmap)) => Box::new(StreamReader::try_new(Cursor::new(mmap), projection)?),
+            (true, Err(_)) => Box::new(StreamReader::try_new(BufReader::new(file), projection)?),
+        };
+        Ok(reader)
+    }
+
+    /// Arrow schema of the projected columns along with their indices within the file. All
+    /// columns are included when the options do not specify any.
+    fn projection(&self) -> BulkDataResult<(ArrowSchema, Option<Vec<usize>>)> {
+        let schema = self.batch_reader(None)?.schema();
+        let Some(columns) = &self.columns else {
+            return Ok((schema.as_ref().clone(), None))
+        };
+        let indices = columns
+            .iter()
+            .map(|name| -> BulkDataResult<usize> {
+                match schema.index_of(name) {
+                    Ok(index) => Ok(index),
+                    Err(_) => {
+                        Err(format!("Could not find column \"{}\" in the IPC file", name).into())
+                    }
+                }
+            })
+            .collect::<BulkDataResult<Vec<_>>>()?;
+        Ok((schema.project(&indices)?, Some(indices)))
     }
 }
 
@@ -31,14 +94,58 @@ pub fn schema(options: &IpcFileOptions) -> BulkDataResult<Schema> {
     let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
         return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
     };
-    let df = options.dataframe()?;
-    schema_from_dataframe(table_name.to_owned(), df)
+    let (arrow_schema, _) = options.projection()?;
+    let columns = arrow_schema
+        .fields()
+        .iter()
+        .map(|field| ColumnMetadata::new(field.name(), field.data_type().into()))
+        .collect::<BulkDataResult<_>>()?;
+    Schema::new(table_name, columns)
 }
 
-pub async fn spool_records(options: &IpcFileOptions, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
-    let df = match options.dataframe() {
-        Ok(df) => df,
+pub async fn spool_records(
+    options: &IpcFileOptions,
+    record_channel: &mut RecordSpoolChannel,
+) -> RecordSpoolResult {
+    let reader = match options
+        .projection()
+        .and_then(|(_, projection)| options.batch_reader(projection))
+    {
+        Ok(reader) => reader,
         Err(error) => return record_channel.send(Err(error)).await.err(),
     };
-    spool_dataframe_records(df, record_channel).await
+    for batch in reader {
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(error) => return record_channel.send(Err(error.into())).await.err(),
+        };
+        for row in 0..batch.num_rows() {
+            let csv_iter = batch
+                .columns()
+                .iter()
+                .map(|column| map_arrow_value(column, row));
+            let result = record_channel
+                .send(csv_result_iter_to_string(csv_iter))
+                .await;
+            if let Err(error) = result {
+                return Some(error);
+            }
+        }
+    }
+    None
+}
+
+#[async_trait]
+impl SourceLoader for IpcFileOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
 }
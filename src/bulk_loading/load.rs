@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::{error::SendError, Sender};
 
 use super::{
@@ -7,10 +8,61 @@ use super::{
     utilities::escape_csv_string,
 };
 
-pub type BulkLoadResult = Result<u64, BulkDataError>;
+pub type BulkLoadResult = Result<LoadSummary, BulkDataError>;
 pub type RecordSpoolResult = Option<SendError<BulkDataResult<String>>>;
 pub type RecordSpoolChannel = Sender<BulkDataResult<String>>;
 
+/// Outcome of a successful load
+#[derive(Debug)]
+pub struct LoadSummary {
+    record_count: u64,
+    warnings: Vec<String>,
+}
+
+impl LoadSummary {
+    pub fn new(record_count: u64, warnings: Vec<String>) -> Self {
+        Self {
+            record_count,
+            warnings,
+        }
+    }
+
+    #[inline]
+    pub fn record_count(&self) -> u64 {
+        self.record_count
+    }
+
+    /// Issues found while loading that did not fail the load
+    #[inline]
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn into_parts(self) -> (u64, Vec<String>) {
+        (self.record_count, self.warnings)
+    }
+}
+
+/// Warnings collected by a source while its records are spooled. Clones share the same warnings.
+#[derive(Clone, Default)]
+pub struct LoadWarnings(Arc<Mutex<Vec<String>>>);
+
+impl LoadWarnings {
+    pub fn push(&self, warning: String) {
+        if let Ok(mut warnings) = self.0.lock() {
+            warnings.push(warning);
+        }
+    }
+
+    /// Remove and return the collected warnings
+    pub fn take(&self) -> Vec<String> {
+        match self.0.lock() {
+            Ok(mut warnings) => std::mem::take(&mut *warnings),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
 pub struct CopyOptions {
     table_name: String,
     columns: Vec<String>,
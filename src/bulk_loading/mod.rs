@@ -1,127 +1,180 @@
 mod analyze;
 mod arcgis;
 mod avro;
+mod crs;
+mod dbf;
 mod delimited;
 pub mod error;
 mod excel;
+mod fixed_width;
 mod geo_json;
+mod geometry;
 mod ipc;
 mod load;
 mod options;
 mod parquet;
 mod shape;
+mod source;
 mod utilities;
+mod validation;
+mod xml;
 
-use std::path::Path;
+use std::{fs::File, io::Read, path::Path};
 
-use self::parquet::{
-    schema as parquet_schema, spool_records as parquet_spool_records, ParquetFileOptions,
+pub use analyze::{
+    ColumnMetadata, ColumnType, GeometryDimension, GeometryMetadata, NumericMetadata, Schema,
 };
-pub use analyze::{ColumnMetadata, ColumnType};
-use analyze::Schema;
-use arcgis::{schema as arc_gis_schema, spool_records as arc_gis_spool_records, ArcGisDataOptions};
-use avro::{schema as avro_schema, spool_records as avro_spool_records, AvroFileOptions};
-use delimited::{
-    schema as delimited_schema, spool_records as delimited_spool_records, DelimitedDataOptions,
+pub use arcgis::{
+    crawl_catalog, set_secret_provider, ArcGisAuth, ArcGisDataOptions, CatalogCrawl, CatalogError,
+    CatalogField, CatalogLayer, CodedValueOutput, EnvironmentSecrets, FilterGeometry, QueryFilter,
+    SecretProvider, SpatialFilter, SpatialRelationship,
 };
 use error::BulkDataResult;
-use excel::{schema as excel_schema, spool_records as excel_spool_records, ExcelOptions};
-use geo_json::{
-    schema as geo_json_schema, spool_records as geo_json_spool_records, GeoJsonOptions,
+pub use load::{
+    csv_iter_to_string, csv_result_iter_to_string, BulkLoadResult, CopyOptions, LoadSummary,
+    RecordSpoolChannel, RecordSpoolResult,
 };
-use ipc::{schema as ipc_schema, spool_records as ipc_spool_records, IpcFileOptions};
-use load::{BulkLoadResult, CopyOptions, RecordSpoolChannel, RecordSpoolResult};
+pub use options::DataOptions;
 use serde_json::Value;
-use shape::{schema as shape_schema, spool_records as shape_spool_records, ShapeDataOptions};
+use source::with_loader_registry;
+pub use source::{register_loader, LoaderRegistration, LoaderRegistry, SourceLoader};
 use sqlx::postgres::PgPool;
 use tokio::sync::mpsc::channel as mpsc_channel;
 
-pub enum DataLoader {
-    ArcGis(ArcGisDataOptions),
-    Avro(AvroFileOptions),
-    Delimited(DelimitedDataOptions),
-    Excel(ExcelOptions),
-    GeoJson(GeoJsonOptions),
-    Ipc(IpcFileOptions),
-    Parquet(ParquetFileOptions),
-    Shape(ShapeDataOptions),
+/// Loader of a single table from source data, created by a [LoaderRegistry]
+pub struct DataLoader {
+    format: String,
+    loader: Box<dyn SourceLoader>,
+}
+
+/// Number of bytes read from the start of a file when sniffing the file's format
+const SNIFF_LENGTH: usize = 4096;
+
+/// Detect the format of a file from its leading bytes. [None] if the format is not recognized
+fn sniff_format(file_path: &Path) -> BulkDataResult<Option<&'static str>> {
+    let mut buffer = Vec::with_capacity(SNIFF_LENGTH);
+    File::open(file_path)?
+        .take(SNIFF_LENGTH as u64)
+        .read_to_end(&mut buffer)?;
+    let format = match buffer.as_slice() {
+        [b'P', b'A', b'R', b'1', ..] => "parquet",
+        [b'O', b'b', b'j', 1, ..] => "avro",
+        [b'A', b'R', b'R', b'O', b'W', b'1', ..] => "ipc",
+        // Big-endian file code 9994 of the shapefile main file header
+        [0x00, 0x00, 0x27, 0x0A, ..] => "shape",
+        // Zip archives are only workbooks when they contain a workbook part
+        [b'P', b'K', 0x03, 0x04, ..] => match excel::sniff_zip_workbook(file_path)? {
+            Some(_) => "excel",
+            None => return Ok(None),
+        },
+        bytes => {
+            let text = String::from_utf8_lossy(bytes);
+            let text = text.trim_start_matches('\u{feff}').trim_start();
+            // Some exporters write a non-standard type name so the features member is also checked
+            let is_feature_collection =
+                text.contains("\"FeatureCollection\"") || text.contains("\"features\"");
+            if text.starts_with('{') && is_feature_collection {
+                "geojson"
+            } else {
+                return Ok(None);
+            }
+        }
+    };
+    Ok(Some(format))
 }
 
 impl DataLoader {
+    /// Create a loader from source data options using the formats registered with
+    /// [register_loader]
     pub fn new(options: &Value) -> BulkDataResult<Self> {
-        let json_string = serde_json::to_string(options)?;
+        with_loader_registry(|registry| Self::from_registry(options, registry))
+    }
+
+    /// Create a loader from source data options using the formats of the registry. The format
+    /// is taken from the `format` property, then the presence of a `url` property for ArcGIS
+    /// services, then the file extension and finally the leading bytes of the file. Files with a
+    /// delimited extension are also sniffed in case they contain another format.
+    pub fn from_registry(options: &Value, registry: &LoaderRegistry) -> BulkDataResult<Self> {
         let Some(object) = options.as_object() else {
             return Err("Source data options must be an object".into())
         };
-        if object.contains_key("url") {
-            let arc_gis_options: ArcGisDataOptions = serde_json::from_str(&json_string)?;
-            return Ok(Self::ArcGis(arc_gis_options));
-        }
-        let Some(file_path) = object.get("file_path").and_then(|p| p.as_str()) else {
-            return Err("Source data options must contain a string \"file_path\" property".into())
-        };
-        let Some(ext) = Path::new(file_path).extension().and_then(|e| e.to_str()) else {
-            return Err(format!("Could not extract a valid file extension for \"file_path\" property of \"{}\"", file_path).into())
+        let format = if let Some(format) = object.get("format") {
+            let Some(format) = format.as_str() else {
+                return Err(format!("Source data options \"format\" property must be a string. Supported formats: {}", registry.formats()).into())
+            };
+            format
+        } else if object.contains_key("url") {
+            "arcgis"
+        } else {
+            let Some(file_path) = object.get("file_path").and_then(|p| p.as_str()) else {
+                return Err("Source data options must contain a string \"file_path\" property".into())
+            };
+            let file_path = Path::new(file_path);
+            let extension_format = file_path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(|ext| registry.extension_format(ext, object));
+            match extension_format {
+                // Text extensions are also used for exports of other formats so the contents are
+                // checked for a binary format or a GeoJSON document
+                Some("delimited") => sniff_format(file_path)?.unwrap_or("delimited"),
+                Some(format) => format,
+                None => match sniff_format(file_path)? {
+                    Some(format) => format,
+                    None => return Err(format!("Could not detect the format of {:?} from the file extension or contents. Specify a \"format\" property with one of: {}", file_path, registry.formats()).into()),
+                },
+            }
         };
-        Ok(match ext {
-            "avro" => Self::Avro(serde_json::from_str(&json_string)?),
-            "txt" | "csv" => Self::Delimited(serde_json::from_str(&json_string)?),
-            "xlsx" | "xls" => Self::Excel(serde_json::from_str(&json_string)?),
-            "geojson" => Self::GeoJson(serde_json::from_str(&json_string)?),
-            "ipc" | "feather" => Self::Ipc(serde_json::from_str(&json_string)?),
-            "parquet" => Self::Parquet(serde_json::from_str(&json_string)?),
-            "shp" => Self::Shape(serde_json::from_str(&json_string)?),
-            _ => return Err(format!("Could not extract a data loader for the extension, \"{}\"", ext).into())
+        let loader = registry.loader(format, options)?;
+        Ok(Self {
+            format: format.to_owned(),
+            loader,
         })
     }
 
-    fn copy_statement(&self, copy_options: CopyOptions) -> String {
-        match self {
-            Self::ArcGis(options) => copy_options.copy_statement(options),
-            Self::Avro(options) => copy_options.copy_statement(options),
-            Self::Delimited(options) => copy_options.copy_statement(options),
-            Self::Excel(options) => copy_options.copy_statement(options),
-            Self::GeoJson(options) => copy_options.copy_statement(options),
-            Self::Ipc(options) => copy_options.copy_statement(options),
-            Self::Parquet(options) => copy_options.copy_statement(options),
-            Self::Shape(options) => copy_options.copy_statement(options),
-        }
+    /// Name of the loader's registered format
+    #[inline]
+    pub fn format(&self) -> &str {
+        &self.format
     }
 
-    pub async fn schema(&self) -> BulkDataResult<Schema> {
-        match self {
-            Self::ArcGis(options) => arc_gis_schema(options).await,
-            Self::Avro(options) => avro_schema(options),
-            Self::Delimited(options) => delimited_schema(options).await,
-            Self::Excel(options) => excel_schema(options),
-            Self::GeoJson(options) => geo_json_schema(options),
-            Self::Ipc(options) => ipc_schema(options),
-            Self::Parquet(options) => parquet_schema(options),
-            Self::Shape(options) => shape_schema(options),
-        }
+    /// Persist the loader's progress under the id, usually the `sd_id` of the source data, so a
+    /// failed load resumes where it stopped. Only ArcGIS scrapes support checkpoints.
+    pub fn with_checkpoint(mut self, id: i64) -> Self {
+        self.loader.enable_checkpoint(id);
+        self
     }
 
-    async fn spool_records(self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
-        match &self {
-            Self::ArcGis(options) => arc_gis_spool_records(options, record_channel).await,
-            Self::Avro(options) => avro_spool_records(options, record_channel).await,
-            Self::Delimited(options) => delimited_spool_records(options, record_channel).await,
-            Self::Excel(options) => excel_spool_records(options, record_channel).await,
-            Self::GeoJson(options) => geo_json_spool_records(options, record_channel).await,
-            Self::Ipc(options) => ipc_spool_records(options, record_channel).await,
-            Self::Parquet(options) => parquet_spool_records(options, record_channel).await,
-            Self::Shape(options) => shape_spool_records(options, record_channel).await,
-        }
+    /// Split the loader into 1 or more loaders that each map to a single table. Only Excel
+    /// loaders that target multiple sheets produce more than 1 loader.
+    pub fn split(self) -> BulkDataResult<Vec<Self>> {
+        let Some(loaders) = self.loader.split()? else {
+            return Ok(vec![self])
+        };
+        Ok(loaders
+            .into_iter()
+            .map(|loader| Self {
+                format: self.format.clone(),
+                loader,
+            })
+            .collect())
     }
 
+    pub async fn schema(&self) -> BulkDataResult<Schema> {
+        self.loader.schema().await
+    }
+
+    /// Copy the loader's records into the table of the copy options. Warnings of the source, such
+    /// as values outside of a range domain, are returned with the number of records loaded.
     pub async fn load_data(self, copy_options: CopyOptions, pool: &PgPool) -> BulkLoadResult {
-        let copy_statement = self.copy_statement(copy_options);
+        let copy_statement = self.loader.copy_statement(&copy_options);
         let mut copy = pool.copy_in_raw(&copy_statement).await?;
         let (mut tx, mut rx) = mpsc_channel(1000);
+        let loader = self.loader;
         let spool_handle = tokio::spawn(async move {
-            let error = self.spool_records(&mut tx).await;
+            let error = loader.spool_records(&mut tx).await;
             drop(tx);
-            error
+            (loader, error)
         });
         let result = loop {
             match rx.recv().await {
@@ -139,13 +192,34 @@ impl DataLoader {
             }
         };
         rx.close();
-        match spool_handle.await {
-            Ok(Some(value)) => println!("SendError\n{:?}", value.0),
-            Ok(None) => println!("Finished spool handle successfully"),
-            Err(error) => println!("Error trying to finish the spool handle\n{}", error),
-        }
+        let loader = match spool_handle.await {
+            Ok((loader, Some(value))) => {
+                println!("SendError\n{:?}", value.0);
+                Some(loader)
+            }
+            Ok((loader, None)) => {
+                println!("Finished spool handle successfully");
+                Some(loader)
+            }
+            Err(error) => {
+                println!("Error trying to finish the spool handle\n{}", error);
+                None
+            }
+        };
         match result {
-            Ok(_) => Ok(copy.finish().await?),
+            Ok(_) => {
+                let count = copy.finish().await?;
+                let mut warnings = loader
+                    .as_ref()
+                    .map(|l| l.take_warnings())
+                    .unwrap_or_default();
+                // Checkpoints are only removed once the copied records are committed. A leftover
+                // checkpoint is replayed by the next load so the load itself still succeeds.
+                if let Some(Err(error)) = loader.map(|l| l.complete_checkpoint()) {
+                    warnings.push(format!("Could not remove the load's checkpoint. {}", error));
+                }
+                Ok(LoadSummary::new(count, warnings))
+            }
             Err(error) => {
                 copy.abort(format!("{}", error)).await?;
                 Err(error)
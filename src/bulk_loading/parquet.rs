@@ -1,27 +1,316 @@
 use super::{
-    analyze::{ColumnType, Schema},
-    error::BulkDataResult,
-    load::{csv_result_iter_to_string, RecordSpoolChannel, RecordSpoolResult},
+    analyze::{ColumnMetadata, ColumnType, GeometryDimension, GeometryMetadata, Schema},
+    crs::{default_target_srid, Crs, GeometryProjector, DEFAULT_TARGET_SRID},
+    error::{BulkDataError, BulkDataResult},
+    geometry::{Coordinate, Geometry},
+    load::{csv_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
+    utilities::map_arrow_value,
+    validation::{GeometryValidation, GeometryWriter},
 };
+use arrow::{
+    array::{
+        Array, ArrayRef, BinaryArray, BooleanArray, FixedSizeListArray, Float32Array, Float64Array,
+        LargeBinaryArray, LargeListArray, ListArray, StringArray, StructArray,
+    },
+    compute::{
+        cast, eq_bool_scalar, eq_scalar, eq_utf8_scalar, gt_eq_scalar, gt_eq_utf8_scalar,
+        gt_scalar, gt_utf8_scalar, lt_eq_scalar, lt_eq_utf8_scalar, lt_scalar, lt_utf8_scalar,
+        neq_bool_scalar, neq_scalar, neq_utf8_scalar,
+    },
+    datatypes::DataType,
+    error::ArrowError,
+    json::writer::record_batches_to_json_rows,
+    record_batch::RecordBatch,
+};
+use async_trait::async_trait;
+use futures::{future, stream, StreamExt};
 use parquet::{
+    arrow::{
+        arrow_reader::{
+            ArrowPredicateFn, ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder, RowFilter,
+        },
+        ProjectionMask,
+    },
     basic::{LogicalType, Type as PhysicalType},
-    file::{reader::FileReader, serialized_reader::SerializedFileReader},
-    record::Field,
+    file::{metadata::FileMetaData, reader::FileReader, serialized_reader::SerializedFileReader},
+    schema::types::SchemaDescriptor,
 };
 use serde::{Deserialize, Serialize};
-use std::{fs::File, path::PathBuf, sync::Arc};
-use wkb::wkb_to_geom;
-use wkt::ToWkt;
+use serde_json::Value;
+use std::{collections::HashMap, fs::File, path::PathBuf, sync::Arc};
+use tokio::sync::mpsc::{channel as mpsc_channel, Sender};
+
+/// Maximum number of rows in each record batch read from a row group
+const BATCH_SIZE: usize = 8192;
+/// Number of row groups read at the same time. Rows are still sent in row group order
+const ROW_GROUP_CONCURRENCY: usize = 4;
+/// Number of batches of CSV rows buffered by each row group reader
+const ROW_GROUP_BUFFER: usize = 2;
+
+/// Encoding of a GeoParquet geometry column. Native encodings follow the GeoArrow memory layouts
+/// with coordinates stored as structs of `x`, `y`, `z` and `m` values or as lists of values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+enum GeoParquetEncoding {
+    #[serde(rename = "WKB")]
+    Wkb,
+    #[serde(rename = "point")]
+    Point,
+    #[serde(rename = "linestring")]
+    LineString,
+    #[serde(rename = "polygon")]
+    Polygon,
+    #[serde(rename = "multipoint")]
+    MultiPoint,
+    #[serde(rename = "multilinestring")]
+    MultiLineString,
+    #[serde(rename = "multipolygon")]
+    MultiPolygon,
+}
+
+fn default_geo_parquet_crs() -> Value {
+    Value::String(String::from("OGC:CRS84"))
+}
+
+/// Description of a geometry column found in the GeoParquet `geo` metadata
+#[derive(Debug, Deserialize)]
+struct GeoParquetColumn {
+    encoding: GeoParquetEncoding,
+    #[serde(default)]
+    geometry_types: Vec<String>,
+    #[serde(default = "default_geo_parquet_crs")]
+    crs: Value,
+}
+
+impl GeoParquetColumn {
+    /// Column used by files without `geo` metadata. Only WKB columns named `geometry` are found
+    /// and their CRS is unknown.
+    fn legacy() -> Self {
+        Self {
+            encoding: GeoParquetEncoding::Wkb,
+            geometry_types: Vec::new(),
+            crs: Value::Null,
+        }
+    }
+
+    /// CRS of the column. A column without a `crs` key is OGC:CRS84 while a `null` CRS is
+    /// unknown.
+    fn crs(&self) -> BulkDataResult<Option<Crs>> {
+        match &self.crs {
+            Value::Null => Ok(None),
+            crs => Ok(Some(Crs::from_projjson(crs)?)),
+        }
+    }
+
+    /// Dimension shared by every geometry type of the column. Columns without geometry types
+    /// are treated as 2D.
+    fn dimension(&self) -> GeometryDimension {
+        if self.geometry_types.is_empty() {
+            return GeometryDimension::Xy;
+        }
+        let suffix = |geometry_type: &String| -> GeometryDimension {
+            match geometry_type.rsplit_once(' ').map(|(_, suffix)| suffix) {
+                Some("Z") => GeometryDimension::Xyz,
+                Some("M") => GeometryDimension::Xym,
+                Some("ZM") => GeometryDimension::Xyzm,
+                _ => GeometryDimension::Xy,
+            }
+        };
+        let dimension = suffix(&self.geometry_types[0]);
+        if self.geometry_types.iter().all(|t| suffix(t) == dimension) {
+            dimension
+        } else {
+            GeometryDimension::Xy
+        }
+    }
+
+    fn metadata(&self, srid: Option<u16>) -> GeometryMetadata {
+        GeometryMetadata::new(self.dimension(), srid)
+            .with_geometry_types(self.geometry_types.clone())
+    }
+}
+
+/// GeoParquet file metadata stored as JSON under the `geo` key of the file's key-value metadata
+#[derive(Debug, Deserialize)]
+struct GeoParquetMetadata {
+    primary_column: String,
+    columns: HashMap<String, GeoParquetColumn>,
+}
+
+/// Geometry columns of the file keyed by name. Files without `geo` metadata fall back to a
+/// `geometry` byte array column when one exists.
+fn geometry_columns(
+    file_metadata: &FileMetaData,
+) -> BulkDataResult<HashMap<String, GeoParquetColumn>> {
+    let geo_metadata = file_metadata
+        .key_value_metadata()
+        .and_then(|metadata| metadata.iter().find(|kv| kv.key == "geo"))
+        .and_then(|kv| kv.value.as_ref());
+    let Some(geo_metadata) = geo_metadata else {
+        let has_legacy_column = file_metadata.schema().get_fields().iter().any(|field| {
+            field.name() == "geometry"
+                && field.is_primitive()
+                && field.get_physical_type() == PhysicalType::BYTE_ARRAY
+        });
+        let mut columns = HashMap::new();
+        if has_legacy_column {
+            columns.insert(String::from("geometry"), GeoParquetColumn::legacy());
+        }
+        return Ok(columns)
+    };
+    let geo_metadata: GeoParquetMetadata = serde_json::from_str(geo_metadata)?;
+    if !geo_metadata
+        .columns
+        .contains_key(&geo_metadata.primary_column)
+    {
+        return Err(format!(
+            "GeoParquet metadata does not describe the primary column \"{}\"",
+            geo_metadata.primary_column
+        )
+        .into());
+    }
+    Ok(geo_metadata.columns)
+}
+
+/// Geometry column of a Parquet file along with the writer of its values
+struct ParquetGeometryColumn {
+    encoding: GeoParquetEncoding,
+    writer: GeometryWriter,
+}
+
+impl ParquetGeometryColumn {
+    fn csv_values(&self, array: &dyn Array, index: usize) -> BulkDataResult<Vec<String>> {
+        let geometry = if array.is_null(index) {
+            None
+        } else {
+            Some(decode_geometry(array, index, self.encoding)?)
+        };
+        self.writer.csv_values(geometry)
+    }
+}
+
+/// Comparison operator of a [ParquetFilter]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParquetFilterOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Predicate applied to the rows of a Parquet file before they are spooled. Rows where the
+/// column is null are filtered out.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParquetFilter {
+    column: String,
+    operator: ParquetFilterOperator,
+    value: Value,
+}
+
+fn downcast_array<'a, T: 'static>(array: &'a ArrayRef) -> Result<&'a T, ArrowError> {
+    array.as_any().downcast_ref::<T>().ok_or_else(|| {
+        ArrowError::CastError(format!("Could not downcast {} array", array.data_type()))
+    })
+}
+
+impl ParquetFilter {
+    pub fn new(column: &str, operator: ParquetFilterOperator, value: Value) -> Self {
+        Self {
+            column: column.to_owned(),
+            operator,
+            value,
+        }
+    }
+
+    /// Rows of the column that match the filter. Numbers and strings are compared after casting
+    /// the column to the value's type, so dates and timestamps can be filtered using ISO 8601
+    /// strings.
+    fn evaluate(&self, array: &ArrayRef) -> Result<BooleanArray, ArrowError> {
+        match &self.value {
+            Value::Number(number) => {
+                let Some(value) = number.as_f64() else {
+                    return Err(ArrowError::InvalidArgumentError(format!("Filter value {} is not a valid float", number)))
+                };
+                let array = cast(array, &DataType::Float64)?;
+                let array = downcast_array::<Float64Array>(&array)?;
+                match self.operator {
+                    ParquetFilterOperator::Eq => eq_scalar(array, value),
+                    ParquetFilterOperator::Ne => neq_scalar(array, value),
+                    ParquetFilterOperator::Lt => lt_scalar(array, value),
+                    ParquetFilterOperator::Le => lt_eq_scalar(array, value),
+                    ParquetFilterOperator::Gt => gt_scalar(array, value),
+                    ParquetFilterOperator::Ge => gt_eq_scalar(array, value),
+                }
+            }
+            Value::String(value) => {
+                let array = cast(array, &DataType::Utf8)?;
+                let array = downcast_array::<StringArray>(&array)?;
+                match self.operator {
+                    ParquetFilterOperator::Eq => eq_utf8_scalar(array, value),
+                    ParquetFilterOperator::Ne => neq_utf8_scalar(array, value),
+                    ParquetFilterOperator::Lt => lt_utf8_scalar(array, value),
+                    ParquetFilterOperator::Le => lt_eq_utf8_scalar(array, value),
+                    ParquetFilterOperator::Gt => gt_utf8_scalar(array, value),
+                    ParquetFilterOperator::Ge => gt_eq_utf8_scalar(array, value),
+                }
+            }
+            Value::Bool(value) => {
+                let array = cast(array, &DataType::Boolean)?;
+                let array = downcast_array::<BooleanArray>(&array)?;
+                match self.operator {
+                    ParquetFilterOperator::Eq => eq_bool_scalar(array, *value),
+                    ParquetFilterOperator::Ne => neq_bool_scalar(array, *value),
+                    _ => Err(ArrowError::InvalidArgumentError(String::from(
+                        "Boolean filter values only support the eq and ne operators",
+                    ))),
+                }
+            }
+            _ => Err(ArrowError::InvalidArgumentError(format!(
+                "Filter value {} must be a number, string or boolean",
+                self.value
+            ))),
+        }
+    }
+}
 
-#[derive(Deserialize, Serialize)]
+/// Index of the named column within the root fields of the Parquet schema
+fn root_column_index(schema: &SchemaDescriptor, name: &str) -> BulkDataResult<usize> {
+    let fields = schema.root_schema().get_fields();
+    match fields.iter().position(|field| field.name() == name) {
+        Some(index) => Ok(index),
+        None => Err(format!("Could not find column \"{}\" in the Parquet file", name).into()),
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct ParquetFileOptions {
     file_path: PathBuf,
+    #[serde(default = "default_target_srid")]
+    target_srid: u16,
+    #[serde(default)]
+    geometry_validation: GeometryValidation,
+    #[serde(default)]
+    columns: Option<Vec<String>>,
+    #[serde(default)]
+    row_groups: Option<Vec<usize>>,
+    #[serde(default)]
+    filter: Option<ParquetFilter>,
 }
 
 impl ParquetFileOptions {
     pub fn new(file_path: PathBuf) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            target_srid: DEFAULT_TARGET_SRID,
+            geometry_validation: GeometryValidation::default(),
+            columns: None,
+            row_groups: None,
+            filter: None,
+        }
     }
 
     pub fn reader(&self) -> BulkDataResult<SerializedFileReader<File>> {
@@ -29,6 +318,140 @@ impl ParquetFileOptions {
         let reader = SerializedFileReader::new(file)?;
         Ok(reader)
     }
+
+    fn projector(&self, column: &GeoParquetColumn) -> BulkDataResult<GeometryProjector> {
+        GeometryProjector::new(column.crs()?.as_ref(), self.target_srid)
+    }
+
+    fn geometry_readers(
+        &self,
+        file_metadata: &FileMetaData,
+    ) -> BulkDataResult<HashMap<String, ParquetGeometryColumn>> {
+        geometry_columns(file_metadata)?
+            .into_iter()
+            .map(
+                |(name, column)| -> BulkDataResult<(String, ParquetGeometryColumn)> {
+                    let geometry_column = ParquetGeometryColumn {
+                        encoding: column.encoding,
+                        writer: GeometryWriter::new(
+                            self.projector(&column)?,
+                            self.geometry_validation,
+                        ),
+                    };
+                    Ok((name, geometry_column))
+                },
+            )
+            .collect()
+    }
+
+    /// Check that the projected and filtered columns exist in the file
+    fn validate(&self, schema: &SchemaDescriptor) -> BulkDataResult<()> {
+        let filter_column = self.filter.as_ref().map(|filter| &filter.column);
+        for name in self.columns.iter().flatten().chain(filter_column) {
+            root_column_index(schema, name)?;
+        }
+        Ok(())
+    }
+
+    /// True when the column is included in the `columns` projection
+    fn is_projected(&self, name: &str) -> bool {
+        match &self.columns {
+            Some(columns) => columns.iter().any(|column| column == name),
+            None => true,
+        }
+    }
+
+    fn projection(&self, schema: &SchemaDescriptor) -> BulkDataResult<ProjectionMask> {
+        let Some(columns) = &self.columns else {
+            return Ok(ProjectionMask::all())
+        };
+        let indices = columns
+            .iter()
+            .map(|name| root_column_index(schema, name))
+            .collect::<BulkDataResult<Vec<_>>>()?;
+        Ok(ProjectionMask::roots(schema, indices))
+    }
+
+    fn row_filter(&self, schema: &SchemaDescriptor) -> BulkDataResult<Option<RowFilter>> {
+        let Some(filter) = &self.filter else {
+            return Ok(None)
+        };
+        let index = root_column_index(schema, &filter.column)?;
+        let filter = filter.clone();
+        let predicate = ArrowPredicateFn::new(
+            ProjectionMask::roots(schema, [index]),
+            move |batch: RecordBatch| filter.evaluate(batch.column(0)),
+        );
+        Ok(Some(RowFilter::new(vec![Box::new(predicate)])))
+    }
+
+    /// Row groups to read. All row groups are read when the options do not specify any
+    fn row_groups(&self, row_group_count: usize) -> BulkDataResult<Vec<usize>> {
+        let Some(row_groups) = &self.row_groups else {
+            return Ok((0..row_group_count).collect())
+        };
+        if let Some(row_group) = row_groups.iter().find(|r| **r >= row_group_count) {
+            return Err(format!(
+                "Row group {} does not exist. File contains {} row groups",
+                row_group, row_group_count
+            )
+            .into());
+        }
+        Ok(row_groups.clone())
+    }
+
+    /// Row groups selected by the options, checked against the file's row group count
+    fn selected_row_groups(&self) -> BulkDataResult<Vec<usize>> {
+        self.row_groups(self.reader()?.metadata().num_row_groups())
+    }
+
+    /// Build a reader of the row group, in batches, applying the column projection and row filter
+    fn batch_reader(
+        &self,
+        row_group: usize,
+    ) -> BulkDataResult<(
+        ParquetRecordBatchReader,
+        HashMap<String, ParquetGeometryColumn>,
+    )> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&self.file_path)?)?;
+        let geometry_columns = self.geometry_readers(builder.metadata().file_metadata())?;
+        let projection = self.projection(builder.parquet_schema())?;
+        let row_filter = self.row_filter(builder.parquet_schema())?;
+        let mut builder = builder
+            .with_projection(projection)
+            .with_row_groups(vec![row_group])
+            .with_batch_size(BATCH_SIZE);
+        if let Some(row_filter) = row_filter {
+            builder = builder.with_row_filter(row_filter);
+        }
+        Ok((builder.build()?, geometry_columns))
+    }
+
+    /// Send the CSV rows of each batch in the row group as it is read. The channel is bounded so
+    /// only a few batches are held in memory. Must be called from a blocking thread.
+    fn spool_row_group(
+        &self,
+        row_group: usize,
+        batch_channel: Sender<BulkDataResult<Vec<String>>>,
+    ) {
+        let (reader, geometry_columns) = match self.batch_reader(row_group) {
+            Ok(reader) => reader,
+            Err(error) => {
+                let _ = batch_channel.blocking_send(Err(error));
+                return;
+            }
+        };
+        for batch in reader {
+            let rows = batch
+                .map_err(BulkDataError::from)
+                .and_then(|batch| batch_csv_rows(&batch, &geometry_columns));
+            let is_error = rows.is_err();
+            // A closed channel means the spool stopped so the rest of the row group is skipped
+            if batch_channel.blocking_send(rows).is_err() || is_error {
+                return;
+            }
+        }
+    }
 }
 
 impl DataOptions for ParquetFileOptions {}
@@ -56,6 +479,7 @@ impl From<&Arc<parquet::schema::types::Type>> for ColumnType {
             Some(LogicalType::Bson) => ColumnType::Json,
             Some(LogicalType::Json) => ColumnType::Json,
             Some(LogicalType::Uuid) => ColumnType::UUID,
+            _ if !field.is_primitive() => ColumnType::Json,
             _ => match field.get_physical_type() {
                 PhysicalType::BOOLEAN => ColumnType::Boolean,
                 PhysicalType::INT32 => ColumnType::Integer,
@@ -63,13 +487,7 @@ impl From<&Arc<parquet::schema::types::Type>> for ColumnType {
                 PhysicalType::INT96 => ColumnType::BigInt,
                 PhysicalType::FLOAT => ColumnType::Real,
                 PhysicalType::DOUBLE => ColumnType::DoublePrecision,
-                PhysicalType::BYTE_ARRAY => {
-                    if field.name() == "geometry" {
-                        ColumnType::Geometry
-                    } else {
-                        ColumnType::Text
-                    }
-                }
+                PhysicalType::BYTE_ARRAY => ColumnType::Text,
                 PhysicalType::FIXED_LEN_BYTE_ARRAY => ColumnType::Text,
             },
         }
@@ -81,60 +499,407 @@ pub fn schema(options: &ParquetFileOptions) -> BulkDataResult<Schema> {
         return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
     };
     let reader = options.reader()?;
-    let columns = reader
-        .metadata()
-        .file_metadata()
+    let file_metadata = reader.metadata().file_metadata();
+    let geometry_columns = geometry_columns(file_metadata)?;
+    options.validate(file_metadata.schema_descr())?;
+    let columns = file_metadata
         .schema()
         .get_fields()
         .iter()
-        .map(|field| {
-            let name = field.name();
-            let actual_type = field.into();
-            (name, actual_type)
-        });
-    Schema::from_iter(table_name, columns)
+        .filter(|field| options.is_projected(field.name()))
+        .map(|field| -> BulkDataResult<Vec<ColumnMetadata>> {
+            match geometry_columns.get(field.name()) {
+                Some(column) => {
+                    let metadata = column.metadata(options.projector(column)?.srid());
+                    options
+                        .geometry_validation
+                        .geometry_columns(field.name(), metadata)
+                }
+                None => Ok(vec![ColumnMetadata::new(field.name(), field.into())?]),
+            }
+        })
+        .collect::<BulkDataResult<Vec<_>>>()?;
+    Schema::new(table_name, columns.into_iter().flatten().collect())
+}
+
+fn float_at(array: &dyn Array, index: usize) -> BulkDataResult<f64> {
+    if let Some(values) = array.as_any().downcast_ref::<Float64Array>() {
+        return Ok(values.value(index));
+    }
+    if let Some(values) = array.as_any().downcast_ref::<Float32Array>() {
+        return Ok(f64::from(values.value(index)));
+    }
+    Err(format!(
+        "Expected a floating point coordinate value but found {}",
+        array.data_type()
+    )
+    .into())
 }
 
-fn map_parquet_field(name: &String, field: &Field) -> BulkDataResult<String> {
-    Ok(match field {
-        Field::Null => String::new(),
-        Field::Bytes(b) => {
-            if name == "geometry" {
-                wkb_to_geom(&mut b.data())?.wkt_string()
-            } else {
-                format!("{}", b)
+/// Values of a native encoded list array at `index`
+fn list_at(array: &dyn Array, index: usize) -> BulkDataResult<ArrayRef> {
+    if let Some(list) = array.as_any().downcast_ref::<ListArray>() {
+        return Ok(list.value(index));
+    }
+    if let Some(list) = array.as_any().downcast_ref::<LargeListArray>() {
+        return Ok(list.value(index));
+    }
+    Err(format!(
+        "Expected a list of geometry parts but found {}",
+        array.data_type()
+    )
+    .into())
+}
+
+/// Convert a native encoded coordinate to a [Coordinate]. Coordinates are either structs of
+/// named ordinates or interleaved fixed size lists of 2 to 4 values.
+fn coordinate_at(array: &dyn Array, index: usize) -> BulkDataResult<Coordinate> {
+    if let Some(points) = array.as_any().downcast_ref::<StructArray>() {
+        let ordinate = |name: &str| -> BulkDataResult<Option<f64>> {
+            match points.column_by_name(name) {
+                Some(values) if values.is_null(index) => Ok(None),
+                Some(values) => float_at(values.as_ref(), index).map(Some),
+                None => Ok(None),
             }
+        };
+        let (Some(x), Some(y)) = (ordinate("x")?, ordinate("y")?) else {
+            return Err("Coordinate struct must contain x and y values".into())
+        };
+        return Ok(Coordinate::new(x, y, ordinate("z")?, ordinate("m")?));
+    }
+    if let Some(points) = array.as_any().downcast_ref::<FixedSizeListArray>() {
+        let values = points.value(index);
+        let ordinates = (0..values.len())
+            .map(|i| float_at(values.as_ref(), i))
+            .collect::<BulkDataResult<Vec<_>>>()?;
+        return match ordinates[..] {
+            [x, y] => Ok(Coordinate::new(x, y, None, None)),
+            [x, y, z] => Ok(Coordinate::new(x, y, Some(z), None)),
+            [x, y, z, m] => Ok(Coordinate::new(x, y, Some(z), Some(m))),
+            _ => Err(format!(
+                "Coordinate list must contain 2 to 4 values. Found {}",
+                ordinates.len()
+            )
+            .into()),
+        };
+    }
+    Err(format!("Expected a coordinate but found {}", array.data_type()).into())
+}
+
+fn coordinates_at(array: &dyn Array, index: usize) -> BulkDataResult<Vec<Coordinate>> {
+    let points = list_at(array, index)?;
+    (0..points.len())
+        .map(|i| coordinate_at(points.as_ref(), i))
+        .collect()
+}
+
+fn rings_at(array: &dyn Array, index: usize) -> BulkDataResult<Vec<Vec<Coordinate>>> {
+    let rings = list_at(array, index)?;
+    (0..rings.len())
+        .map(|i| coordinates_at(rings.as_ref(), i))
+        .collect()
+}
+
+fn wkb_at(array: &dyn Array, index: usize) -> BulkDataResult<&[u8]> {
+    if let Some(values) = array.as_any().downcast_ref::<BinaryArray>() {
+        return Ok(values.value(index));
+    }
+    if let Some(values) = array.as_any().downcast_ref::<LargeBinaryArray>() {
+        return Ok(values.value(index));
+    }
+    Err(format!("Expected WKB bytes but found {}", array.data_type()).into())
+}
+
+/// Decode the non-null geometry at `index` using the column's encoding
+fn decode_geometry(
+    array: &dyn Array,
+    index: usize,
+    encoding: GeoParquetEncoding,
+) -> BulkDataResult<Geometry> {
+    Ok(match encoding {
+        GeoParquetEncoding::Wkb => Geometry::from_wkb(wkb_at(array, index)?)?,
+        GeoParquetEncoding::Point => Geometry::Point(coordinate_at(array, index)?),
+        GeoParquetEncoding::LineString => Geometry::LineString(coordinates_at(array, index)?),
+        GeoParquetEncoding::Polygon => Geometry::Polygon(rings_at(array, index)?),
+        GeoParquetEncoding::MultiPoint => Geometry::MultiPoint(coordinates_at(array, index)?),
+        GeoParquetEncoding::MultiLineString => Geometry::MultiLineString(rings_at(array, index)?),
+        GeoParquetEncoding::MultiPolygon => {
+            let polygons = list_at(array, index)?;
+            Geometry::MultiPolygon(
+                (0..polygons.len())
+                    .map(|i| rings_at(polygons.as_ref(), i))
+                    .collect::<BulkDataResult<_>>()?,
+            )
         }
-        Field::Group(_) | Field::ListInternal(_) | Field::MapInternal(_) => {
-            format!("{}", field.to_json_value())
-        }
-        Field::Str(s) => s.to_string(),
-        _ => field.to_string(),
     })
 }
 
+#[inline]
+fn is_nested(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::Struct(_)
+            | DataType::Map(_, _)
+    )
+}
+
+/// CSV rows of a record batch. Geometry columns are written by their [GeometryWriter] so they can
+/// produce more than 1 value and nested columns are written as JSON.
+fn batch_csv_rows(
+    batch: &RecordBatch,
+    geometry_columns: &HashMap<String, ParquetGeometryColumn>,
+) -> BulkDataResult<Vec<String>> {
+    let schema = batch.schema();
+    let nested_columns: Vec<usize> = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| {
+            is_nested(field.data_type()) && !geometry_columns.contains_key(field.name())
+        })
+        .map(|(i, _)| i)
+        .collect();
+    let json_rows = if nested_columns.is_empty() {
+        Vec::new()
+    } else {
+        record_batches_to_json_rows(&[batch.project(&nested_columns)?])?
+    };
+    (0..batch.num_rows())
+        .map(|row| {
+            let mut values = Vec::with_capacity(schema.fields().len());
+            for (field, column) in schema.fields().iter().zip(batch.columns()) {
+                if let Some(geometry_column) = geometry_columns.get(field.name()) {
+                    values.extend(geometry_column.csv_values(column.as_ref(), row)?);
+                    continue;
+                }
+                if is_nested(field.data_type()) && !column.is_null(row) {
+                    let value = json_rows[row]
+                        .get(field.name())
+                        .map(|value| value.to_string())
+                        .unwrap_or_default();
+                    values.push(value);
+                } else {
+                    values.push(map_arrow_value(column, row)?);
+                }
+            }
+            Ok(csv_iter_to_string(values.into_iter()))
+        })
+        .collect()
+}
+
 pub async fn spool_records(
     options: &ParquetFileOptions,
     record_channel: &mut RecordSpoolChannel,
 ) -> RecordSpoolResult {
-    let reader = match options.reader() {
-        Ok(r) => r,
+    let row_groups = match options.selected_row_groups() {
+        Ok(row_groups) => row_groups,
         Err(error) => return record_channel.send(Err(error)).await.err(),
     };
-    let iter = match reader.get_row_iter(None) {
-        Ok(iter) => iter,
-        Err(error) => return record_channel.send(Err(error.into())).await.err(),
-    };
-    for row in iter {
-        let csv_iter = row
-            .get_column_iter()
-            .map(|(name, field)| map_parquet_field(name, field));
-        let result = record_channel
-            .send(csv_result_iter_to_string(csv_iter))
-            .await;
-        if let Err(error) = result {
-            return Some(error);
+    // Each row group is read on its own blocking thread. Readers are started ahead of the row
+    // group being sent but their bounded channels are drained in row group order.
+    let mut row_group_readers = stream::iter(row_groups)
+        .map(|row_group| {
+            let reader_options = options.clone();
+            let (batch_channel, batch_receiver) = mpsc_channel(ROW_GROUP_BUFFER);
+            let handle = tokio::task::spawn_blocking(move || {
+                reader_options.spool_row_group(row_group, batch_channel)
+            });
+            future::ready((batch_receiver, handle))
+        })
+        .buffered(ROW_GROUP_CONCURRENCY);
+    while let Some((mut batch_receiver, handle)) = row_group_readers.next().await {
+        while let Some(rows) = batch_receiver.recv().await {
+            let rows = match rows {
+                Ok(rows) => rows,
+                Err(error) => return record_channel.send(Err(error)).await.err(),
+            };
+            for row in rows {
+                if let Err(error) = record_channel.send(Ok(row)).await {
+                    return Some(error);
+                }
+            }
+        }
+        if let Err(error) = handle.await {
+            let error = BulkDataError::from(format!("Parquet reader failed. {}", error));
+            return record_channel.send(Err(error)).await.err();
         }
     }
     None
 }
+
+#[async_trait]
+impl SourceLoader for ParquetFileOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::{
+        array::Int32Array,
+        datatypes::{Field as ArrowField, Schema as ArrowSchema},
+    };
+    use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+    use serde_json::json;
+    use tokio::sync::mpsc::channel;
+
+    fn geo_parquet_column(geometry_types: &[&str]) -> GeoParquetColumn {
+        GeoParquetColumn {
+            encoding: GeoParquetEncoding::Wkb,
+            geometry_types: geometry_types.iter().map(|t| t.to_string()).collect(),
+            crs: default_geo_parquet_crs(),
+        }
+    }
+
+    #[test]
+    fn geo_parquet_metadata_should_deserialize_secondary_columns() -> BulkDataResult<()> {
+        let json = r#"{
+            "version": "1.0.0",
+            "primary_column": "geom",
+            "columns": {
+                "geom": {"encoding": "WKB", "geometry_types": ["Polygon", "MultiPolygon"]},
+                "centroid": {"encoding": "point", "geometry_types": ["Point"], "crs": null}
+            }
+        }"#;
+
+        let actual: GeoParquetMetadata = serde_json::from_str(json)?;
+
+        assert_eq!("geom", actual.primary_column);
+        let geom = &actual.columns["geom"];
+        assert_eq!(GeoParquetEncoding::Wkb, geom.encoding);
+        assert_eq!(Some(Crs::Epsg(4326)), geom.crs()?);
+        let centroid = &actual.columns["centroid"];
+        assert_eq!(GeoParquetEncoding::Point, centroid.encoding);
+        assert_eq!(None, centroid.crs()?);
+        Ok(())
+    }
+
+    #[test]
+    fn dimension_should_return_xyz_when_all_geometry_types_have_z() {
+        let column = geo_parquet_column(&["Point Z", "MultiPoint Z"]);
+
+        let actual = column.dimension();
+
+        assert_eq!(GeometryDimension::Xyz, actual);
+    }
+
+    #[test]
+    fn dimension_should_return_xy_when_geometry_types_mixed() {
+        let column = geo_parquet_column(&["LineString Z", "LineString"]);
+
+        let actual = column.dimension();
+
+        assert_eq!(GeometryDimension::Xy, actual);
+    }
+
+    #[test]
+    fn dimension_should_return_xy_when_geometry_types_empty() {
+        let column = geo_parquet_column(&[]);
+
+        let actual = column.dimension();
+
+        assert_eq!(GeometryDimension::Xy, actual);
+    }
+
+    #[test]
+    fn evaluate_should_compare_numbers_when_filter_value_number() -> BulkDataResult<()> {
+        let filter = ParquetFilter::new("test", ParquetFilterOperator::Ge, json!(2));
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(2), None, Some(3)]));
+
+        let actual = filter.evaluate(&array)?;
+
+        let expected = BooleanArray::from(vec![Some(false), Some(true), None, Some(true)]);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_should_compare_strings_when_filter_value_string() -> BulkDataResult<()> {
+        let filter = ParquetFilter::new("test", ParquetFilterOperator::Eq, json!("b"));
+        let array: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c"]));
+
+        let actual = filter.evaluate(&array)?;
+
+        let expected = BooleanArray::from(vec![false, true, false]);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_should_fail_when_boolean_compared_with_lt() {
+        let filter = ParquetFilter::new("test", ParquetFilterOperator::Lt, json!(true));
+        let array: ArrayRef = Arc::new(BooleanArray::from(vec![true, false]));
+
+        let actual = filter.evaluate(&array);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn decode_geometry_should_read_struct_coordinates_when_point_encoding() -> BulkDataResult<()> {
+        let x: ArrayRef = Arc::new(Float64Array::from(vec![1.0, 4.0]));
+        let y: ArrayRef = Arc::new(Float64Array::from(vec![2.0, 5.0]));
+        let z: ArrayRef = Arc::new(Float64Array::from(vec![3.0, 6.0]));
+        let points = StructArray::from(vec![
+            (ArrowField::new("x", DataType::Float64, false), x),
+            (ArrowField::new("y", DataType::Float64, false), y),
+            (ArrowField::new("z", DataType::Float64, false), z),
+        ]);
+
+        let actual = decode_geometry(&points, 1, GeoParquetEncoding::Point)?;
+
+        assert_eq!(
+            Geometry::Point(Coordinate::new(4.0, 5.0, Some(6.0), None)),
+            actual
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spool_records_should_send_rows_in_row_group_order_when_groups_read_in_parallel(
+    ) -> BulkDataResult<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let file_path = temp_dir.path().join("test.parquet");
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "id",
+            DataType::Int32,
+            false,
+        )]));
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(10)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&file_path)?, schema.clone(), Some(properties))?;
+        let ids: ArrayRef = Arc::new(Int32Array::from((0..95).collect::<Vec<i32>>()));
+        writer.write(&RecordBatch::try_new(schema, vec![ids])?)?;
+        writer.close()?;
+        let options = ParquetFileOptions::new(file_path);
+        let (mut tx, mut rx) = channel(1000);
+
+        let send_error = spool_records(&options, &mut tx).await;
+        drop(tx);
+        let mut rows = Vec::new();
+        while let Some(row) = rx.recv().await {
+            rows.push(row?);
+        }
+
+        assert!(send_error.is_none());
+        assert_eq!(10, options.selected_row_groups()?.len());
+        let expected: Vec<String> = (0..95).map(|id| format!("{}\n", id)).collect();
+        assert_eq!(expected, rows);
+        Ok(())
+    }
+}
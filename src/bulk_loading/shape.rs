@@ -1,29 +1,186 @@
 use super::{
-    analyze::{ColumnMetadata, ColumnType, Schema},
+    analyze::{ColumnMetadata, GeometryDimension, GeometryMetadata, Schema},
+    crs::{default_target_srid, Crs, GeometryProjector, DEFAULT_TARGET_SRID},
+    dbf::{read_field_descriptors, BigIntFieldReader},
     error::BulkDataResult,
-    load::{csv_result_iter_to_string, RecordSpoolChannel, RecordSpoolResult},
+    geometry::{Coordinate, Geometry},
+    load::{csv_result_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
     options::DataOptions,
+    source::SourceLoader,
+    validation::{GeometryValidation, GeometryWriter},
 };
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use shapefile::{
     dbase::{FieldInfo, FieldValue, Reader as DbfReader},
-    Reader, Shape,
+    Point, PointM, PointZ, PolygonRing, Reader, Shape,
 };
 use std::{fs::File, io::BufReader, path::PathBuf};
-use wkt::ToWkt;
+
+/// Measures less than this value are considered "no data" by the shapefile specification
+const NO_DATA_LIMIT: f64 = -1e38;
+
+fn measure(m: f64) -> Option<f64> {
+    if m < NO_DATA_LIMIT {
+        None
+    } else {
+        Some(m)
+    }
+}
+
+impl From<&Point> for Coordinate {
+    fn from(point: &Point) -> Self {
+        Coordinate::new(point.x, point.y, None, None)
+    }
+}
+
+impl From<&PointM> for Coordinate {
+    fn from(point: &PointM) -> Self {
+        Coordinate::new(point.x, point.y, None, measure(point.m))
+    }
+}
+
+impl From<&PointZ> for Coordinate {
+    fn from(point: &PointZ) -> Self {
+        Coordinate::new(point.x, point.y, Some(point.z), measure(point.m))
+    }
+}
+
+fn points_to_coordinates<P>(points: &[P]) -> Vec<Coordinate>
+where
+    for<'p> Coordinate: From<&'p P>,
+{
+    points.iter().map(Coordinate::from).collect()
+}
+
+fn parts_to_geometry<P>(parts: &[Vec<P>]) -> Geometry
+where
+    for<'p> Coordinate: From<&'p P>,
+{
+    let mut lines: Vec<Vec<Coordinate>> = parts.iter().map(|p| points_to_coordinates(p)).collect();
+    if lines.len() == 1 {
+        Geometry::LineString(lines.remove(0))
+    } else {
+        Geometry::MultiLineString(lines)
+    }
+}
+
+/// Group the rings of a shapefile polygon into polygons. Each outer ring starts a new polygon and
+/// the inner rings that follow are the holes of that polygon.
+fn rings_to_geometry<P>(rings: &[PolygonRing<P>]) -> Geometry
+where
+    for<'p> Coordinate: From<&'p P>,
+{
+    let mut polygons: Vec<Vec<Vec<Coordinate>>> = Vec::new();
+    for ring in rings {
+        let coordinates = points_to_coordinates(ring.points());
+        match (ring, polygons.last_mut()) {
+            (PolygonRing::Inner(_), Some(polygon)) => polygon.push(coordinates),
+            _ => polygons.push(vec![coordinates]),
+        }
+    }
+    if polygons.len() == 1 {
+        Geometry::Polygon(polygons.remove(0))
+    } else {
+        Geometry::MultiPolygon(polygons)
+    }
+}
+
+/// Convert a shape into a [Geometry], keeping any Z and M values. [Shape::NullShape] returns
+/// [None].
+fn shape_to_geometry(shape: &Shape) -> BulkDataResult<Option<Geometry>> {
+    let geometry = match shape {
+        Shape::NullShape => return Ok(None),
+        Shape::Point(point) => Geometry::Point(point.into()),
+        Shape::PointM(point) => Geometry::Point(point.into()),
+        Shape::PointZ(point) => Geometry::Point(point.into()),
+        Shape::Multipoint(multipoint) => {
+            Geometry::MultiPoint(points_to_coordinates(multipoint.points()))
+        }
+        Shape::MultipointM(multipoint) => {
+            Geometry::MultiPoint(points_to_coordinates(multipoint.points()))
+        }
+        Shape::MultipointZ(multipoint) => {
+            Geometry::MultiPoint(points_to_coordinates(multipoint.points()))
+        }
+        Shape::Polyline(polyline) => parts_to_geometry(polyline.parts()),
+        Shape::PolylineM(polyline) => parts_to_geometry(polyline.parts()),
+        Shape::PolylineZ(polyline) => parts_to_geometry(polyline.parts()),
+        Shape::Polygon(polygon) => rings_to_geometry(polygon.rings()),
+        Shape::PolygonM(polygon) => rings_to_geometry(polygon.rings()),
+        Shape::PolygonZ(polygon) => rings_to_geometry(polygon.rings()),
+        Shape::Multipatch(_) => return Err("Multipatch shapes are not supported".into()),
+    };
+    Ok(Some(geometry))
+}
 
 #[derive(Deserialize, Serialize)]
 pub struct ShapeDataOptions {
     file_path: PathBuf,
+    #[serde(default = "default_target_srid")]
+    target_srid: u16,
+    #[serde(default)]
+    geometry_validation: GeometryValidation,
+    /// SRID of the shapefile's coordinates, used instead of the `.prj` file
+    #[serde(default)]
+    source_srid: Option<u16>,
 }
 
 impl ShapeDataOptions {
     pub fn new(file_path: PathBuf) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            target_srid: DEFAULT_TARGET_SRID,
+            geometry_validation: GeometryValidation::default(),
+            source_srid: None,
+        }
+    }
+
+    /// Read the coordinates as the SRID instead of the CRS in the `.prj` file
+    pub fn with_source_srid(mut self, source_srid: u16) -> Self {
+        self.source_srid = Some(source_srid);
+        self
+    }
+
+    #[inline]
+    fn dbf_path(&self) -> PathBuf {
+        self.file_path.with_extension("dbf")
+    }
+
+    /// CRS of the `source_srid` option or the shapefile's `.prj` file. [None] when neither is
+    /// available. Fails when the `.prj` file cannot be read so geometries are never loaded with
+    /// an unknown projection.
+    fn crs(&self) -> BulkDataResult<Option<Crs>> {
+        if let Some(srid) = self.source_srid {
+            return Ok(Some(Crs::Epsg(srid)));
+        }
+        let prj_path = self.file_path.with_extension("prj");
+        if !prj_path.exists() {
+            return Ok(None);
+        }
+        let wkt = std::fs::read_to_string(&prj_path)?;
+        Crs::from_wkt(&wkt).map(Some).map_err(|error| {
+            format!(
+                "Could not read the CRS of {:?}. Set \"source_srid\" to load the file\n{}",
+                prj_path, error
+            )
+            .into()
+        })
+    }
+
+    fn projector(&self) -> BulkDataResult<GeometryProjector> {
+        GeometryProjector::new(self.crs()?.as_ref(), self.target_srid)
+    }
+
+    fn geometry_writer(&self) -> BulkDataResult<GeometryWriter> {
+        Ok(GeometryWriter::new(
+            self.projector()?,
+            self.geometry_validation,
+        ))
     }
 
     fn fields(&self) -> BulkDataResult<Vec<FieldInfo>> {
-        let dbf_reader = DbfReader::from_path(self.file_path.with_extension("dbf"))?;
+        let dbf_reader = DbfReader::from_path(self.dbf_path())?;
         Ok(dbf_reader
             .fields()
             .iter()
@@ -41,50 +198,42 @@ impl ShapeDataOptions {
         let reader = Reader::from_path(&self.file_path)?;
         Ok(reader)
     }
-}
 
-impl DataOptions for ShapeDataOptions {}
-
-fn column_type_from_value(value: &FieldValue) -> ColumnType {
-    match value {
-        FieldValue::Character(_) => ColumnType::Text,
-        FieldValue::Numeric(_) => ColumnType::Number,
-        FieldValue::Logical(_) => ColumnType::Boolean,
-        FieldValue::Date(_) => ColumnType::Date,
-        FieldValue::Float(_) => ColumnType::Real,
-        FieldValue::Integer(_) => ColumnType::Integer,
-        FieldValue::Currency(_) => ColumnType::Money,
-        FieldValue::DateTime(_) => ColumnType::Timestamp,
-        FieldValue::Double(_) => ColumnType::DoublePrecision,
-        FieldValue::Memo(_) => ColumnType::Text,
+    /// Dimension of the shapefile's geometries, taken from the first shape that is not a
+    /// [Shape::NullShape]. Files without shapes are treated as 2D.
+    fn geometry_dimension(&self) -> BulkDataResult<GeometryDimension> {
+        let mut reader = self.reader()?;
+        for feature in reader.iter_shapes_and_records() {
+            let (shape, _) = feature?;
+            if let Some(geometry) = shape_to_geometry(&shape)? {
+                return Ok(geometry.dimension());
+            }
+        }
+        Ok(GeometryDimension::Xy)
     }
 }
 
+impl DataOptions for ShapeDataOptions {}
+
 pub fn schema(options: &ShapeDataOptions) -> BulkDataResult<Schema> {
     let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
         return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
     };
-    let mut feature_reader = options.reader()?;
-    let Some(Ok((_, record))) = feature_reader.iter_shapes_and_records().next() else {
-        return Err(format!("Could not get the first feature for \"{:?}\"", &options.file_path).into())
-    };
-    let mut columns: Vec<ColumnMetadata> = options
-        .fields()?
+    let mut columns: Vec<ColumnMetadata> = read_field_descriptors(options.dbf_path())?
         .iter()
-        .filter(|f| f.name() != "DeletionFlag")
-        .map(|field| -> BulkDataResult<ColumnMetadata> {
-            let field_name = field.name();
-            let Some(field_value) = record.get(field_name) else {
-                return Err(format!("Could not find value for field {}", field_name).into())
-            };
-            ColumnMetadata::new(field_name, column_type_from_value(field_value))
-        })
+        .map(|descriptor| ColumnMetadata::new(descriptor.name(), descriptor.column_type()))
         .collect::<BulkDataResult<_>>()?;
-    columns.push(ColumnMetadata::new("geometry", ColumnType::Geometry)?);
+    let srid = options.projector()?.srid();
+    let metadata = GeometryMetadata::new(options.geometry_dimension()?, srid);
+    columns.extend(
+        options
+            .geometry_validation
+            .geometry_columns("geometry", metadata)?,
+    );
     Schema::new(table_name, columns)
 }
 
-fn map_field_value(value: FieldValue) -> String {
+pub fn map_field_value(value: FieldValue) -> String {
     match value {
         FieldValue::Character(str) => str.unwrap_or_default(),
         FieldValue::Numeric(n) => n.map(|f| f.to_string()).unwrap_or_default(),
@@ -125,6 +274,14 @@ pub async fn spool_records(
         Ok(reader) => reader,
         Err(error) => return record_channel.send(Err(error)).await.err(),
     };
+    let geometry_writer = match options.geometry_writer() {
+        Ok(geometry_writer) => geometry_writer,
+        Err(error) => return record_channel.send(Err(error)).await.err(),
+    };
+    let mut big_int_reader = match BigIntFieldReader::new(options.dbf_path()) {
+        Ok(reader) => reader,
+        Err(error) => return record_channel.send(Err(error)).await.err(),
+    };
     for (feature_number, feature) in reader.iter_shapes_and_records().enumerate() {
         let Ok((shape, mut record)) = feature else {
             return record_channel
@@ -132,27 +289,39 @@ pub async fn spool_records(
                 .await
                 .err();
         };
-        let wkt = match shape {
-            Shape::NullShape => String::new(),
-            _ => {
-                let Ok(geo) = geo_types::Geometry::<f64>::try_from(shape) else {
+        let mut big_ints = match big_int_reader.next_record() {
+            Ok(values) => values,
+            Err(error) => return record_channel.send(Err(error)).await.err(),
+        };
+        let geometry_values = match shape_to_geometry(&shape) {
+            Ok(geometry) => match geometry_writer.csv_values(geometry) {
+                Ok(values) => values,
+                Err(error) => {
                     return record_channel
-                        .send(Err(format!("Could not obtain shape for feature {}", &feature_number).into()))
+                        .send(Err(format!("Feature {}. {}", &feature_number, error).into()))
                         .await
                         .err();
-                };
-                geo.wkt_string()
+                }
+            },
+            Err(error) => {
+                return record_channel
+                    .send(Err(format!("Could not obtain shape for feature {}. {}", &feature_number, error).into()))
+                    .await
+                    .err();
             }
         };
         let csv_iter = fields
             .iter()
             .map(|f| -> BulkDataResult<String> {
+                if let Some(value) = big_ints.remove(f.name()) {
+                    return Ok(value);
+                }
                 let Some(field_value) = record.remove(f.name()) else {
                     return Err(format!("Could not find field \"{}\" in record number {}", f.name(), feature_number).into())
                 };
                 Ok(map_field_value(field_value))
             })
-            .chain(std::iter::once(Ok(wkt)));
+            .chain(geometry_values.into_iter().map(Ok));
         let result = record_channel
             .send(csv_result_iter_to_string(csv_iter))
             .await;
@@ -163,10 +332,103 @@ pub async fn spool_records(
     None
 }
 
+#[async_trait]
+impl SourceLoader for ShapeDataOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use shapefile::dbase::{Date, DateTime, Time};
+    use shapefile::{
+        dbase::{Date, DateTime, Time},
+        PolygonZ, PolylineZ,
+    };
+
+    #[test]
+    fn crs_should_fail_when_prj_not_supported() -> BulkDataResult<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let file_path = temp_dir.path().join("test.shp");
+        std::fs::write(
+            file_path.with_extension("prj"),
+            r#"PROJCS["Test",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]]],PROJECTION["Krovak"],UNIT["Meter",1.0]]"#,
+        )?;
+        let options = ShapeDataOptions::new(file_path.clone());
+        let overridden = ShapeDataOptions::new(file_path).with_source_srid(5514);
+
+        assert!(options.crs().is_err());
+        assert_eq!(Some(Crs::Epsg(5514)), overridden.crs()?);
+        Ok(())
+    }
+
+    #[test]
+    fn shape_to_geometry_should_keep_z_and_m_when_point_z() -> BulkDataResult<()> {
+        let shape = Shape::PointZ(PointZ::new(1.0, 2.0, 3.0, 4.0));
+
+        let actual = shape_to_geometry(&shape)?.map(|g| g.to_ewkt());
+
+        assert_eq!(Some(String::from("POINT ZM (1 2 3 4)")), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn shape_to_geometry_should_drop_m_when_no_data() -> BulkDataResult<()> {
+        let shape = Shape::PolylineZ(PolylineZ::new(vec![
+            PointZ::new(1.0, 2.0, 3.0, -1e39),
+            PointZ::new(4.0, 5.0, 6.0, -1e39),
+        ]));
+
+        let actual = shape_to_geometry(&shape)?.map(|g| g.to_ewkt());
+
+        assert_eq!(Some(String::from("LINESTRING Z (1 2 3,4 5 6)")), actual);
+        Ok(())
+    }
+
+    #[test]
+    fn shape_to_geometry_should_group_inner_rings_with_outer_ring() -> BulkDataResult<()> {
+        let shape = Shape::PolygonZ(PolygonZ::with_rings(vec![
+            PolygonRing::Outer(vec![
+                PointZ::new(0.0, 0.0, 1.0, -1e39),
+                PointZ::new(0.0, 4.0, 1.0, -1e39),
+                PointZ::new(4.0, 4.0, 1.0, -1e39),
+                PointZ::new(0.0, 0.0, 1.0, -1e39),
+            ]),
+            PolygonRing::Inner(vec![
+                PointZ::new(1.0, 1.0, 1.0, -1e39),
+                PointZ::new(2.0, 2.0, 1.0, -1e39),
+                PointZ::new(2.0, 1.0, 1.0, -1e39),
+                PointZ::new(1.0, 1.0, 1.0, -1e39),
+            ]),
+        ]));
+
+        let actual = shape_to_geometry(&shape)?.map(|g| g.to_ewkt());
+
+        assert_eq!(
+            Some(String::from(
+                "POLYGON Z ((0 0 1,0 4 1,4 4 1,0 0 1),(1 1 1,2 2 1,2 1 1,1 1 1))"
+            )),
+            actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shape_to_geometry_should_return_none_when_null_shape() -> BulkDataResult<()> {
+        let actual = shape_to_geometry(&Shape::NullShape)?;
+
+        assert_eq!(None, actual);
+        Ok(())
+    }
 
     #[test]
     fn map_field_value_should_return_exact_string_when_character_some() {
@@ -0,0 +1,209 @@
+use super::{
+    analyze::Schema,
+    arcgis::ArcGisDataOptions,
+    avro::AvroFileOptions,
+    dbf::DbfOptions,
+    delimited::DelimitedDataOptions,
+    error::BulkDataResult,
+    excel::ExcelOptions,
+    fixed_width::FixedWidthOptions,
+    geo_json::GeoJsonOptions,
+    ipc::IpcFileOptions,
+    load::{CopyOptions, RecordSpoolChannel, RecordSpoolResult},
+    options::DataOptions,
+    parquet::ParquetFileOptions,
+    shape::ShapeDataOptions,
+    xml::XmlOptions,
+};
+use async_trait::async_trait;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref LOADER_REGISTRY: RwLock<LoaderRegistry> = RwLock::new(LoaderRegistry::default());
+}
+
+/// Source of records that are loaded into a single table. Implemented by the options of each
+/// supported format.
+#[async_trait]
+pub trait SourceLoader: Send + Sync {
+    async fn schema(&self) -> BulkDataResult<Schema>;
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult;
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String;
+
+    /// Split the source into 1 loader per table. [None] when the source maps to a single table
+    fn split(&self) -> BulkDataResult<Option<Vec<Box<dyn SourceLoader>>>> {
+        Ok(None)
+    }
+
+    /// Persist the loader's progress under the id so a failed load can resume. Ignored by sources
+    /// that cannot resume.
+    fn enable_checkpoint(&mut self, _id: i64) {}
+
+    /// Remove the loader's checkpoint once the loaded records are committed
+    fn complete_checkpoint(&self) -> BulkDataResult<()> {
+        Ok(())
+    }
+
+    /// Take the warnings found while spooling records, such as values outside of a range domain
+    fn take_warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+type ParseLoader = fn(&Value) -> BulkDataResult<Box<dyn SourceLoader>>;
+type OptionsCondition = fn(&Map<String, Value>) -> bool;
+
+fn parse_loader<L>(options: &Value) -> BulkDataResult<Box<dyn SourceLoader>>
+where
+    L: SourceLoader + DataOptions + 'static,
+{
+    let loader: L = serde_json::from_value(options.clone())?;
+    Ok(Box::new(loader))
+}
+
+/// Format that a [LoaderRegistry] can create loaders for
+pub struct LoaderRegistration {
+    format: &'static str,
+    extensions: &'static [&'static str],
+    condition: Option<OptionsCondition>,
+    parse: ParseLoader,
+}
+
+impl LoaderRegistration {
+    /// Create a registration for the format name, parsing source data options as `L`
+    pub fn new<L>(format: &'static str) -> Self
+    where
+        L: SourceLoader + DataOptions + 'static,
+    {
+        Self {
+            format,
+            extensions: &[],
+            condition: None,
+            parse: parse_loader::<L>,
+        }
+    }
+
+    /// File extensions that select this format when the options do not specify a format
+    pub fn with_extensions(mut self, extensions: &'static [&'static str]) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Only select this format from a file extension when the options satisfy the condition.
+    /// Checked before registrations of the same extension without a condition.
+    pub fn with_condition(mut self, condition: OptionsCondition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    #[inline]
+    pub fn format(&self) -> &'static str {
+        self.format
+    }
+}
+
+/// Formats available to create loaders from source data options
+pub struct LoaderRegistry {
+    registrations: Vec<LoaderRegistration>,
+}
+
+impl LoaderRegistry {
+    /// Create a registry without any formats
+    pub fn empty() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, registration: LoaderRegistration) -> BulkDataResult<()> {
+        if self.registration(registration.format).is_some() {
+            return Err(format!("Format \"{}\" is already registered", registration.format).into());
+        }
+        self.registrations.push(registration);
+        Ok(())
+    }
+
+    fn registration(&self, format: &str) -> Option<&LoaderRegistration> {
+        self.registrations.iter().find(|r| r.format == format)
+    }
+
+    /// Names of the registered formats, joined for use in error messages
+    pub fn formats(&self) -> String {
+        self.registrations.iter().map(|r| r.format).join(", ")
+    }
+
+    /// Format selected by a file extension. Registrations with a condition are checked first
+    pub fn extension_format(
+        &self,
+        extension: &str,
+        options: &Map<String, Value>,
+    ) -> Option<&'static str> {
+        let mut candidates = self
+            .registrations
+            .iter()
+            .filter(|r| r.extensions.contains(&extension));
+        let conditional = candidates
+            .clone()
+            .find(|r| matches!(r.condition, Some(condition) if condition(options)));
+        conditional
+            .or_else(|| candidates.find(|r| r.condition.is_none()))
+            .map(|r| r.format)
+    }
+
+    /// Parse the source data options as a loader of the format
+    pub fn loader(&self, format: &str, options: &Value) -> BulkDataResult<Box<dyn SourceLoader>> {
+        let Some(registration) = self.registration(format) else {
+            return Err(format!("Unknown source data format \"{}\". Supported formats: {}", format, self.formats()).into())
+        };
+        (registration.parse)(options)
+    }
+}
+
+impl Default for LoaderRegistry {
+    /// Registry of the formats provided by this crate
+    fn default() -> Self {
+        Self {
+            registrations: vec![
+                LoaderRegistration::new::<ArcGisDataOptions>("arcgis"),
+                LoaderRegistration::new::<AvroFileOptions>("avro").with_extensions(&["avro"]),
+                LoaderRegistration::new::<DbfOptions>("dbf").with_extensions(&["dbf"]),
+                LoaderRegistration::new::<DelimitedDataOptions>("delimited")
+                    .with_extensions(&["txt", "csv"]),
+                LoaderRegistration::new::<ExcelOptions>("excel")
+                    .with_extensions(&["xlsx", "xls", "xlsm", "xlsb", "ods"]),
+                // Fixed width files share extensions with delimited files so the format must be set
+                LoaderRegistration::new::<FixedWidthOptions>("fixed_width"),
+                LoaderRegistration::new::<GeoJsonOptions>("geojson").with_extensions(&["geojson"]),
+                LoaderRegistration::new::<IpcFileOptions>("ipc")
+                    .with_extensions(&["ipc", "feather", "arrow", "arrows"]),
+                LoaderRegistration::new::<ParquetFileOptions>("parquet")
+                    .with_extensions(&["parquet"]),
+                LoaderRegistration::new::<ShapeDataOptions>("shape").with_extensions(&["shp"]),
+                LoaderRegistration::new::<XmlOptions>("xml").with_extensions(&["xml"]),
+            ],
+        }
+    }
+}
+
+/// Add a format to the registry used by [super::DataLoader::new]
+pub fn register_loader(registration: LoaderRegistration) -> BulkDataResult<()> {
+    let Ok(mut registry) = LOADER_REGISTRY.write() else {
+        return Err("Loader registry lock was poisoned".into())
+    };
+    registry.register(registration)
+}
+
+/// Run the function with the registry used by [super::DataLoader::new]
+pub(super) fn with_loader_registry<T>(
+    f: impl FnOnce(&LoaderRegistry) -> BulkDataResult<T>,
+) -> BulkDataResult<T> {
+    let Ok(registry) = LOADER_REGISTRY.read() else {
+        return Err("Loader registry lock was poisoned".into())
+    };
+    f(&registry)
+}
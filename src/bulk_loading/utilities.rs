@@ -1,9 +1,16 @@
 use super::{
-    analyze::{ColumnMetadata, ColumnType, Schema},
+    analyze::ColumnType,
     error::{BulkDataError, BulkDataResult},
-    load::{csv_result_iter_to_string, RecordSpoolChannel, RecordSpoolResult},
+    load::{RecordSpoolChannel, RecordSpoolResult},
+};
+use arrow::{
+    array::{as_primitive_array, Array, ArrayRef},
+    datatypes::{
+        DataType as ArrowDataType, DurationMicrosecondType, DurationMillisecondType,
+        DurationNanosecondType, DurationSecondType, TimeUnit as ArrowTimeUnit,
+    },
+    util::display::array_value_to_string,
 };
-use polars::prelude::{AnyValue, DataFrame, DataType, TimeUnit};
 
 pub fn escape_csv_string(csv_string: String) -> String {
     if csv_string
@@ -24,157 +31,83 @@ pub async fn send_error_message<E: Into<BulkDataError>>(
     channel.send(Err(error.into())).await.err()
 }
 
-pub fn map_formatted_value(value: AnyValue) -> String {
-    match value {
-        AnyValue::Null => String::new(),
-        AnyValue::Utf8(string) => string.to_owned(),
-        AnyValue::Utf8Owned(string) => string,
-        AnyValue::Duration(duration, unit) => match unit {
-            TimeUnit::Microseconds => format!("{} microsecond", duration),
-            TimeUnit::Milliseconds => format!("{} milisecond", duration),
-            TimeUnit::Nanoseconds => format!("{:.2} microsecond", duration as f64 / 1000.0_f64),
-        },
-        _ => format!("{}", value),
+/// Format the value of an Arrow array at `row` as a CSV value. Nulls are written as an empty
+/// string and durations as interval literals.
+pub fn map_arrow_value(column: &ArrayRef, row: usize) -> BulkDataResult<String> {
+    if column.is_null(row) {
+        return Ok(String::new());
     }
-}
-
-impl From<&DataType> for ColumnType {
-    fn from(typ: &DataType) -> Self {
-        match typ {
-            DataType::Boolean => ColumnType::Boolean,
-            DataType::UInt8 => ColumnType::SmallInt,
-            DataType::UInt16 => ColumnType::Integer,
-            DataType::UInt32 => ColumnType::BigInt,
-            DataType::UInt64 => ColumnType::BigInt,
-            DataType::Int8 => ColumnType::SmallInt,
-            DataType::Int16 => ColumnType::Integer,
-            DataType::Int32 => ColumnType::BigInt,
-            DataType::Int64 => ColumnType::BigInt,
-            DataType::Float32 => ColumnType::Real,
-            DataType::Float64 => ColumnType::DoublePrecision,
-            DataType::Utf8 => ColumnType::Text,
-            DataType::Date => ColumnType::Date,
-            DataType::Datetime(_, None) => ColumnType::TimestampWithZone,
-            DataType::Datetime(_, Some(_)) => ColumnType::Timestamp,
-            DataType::Duration(_) => ColumnType::Interval,
-            DataType::Time => ColumnType::Time,
-            DataType::List(_) => ColumnType::Text,
-            DataType::Null => ColumnType::Text,
-            DataType::Categorical(_) => ColumnType::Text,
-            DataType::Struct(_) => ColumnType::Text,
-            DataType::Unknown => ColumnType::Text,
+    Ok(match column.data_type() {
+        ArrowDataType::Duration(ArrowTimeUnit::Second) => {
+            let duration = as_primitive_array::<DurationSecondType>(column).value(row);
+            format!("{} second", duration)
         }
-    }
-}
-
-pub fn schema_from_dataframe(file_name: String, dataframe: DataFrame) -> BulkDataResult<Schema> {
-    let columns: Vec<ColumnMetadata> = dataframe
-        .schema()
-        .iter()
-        .map(|(field, typ)| ColumnMetadata::new(field, typ.into()))
-        .collect::<BulkDataResult<_>>()?;
-    Schema::new(&file_name, columns)
+        ArrowDataType::Duration(ArrowTimeUnit::Millisecond) => {
+            let duration = as_primitive_array::<DurationMillisecondType>(column).value(row);
+            format!("{} millisecond", duration)
+        }
+        ArrowDataType::Duration(ArrowTimeUnit::Microsecond) => {
+            let duration = as_primitive_array::<DurationMicrosecondType>(column).value(row);
+            format!("{} microsecond", duration)
+        }
+        ArrowDataType::Duration(ArrowTimeUnit::Nanosecond) => {
+            let duration = as_primitive_array::<DurationNanosecondType>(column).value(row);
+            format!("{:.2} microsecond", duration as f64 / 1000.0_f64)
+        }
+        _ => array_value_to_string(column, row)?,
+    })
 }
 
-pub async fn spool_dataframe_records(
-    dataframe: DataFrame,
-    record_channel: &mut RecordSpoolChannel,
-) -> RecordSpoolResult {
-    let mut iters = dataframe.iter().map(|s| s.iter()).collect::<Vec<_>>();
-    for _ in 0..dataframe.height() {
-        let row_data = iters.iter_mut().map(|iter| {
-            let Some(value) = iter.next() else {
-                    return Err("Dataframe value was not found. This should never happen".into())
-                };
-            Ok(map_formatted_value(value))
-        });
-        let result = record_channel
-            .send(csv_result_iter_to_string(row_data))
-            .await;
-        if let Err(error) = result {
-            return Some(error);
+impl From<&ArrowDataType> for ColumnType {
+    fn from(typ: &ArrowDataType) -> Self {
+        match typ {
+            ArrowDataType::Boolean => ColumnType::Boolean,
+            ArrowDataType::UInt8 => ColumnType::SmallInt,
+            ArrowDataType::UInt16 => ColumnType::Integer,
+            ArrowDataType::UInt32 => ColumnType::BigInt,
+            ArrowDataType::UInt64 => ColumnType::BigInt,
+            ArrowDataType::Int8 => ColumnType::SmallInt,
+            ArrowDataType::Int16 => ColumnType::Integer,
+            ArrowDataType::Int32 => ColumnType::BigInt,
+            ArrowDataType::Int64 => ColumnType::BigInt,
+            ArrowDataType::Float16 | ArrowDataType::Float32 => ColumnType::Real,
+            ArrowDataType::Float64 => ColumnType::DoublePrecision,
+            ArrowDataType::Decimal128(_, _) | ArrowDataType::Decimal256(_, _) => ColumnType::Number,
+            ArrowDataType::Date32 | ArrowDataType::Date64 => ColumnType::Date,
+            ArrowDataType::Timestamp(_, None) => ColumnType::TimestampWithZone,
+            ArrowDataType::Timestamp(_, Some(_)) => ColumnType::Timestamp,
+            ArrowDataType::Duration(_) => ColumnType::Interval,
+            ArrowDataType::Time32(_) | ArrowDataType::Time64(_) => ColumnType::Time,
+            _ => ColumnType::Text,
         }
     }
-    None
 }
 
 #[cfg(test)]
 mod tests {
-    use polars::prelude::TimeUnit;
+    use arrow::array::{DurationMillisecondArray, Int32Array};
+    use std::sync::Arc;
 
     use super::*;
 
     #[test]
-    fn map_formatted_value_should_return_true_string_literal_when_boolean_true() {
-        let value = AnyValue::Boolean(true);
-
-        let actual = map_formatted_value(value);
-
-        assert_eq!("true", actual);
-    }
-
-    #[test]
-    fn map_formatted_value_should_return_false_string_literal_when_boolean_false() {
-        let value = AnyValue::Boolean(false);
-
-        let actual = map_formatted_value(value);
-
-        assert_eq!("false", actual);
-    }
-
-    #[test]
-    fn map_formatted_value_should_return_formatted_date_when_zero_date() {
-        let value = AnyValue::Date(0);
-
-        let actual = map_formatted_value(value);
-
-        assert_eq!("1970-01-01", actual);
-    }
-
-    #[test]
-    fn map_formatted_value_should_return_formatted_date_when_datetime_without_timezone() {
-        let value = AnyValue::Datetime(1666469363000, TimeUnit::Milliseconds, &None);
-
-        let actual = map_formatted_value(value);
-
-        assert_eq!("2022-10-22 20:09:23", actual);
-    }
-
-    #[test]
-    fn map_formatted_value_should_return_formatted_date_when_datetime_with_timezone() {
-        let timezone = Some(String::from("America/New_York"));
-        let value = AnyValue::Datetime(1666469363000, TimeUnit::Milliseconds, &timezone);
-
-        let actual = map_formatted_value(value);
-
-        assert_eq!("2022-10-22 20:09:23 EDT", actual);
-    }
-
-    #[test]
-    fn map_formatted_value_should_return_formatted_interval_when_milliseconds() {
-        let value = AnyValue::Duration(20200, TimeUnit::Milliseconds);
-
-        let actual = map_formatted_value(value);
-
-        assert_eq!("20200 milisecond", actual);
-    }
-
-    #[test]
-    fn map_formatted_value_should_return_formatted_interval_when_microseconds() {
-        let value = AnyValue::Duration(56, TimeUnit::Microseconds);
+    fn map_arrow_value_should_return_empty_string_when_null() -> BulkDataResult<()> {
+        let column: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None]));
 
-        let actual = map_formatted_value(value);
+        let actual = map_arrow_value(&column, 1)?;
 
-        assert_eq!("56 microsecond", actual);
+        assert_eq!("", actual);
+        Ok(())
     }
 
     #[test]
-    fn map_formatted_value_should_return_formatted_interval_when_nanoseconds() {
-        let value = AnyValue::Duration(9865, TimeUnit::Nanoseconds);
+    fn map_arrow_value_should_return_formatted_interval_when_duration() -> BulkDataResult<()> {
+        let column: ArrayRef = Arc::new(DurationMillisecondArray::from(vec![20200]));
 
-        let actual = map_formatted_value(value);
+        let actual = map_arrow_value(&column, 0)?;
 
-        assert_eq!("9.87 microsecond", actual);
+        assert_eq!("20200 millisecond", actual);
+        Ok(())
     }
 
     #[test]
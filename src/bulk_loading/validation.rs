@@ -0,0 +1,475 @@
+use super::{
+    analyze::{ColumnMetadata, ColumnType, GeometryMetadata},
+    crs::GeometryProjector,
+    error::BulkDataResult,
+    geometry::{Coordinate, Geometry},
+};
+use serde::{Deserialize, Serialize};
+
+/// How geometries are validated before they are spooled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeometryValidation {
+    /// Geometries are written without validation
+    #[default]
+    Off,
+    /// Invalid geometries fail the load
+    Reject,
+    /// Rings are closed and oriented and degenerate parts are dropped. Geometries that are still
+    /// invalid fail the load.
+    Repair,
+    /// Geometries are written as is and the reason a geometry is invalid is written to an extra
+    /// column
+    Flag,
+}
+
+impl GeometryValidation {
+    /// Schema columns for a geometry column, including the validity reason column when flagging
+    pub fn geometry_columns(
+        &self,
+        name: &str,
+        metadata: GeometryMetadata,
+    ) -> BulkDataResult<Vec<ColumnMetadata>> {
+        let mut columns = vec![ColumnMetadata::geometry(name, metadata)?];
+        if *self == GeometryValidation::Flag {
+            let reason_column = format!("{}_validity_reason", name);
+            columns.push(ColumnMetadata::new(&reason_column, ColumnType::Text)?);
+        }
+        Ok(columns)
+    }
+}
+
+#[inline]
+fn same_position(first: &Coordinate, second: &Coordinate) -> bool {
+    first.x() == second.x() && first.y() == second.y()
+}
+
+#[inline]
+fn is_finite(coordinate: &Coordinate) -> bool {
+    coordinate.x().is_finite() && coordinate.y().is_finite()
+}
+
+/// Copy of the coordinates without consecutive duplicate positions
+fn distinct_positions(coordinates: &[Coordinate]) -> Vec<Coordinate> {
+    let mut positions = coordinates.to_vec();
+    positions.dedup_by(|a, b| same_position(a, b));
+    positions
+}
+
+/// Twice the signed area of a closed ring. Positive when the ring is counter-clockwise.
+fn signed_area(ring: &[Coordinate]) -> f64 {
+    ring.windows(2)
+        .map(|pair| pair[0].x() * pair[1].y() - pair[1].x() * pair[0].y())
+        .sum()
+}
+
+fn orientation(a: &Coordinate, b: &Coordinate, c: &Coordinate) -> f64 {
+    (b.x() - a.x()) * (c.y() - a.y()) - (b.y() - a.y()) * (c.x() - a.x())
+}
+
+fn on_segment(a: &Coordinate, b: &Coordinate, point: &Coordinate) -> bool {
+    point.x() >= a.x().min(b.x())
+        && point.x() <= a.x().max(b.x())
+        && point.y() >= a.y().min(b.y())
+        && point.y() <= a.y().max(b.y())
+}
+
+fn segments_intersect(a: &Coordinate, b: &Coordinate, c: &Coordinate, d: &Coordinate) -> bool {
+    let o1 = orientation(a, b, c);
+    let o2 = orientation(a, b, d);
+    let o3 = orientation(c, d, a);
+    let o4 = orientation(c, d, b);
+    if o1 * o2 < 0.0 && o3 * o4 < 0.0 {
+        return true;
+    }
+    (o1 == 0.0 && on_segment(a, b, c))
+        || (o2 == 0.0 && on_segment(a, b, d))
+        || (o3 == 0.0 && on_segment(c, d, a))
+        || (o4 == 0.0 && on_segment(c, d, b))
+}
+
+#[inline]
+fn x_range(a: &Coordinate, b: &Coordinate) -> (f64, f64) {
+    (a.x().min(b.x()), a.x().max(b.x()))
+}
+
+#[inline]
+fn y_ranges_overlap(a: &Coordinate, b: &Coordinate, c: &Coordinate, d: &Coordinate) -> bool {
+    a.y().min(b.y()) <= c.y().max(d.y()) && c.y().min(d.y()) <= a.y().max(b.y())
+}
+
+/// First pair of non-adjacent segments of a closed ring that touch or cross. Segments are swept
+/// in order of their minimum x so only segments with overlapping bounding boxes are compared.
+fn self_intersection(ring: &[Coordinate]) -> Option<(usize, usize)> {
+    let segment_count = ring.len() - 1;
+    let mut sweep_order: Vec<usize> = (0..segment_count).collect();
+    sweep_order.sort_by(|a, b| {
+        let a_min = x_range(&ring[*a], &ring[*a + 1]).0;
+        a_min.total_cmp(&x_range(&ring[*b], &ring[*b + 1]).0)
+    });
+    let mut active: Vec<usize> = Vec::new();
+    let mut first_pair: Option<(usize, usize)> = None;
+    for segment in sweep_order {
+        let min_x = x_range(&ring[segment], &ring[segment + 1]).0;
+        active.retain(|other| x_range(&ring[*other], &ring[*other + 1]).1 >= min_x);
+        for other in &active {
+            let (i, j) = (segment.min(*other), segment.max(*other));
+            if j - i < 2 || (i == 0 && j == segment_count - 1) {
+                continue;
+            }
+            if y_ranges_overlap(&ring[i], &ring[i + 1], &ring[j], &ring[j + 1])
+                && segments_intersect(&ring[i], &ring[i + 1], &ring[j], &ring[j + 1])
+            {
+                first_pair = Some(first_pair.map_or((i, j), |pair| pair.min((i, j))));
+            }
+        }
+        active.push(segment);
+    }
+    first_pair
+}
+
+fn line_validity_reason(line: &[Coordinate]) -> Option<String> {
+    if distinct_positions(line).len() < 2 {
+        return Some(String::from("LineString has fewer than 2 distinct points"));
+    }
+    None
+}
+
+fn ring_validity_reason(ring: &[Coordinate]) -> Option<String> {
+    let (Some(first), Some(last)) = (ring.first(), ring.last()) else {
+        return Some(String::from("Ring is empty"))
+    };
+    if !same_position(first, last) {
+        return Some(String::from("Ring is not closed"));
+    }
+    let positions = distinct_positions(ring);
+    if positions.len() < 4 {
+        return Some(String::from("Ring has fewer than 4 points"));
+    }
+    if let Some((first, second)) = self_intersection(&positions) {
+        return Some(format!(
+            "Ring self-intersects between segments {} and {}",
+            first + 1,
+            second + 1
+        ));
+    }
+    if signed_area(&positions) == 0.0 {
+        return Some(String::from("Ring has zero area"));
+    }
+    None
+}
+
+fn polygon_validity_reason(rings: &[Vec<Coordinate>]) -> Option<String> {
+    rings.iter().enumerate().find_map(|(i, ring)| {
+        ring_validity_reason(ring).map(|reason| format!("Ring {}: {}", i + 1, reason))
+    })
+}
+
+/// Reason the geometry is not valid, or [None] when the geometry is valid. Checks for empty
+/// geometries, non-finite coordinates, degenerate lines and rings, unclosed rings and
+/// self-intersecting rings.
+pub fn validity_reason(geometry: &Geometry) -> Option<String> {
+    if geometry.is_empty() {
+        return Some(String::from("Geometry is empty"));
+    }
+    let mut finite = true;
+    geometry.for_each_coordinate(&mut |coordinate| finite = finite && is_finite(coordinate));
+    if !finite {
+        return Some(String::from("Geometry contains a non-finite coordinate"));
+    }
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => None,
+        Geometry::LineString(line) => line_validity_reason(line),
+        Geometry::MultiLineString(lines) => lines.iter().enumerate().find_map(|(i, line)| {
+            line_validity_reason(line).map(|reason| format!("LineString {}: {}", i + 1, reason))
+        }),
+        Geometry::Polygon(rings) => polygon_validity_reason(rings),
+        Geometry::MultiPolygon(polygons) => polygons.iter().enumerate().find_map(|(i, rings)| {
+            polygon_validity_reason(rings).map(|reason| format!("Polygon {}: {}", i + 1, reason))
+        }),
+        Geometry::GeometryCollection(geometries) => {
+            geometries.iter().enumerate().find_map(|(i, geometry)| {
+                validity_reason(geometry).map(|reason| format!("Geometry {}: {}", i + 1, reason))
+            })
+        }
+    }
+}
+
+fn repair_line(line: &[Coordinate]) -> Option<Vec<Coordinate>> {
+    let finite: Vec<Coordinate> = line.iter().filter(|c| is_finite(c)).copied().collect();
+    let line = distinct_positions(&finite);
+    if line.len() < 2 {
+        return None;
+    }
+    Some(line)
+}
+
+/// Close the ring and orient it counter-clockwise for shells or clockwise for holes. Rings
+/// without an area are dropped.
+fn repair_ring(ring: &[Coordinate], is_shell: bool) -> Option<Vec<Coordinate>> {
+    let mut ring = repair_line(ring)?;
+    if !same_position(&ring[0], &ring[ring.len() - 1]) {
+        ring.push(ring[0]);
+    }
+    if ring.len() < 4 {
+        return None;
+    }
+    let area = signed_area(&ring);
+    if area == 0.0 {
+        return None;
+    }
+    if (area > 0.0) != is_shell {
+        ring.reverse();
+    }
+    Some(ring)
+}
+
+fn repair_polygon(rings: &[Vec<Coordinate>]) -> Option<Vec<Vec<Coordinate>>> {
+    let (shell, holes) = rings.split_first()?;
+    let shell = repair_ring(shell, true)?;
+    Some(
+        std::iter::once(shell)
+            .chain(holes.iter().filter_map(|hole| repair_ring(hole, false)))
+            .collect(),
+    )
+}
+
+/// Repair the geometry by dropping non-finite coordinates and duplicate points, closing and
+/// orienting rings, and removing degenerate parts. Returns [None] when nothing is left of the
+/// geometry.
+pub fn repair(geometry: Geometry) -> Option<Geometry> {
+    let repaired = match geometry {
+        Geometry::Point(point) if !is_finite(&point) => return None,
+        Geometry::Point(point) => Geometry::Point(point),
+        Geometry::MultiPoint(points) => Geometry::MultiPoint(
+            points
+                .into_iter()
+                .filter(|point| is_finite(point))
+                .collect(),
+        ),
+        Geometry::LineString(line) => Geometry::LineString(repair_line(&line)?),
+        Geometry::MultiLineString(lines) => {
+            Geometry::MultiLineString(lines.iter().filter_map(|l| repair_line(l)).collect())
+        }
+        Geometry::Polygon(rings) => Geometry::Polygon(repair_polygon(&rings)?),
+        Geometry::MultiPolygon(polygons) => {
+            Geometry::MultiPolygon(polygons.iter().filter_map(|p| repair_polygon(p)).collect())
+        }
+        Geometry::GeometryCollection(geometries) => {
+            Geometry::GeometryCollection(geometries.into_iter().filter_map(repair).collect())
+        }
+    };
+    if repaired.is_empty() {
+        None
+    } else {
+        Some(repaired)
+    }
+}
+
+/// Validates, projects and writes geometries as the CSV values of a geometry column
+pub struct GeometryWriter {
+    projector: GeometryProjector,
+    validation: GeometryValidation,
+}
+
+impl GeometryWriter {
+    pub fn new(projector: GeometryProjector, validation: GeometryValidation) -> Self {
+        Self {
+            projector,
+            validation,
+        }
+    }
+
+    /// CSV values for the geometry column and, when flagging, the validity reason column. A
+    /// [None] geometry is written as null.
+    pub fn csv_values(&self, geometry: Option<Geometry>) -> BulkDataResult<Vec<String>> {
+        let (geometry, reason) = match self.validation {
+            GeometryValidation::Off => (geometry, None),
+            GeometryValidation::Reject => {
+                if let Some(reason) = geometry.as_ref().and_then(validity_reason) {
+                    return Err(format!("Invalid geometry. {}", reason).into());
+                }
+                (geometry, None)
+            }
+            GeometryValidation::Repair => {
+                let geometry = geometry.and_then(repair);
+                if let Some(reason) = geometry.as_ref().and_then(validity_reason) {
+                    return Err(format!("Geometry could not be repaired. {}", reason).into());
+                }
+                (geometry, None)
+            }
+            GeometryValidation::Flag => {
+                let reason = geometry.as_ref().and_then(validity_reason);
+                (geometry, reason)
+            }
+        };
+        let wkt = match geometry {
+            Some(geometry) => self.projector.to_ewkt(geometry)?,
+            None => String::new(),
+        };
+        if self.validation == GeometryValidation::Flag {
+            Ok(vec![wkt, reason.unwrap_or_default()])
+        } else {
+            Ok(vec![wkt])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xy(x: f64, y: f64) -> Coordinate {
+        Coordinate::new(x, y, None, None)
+    }
+
+    fn ring(points: &[(f64, f64)]) -> Vec<Coordinate> {
+        points.iter().map(|(x, y)| xy(*x, *y)).collect()
+    }
+
+    #[test]
+    fn validity_reason_should_return_none_when_polygon_valid() {
+        let geometry = Geometry::Polygon(vec![ring(&[
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ])]);
+
+        let actual = validity_reason(&geometry);
+
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn validity_reason_should_return_reason_when_ring_not_closed() {
+        let geometry = Geometry::Polygon(vec![ring(&[
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 4.0),
+            (0.0, 4.0),
+        ])]);
+
+        let actual = validity_reason(&geometry);
+
+        assert_eq!(Some(String::from("Ring 1: Ring is not closed")), actual);
+    }
+
+    #[test]
+    fn validity_reason_should_return_reason_when_ring_self_intersects() {
+        let geometry = Geometry::Polygon(vec![ring(&[
+            (0.0, 0.0),
+            (4.0, 4.0),
+            (4.0, 0.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ])]);
+
+        let actual = validity_reason(&geometry);
+
+        assert_eq!(
+            Some(String::from(
+                "Ring 1: Ring self-intersects between segments 1 and 3"
+            )),
+            actual
+        );
+    }
+
+    #[test]
+    fn validity_reason_should_return_none_when_ring_has_many_vertices() {
+        let vertex_count = 200_000;
+        let mut points: Vec<(f64, f64)> = (0..vertex_count)
+            .map(|i| {
+                let angle = i as f64 / vertex_count as f64 * std::f64::consts::TAU;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        points.push(points[0]);
+        let geometry = Geometry::Polygon(vec![ring(&points)]);
+
+        let actual = validity_reason(&geometry);
+
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn validity_reason_should_return_reason_when_empty() {
+        let geometry = Geometry::MultiPolygon(vec![]);
+
+        let actual = validity_reason(&geometry);
+
+        assert_eq!(Some(String::from("Geometry is empty")), actual);
+    }
+
+    #[test]
+    fn repair_should_close_and_orient_rings() {
+        let geometry = Geometry::Polygon(vec![
+            ring(&[(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (4.0, 0.0)]),
+            ring(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 1.0)]),
+        ]);
+
+        let actual = repair(geometry);
+
+        let expected = Geometry::Polygon(vec![
+            ring(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)]),
+            ring(&[(1.0, 1.0), (2.0, 2.0), (2.0, 1.0), (1.0, 1.0)]),
+        ]);
+        assert_eq!(Some(expected), actual);
+    }
+
+    #[test]
+    fn repair_should_drop_degenerate_parts() {
+        let geometry = Geometry::MultiLineString(vec![
+            ring(&[(0.0, 0.0), (0.0, 0.0)]),
+            ring(&[(0.0, 0.0), (1.0, 1.0), (1.0, 1.0)]),
+        ]);
+
+        let actual = repair(geometry);
+
+        let expected = Geometry::MultiLineString(vec![ring(&[(0.0, 0.0), (1.0, 1.0)])]);
+        assert_eq!(Some(expected), actual);
+    }
+
+    #[test]
+    fn repair_should_return_none_when_nothing_left() {
+        let geometry = Geometry::Polygon(vec![ring(&[(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)])]);
+
+        let actual = repair(geometry);
+
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn csv_values_should_include_reason_when_flagging() -> BulkDataResult<()> {
+        let writer = GeometryWriter::new(
+            GeometryProjector::new(None, 4326)?,
+            GeometryValidation::Flag,
+        );
+        let geometry = Geometry::LineString(vec![xy(1.0, 1.0), xy(1.0, 1.0)]);
+
+        let actual = writer.csv_values(Some(geometry))?;
+
+        assert_eq!(
+            vec![
+                String::from("LINESTRING(1 1,1 1)"),
+                String::from("LineString has fewer than 2 distinct points")
+            ],
+            actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn csv_values_should_fail_when_rejecting_invalid_geometry() -> BulkDataResult<()> {
+        let writer = GeometryWriter::new(
+            GeometryProjector::new(None, 4326)?,
+            GeometryValidation::Reject,
+        );
+
+        let actual = writer.csv_values(Some(Geometry::GeometryCollection(vec![])));
+
+        assert!(actual.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+use super::{
+    analyze::{ColumnType, Schema},
+    error::BulkDataResult,
+    geo_json::map_json_value,
+    load::{csv_iter_to_string, CopyOptions, RecordSpoolChannel, RecordSpoolResult},
+    options::DataOptions,
+    source::SourceLoader,
+    utilities::send_error_message,
+};
+use async_trait::async_trait;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct XmlOptions {
+    file_path: PathBuf,
+    row_path: String,
+}
+
+impl XmlOptions {
+    pub fn new(file_path: PathBuf, row_path: String) -> Self {
+        Self {
+            file_path,
+            row_path,
+        }
+    }
+
+    fn reader(&self) -> BulkDataResult<XmlRowReader<BufReader<File>>> {
+        let file = File::open(&self.file_path)?;
+        XmlRowReader::new(BufReader::new(file), &self.row_path)
+    }
+}
+
+impl DataOptions for XmlOptions {}
+
+/// Owned version of the quick-xml events required to build a row. Allows the reader's buffer to
+/// be released before nested elements are read.
+enum XmlNode {
+    Start(String, Vec<(String, String)>),
+    Empty(String, Vec<(String, String)>),
+    Text(String),
+    End,
+    Eof,
+    Other,
+}
+
+fn element_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).into_owned()
+}
+
+fn element_attributes(element: &BytesStart) -> BulkDataResult<Vec<(String, String)>> {
+    element
+        .attributes()
+        .map(|attribute| -> BulkDataResult<(String, String)> {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            let name = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            let value = attribute.unescape_value()?.into_owned();
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn attributes_to_map(attributes: Vec<(String, String)>) -> Map<String, Value> {
+    attributes
+        .into_iter()
+        .map(|(name, value)| (name, Value::String(value)))
+        .collect()
+}
+
+/// Collapse an element's content into a single value. Elements with only text become strings
+/// while elements with attributes or children become objects.
+fn element_value(mut contents: Map<String, Value>, text: String) -> Value {
+    if contents.is_empty() {
+        return if text.is_empty() {
+            Value::Null
+        } else {
+            Value::String(text)
+        };
+    }
+    if !text.is_empty() {
+        contents.insert(String::from("#text"), Value::String(text));
+    }
+    Value::Object(contents)
+}
+
+/// Add a child element's value to its parent. Repeated children are collected into an array.
+fn insert_child(contents: &mut Map<String, Value>, name: String, value: Value) {
+    match contents.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            contents.insert(name, value);
+        }
+    }
+}
+
+/// Streaming reader that yields each element found at the `row_path` as a map of column name to
+/// value. Only a single row is held in memory at a time.
+struct XmlRowReader<R: BufRead> {
+    reader: Reader<R>,
+    row_path: Vec<String>,
+    path: Vec<String>,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> XmlRowReader<R> {
+    fn new(reader: R, row_path: &str) -> BulkDataResult<Self> {
+        let row_path: Vec<String> = row_path
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .map(|part| part.to_owned())
+            .collect();
+        if row_path.is_empty() {
+            return Err("XML \"row_path\" must contain at least 1 element name".into());
+        }
+        let mut reader = Reader::from_reader(reader);
+        reader.trim_text(true);
+        Ok(Self {
+            reader,
+            row_path,
+            path: Vec::new(),
+            buffer: Vec::new(),
+        })
+    }
+
+    fn next_node(&mut self) -> BulkDataResult<XmlNode> {
+        self.buffer.clear();
+        Ok(match self.reader.read_event_into(&mut self.buffer)? {
+            Event::Start(element) => {
+                XmlNode::Start(element_name(&element), element_attributes(&element)?)
+            }
+            Event::Empty(element) => {
+                XmlNode::Empty(element_name(&element), element_attributes(&element)?)
+            }
+            Event::Text(text) => XmlNode::Text(text.unescape()?.into_owned()),
+            Event::CData(data) => {
+                XmlNode::Text(String::from_utf8_lossy(&data.into_inner()).into_owned())
+            }
+            Event::End(_) => XmlNode::End,
+            Event::Eof => XmlNode::Eof,
+            _ => XmlNode::Other,
+        })
+    }
+
+    fn read_contents(
+        &mut self,
+        attributes: Vec<(String, String)>,
+    ) -> BulkDataResult<(Map<String, Value>, String)> {
+        let mut contents = attributes_to_map(attributes);
+        let mut text = String::new();
+        loop {
+            match self.next_node()? {
+                XmlNode::Start(name, attributes) => {
+                    let (child_contents, child_text) = self.read_contents(attributes)?;
+                    insert_child(&mut contents, name, element_value(child_contents, child_text));
+                }
+                XmlNode::Empty(name, attributes) => {
+                    let value = element_value(attributes_to_map(attributes), String::new());
+                    insert_child(&mut contents, name, value);
+                }
+                XmlNode::Text(value) => text.push_str(&value),
+                XmlNode::End => break,
+                XmlNode::Eof => {
+                    return Err(format!(
+                        "Reached the end of the XML file while reading \"/{}\"",
+                        self.path.join("/")
+                    )
+                    .into())
+                }
+                XmlNode::Other => continue,
+            }
+        }
+        Ok((contents, text))
+    }
+
+    fn next_row(&mut self) -> BulkDataResult<Option<Map<String, Value>>> {
+        loop {
+            match self.next_node()? {
+                XmlNode::Start(name, attributes) => {
+                    self.path.push(name);
+                    if self.path == self.row_path {
+                        let (row, _) = self.read_contents(attributes)?;
+                        self.path.pop();
+                        return Ok(Some(row));
+                    }
+                }
+                XmlNode::Empty(name, attributes) => {
+                    self.path.push(name);
+                    let is_row = self.path == self.row_path;
+                    self.path.pop();
+                    if is_row {
+                        return Ok(Some(attributes_to_map(attributes)));
+                    }
+                }
+                XmlNode::End => {
+                    self.path.pop();
+                }
+                XmlNode::Eof => return Ok(None),
+                XmlNode::Text(_) | XmlNode::Other => continue,
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for XmlRowReader<R> {
+    type Item = BulkDataResult<Map<String, Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_row().transpose()
+    }
+}
+
+fn column_type_from_value(value: &Value) -> Option<ColumnType> {
+    match value {
+        Value::Null => None,
+        Value::Array(_) | Value::Object(_) => Some(ColumnType::Json),
+        _ => Some(ColumnType::Text),
+    }
+}
+
+/// Scan every row to find all columns in order of first appearance. A column is `Json` if any
+/// row contains repeated or nested elements for that column, otherwise it is `Text`.
+fn scan_columns<R: BufRead>(
+    rows: XmlRowReader<R>,
+) -> BulkDataResult<Vec<(String, Option<ColumnType>)>> {
+    let mut columns: Vec<(String, Option<ColumnType>)> = Vec::new();
+    for row in rows {
+        for (name, value) in row? {
+            let typ = column_type_from_value(&value);
+            match columns.iter_mut().find(|(column, _)| *column == name) {
+                Some((_, column_type @ None)) => *column_type = typ,
+                Some((_, Some(ColumnType::Json))) => continue,
+                Some((_, column_type)) => {
+                    if typ == Some(ColumnType::Json) {
+                        *column_type = typ
+                    }
+                }
+                None => columns.push((name, typ)),
+            }
+        }
+    }
+    Ok(columns)
+}
+
+fn map_xml_value(value: Option<&Value>, column_type: &Option<ColumnType>) -> String {
+    match (value, column_type) {
+        (None, _) | (Some(Value::Null), _) => String::new(),
+        (Some(value), Some(ColumnType::Json)) => value.to_string(),
+        (Some(value), _) => map_json_value(value),
+    }
+}
+
+pub fn schema(options: &XmlOptions) -> BulkDataResult<Schema> {
+    let Some(table_name) = options.file_path.file_name().and_then(|f| f.to_str()) else {
+        return Err(format!("Could not get filename for \"{:?}\"", &options.file_path).into())
+    };
+    let columns = scan_columns(options.reader()?)?
+        .into_iter()
+        .map(|(name, typ)| (name, typ.unwrap_or(ColumnType::Text)));
+    Schema::from_iter(table_name, columns)
+}
+
+pub async fn spool_records(
+    options: &XmlOptions,
+    record_channel: &mut RecordSpoolChannel,
+) -> RecordSpoolResult {
+    let columns = match options.reader().and_then(scan_columns) {
+        Ok(columns) => columns,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    let reader = match options.reader() {
+        Ok(reader) => reader,
+        Err(error) => return send_error_message(record_channel, error).await,
+    };
+    for (row_number, row) in reader.enumerate() {
+        let row = match row {
+            Ok(row) => row,
+            Err(error) => {
+                let message = format!("Could not read XML row {}. {}", row_number + 1, error);
+                return send_error_message(record_channel, message).await;
+            }
+        };
+        let csv_iter = columns
+            .iter()
+            .map(|(name, typ)| map_xml_value(row.get(name), typ));
+        let result = record_channel.send(Ok(csv_iter_to_string(csv_iter))).await;
+        if let Err(error) = result {
+            return Some(error);
+        }
+    }
+    None
+}
+
+#[async_trait]
+impl SourceLoader for XmlOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        schema(self)
+    }
+
+    async fn spool_records(&self, record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        spool_records(self, record_channel).await
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    static TEST_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Facilities>
+    <Facility id="1">
+        <Name>First &amp; Main</Name>
+        <Permit>A-1</Permit>
+        <Permit>A-2</Permit>
+    </Facility>
+    <Facility id="2">
+        <Name>Second</Name>
+        <Address type="mailing"><Line>PO Box 1</Line></Address>
+        <Closed/>
+    </Facility>
+</Facilities>"#;
+
+    fn test_reader() -> BulkDataResult<XmlRowReader<&'static [u8]>> {
+        XmlRowReader::new(TEST_XML.as_bytes(), "/Facilities/Facility")
+    }
+
+    #[test]
+    fn xml_row_reader_should_return_attributes_and_children_as_columns() -> BulkDataResult<()> {
+        let rows = test_reader()?.collect::<BulkDataResult<Vec<_>>>()?;
+
+        assert_eq!(2, rows.len());
+        assert_eq!(Some(&json!("1")), rows[0].get("id"));
+        assert_eq!(Some(&json!("First & Main")), rows[0].get("Name"));
+        assert_eq!(Some(&json!(null)), rows[1].get("Closed"));
+        Ok(())
+    }
+
+    #[test]
+    fn xml_row_reader_should_collect_repeated_children_into_array() -> BulkDataResult<()> {
+        let rows = test_reader()?.collect::<BulkDataResult<Vec<_>>>()?;
+
+        assert_eq!(Some(&json!(["A-1", "A-2"])), rows[0].get("Permit"));
+        Ok(())
+    }
+
+    #[test]
+    fn xml_row_reader_should_return_object_when_nested_children() -> BulkDataResult<()> {
+        let rows = test_reader()?.collect::<BulkDataResult<Vec<_>>>()?;
+
+        assert_eq!(
+            Some(&json!({"type": "mailing", "Line": "PO Box 1"})),
+            rows[1].get("Address")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn xml_row_reader_should_fail_when_row_path_empty() {
+        let result = XmlRowReader::new(TEST_XML.as_bytes(), "/");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn scan_columns_should_return_json_type_when_repeated_or_nested() -> BulkDataResult<()> {
+        let expected = vec![
+            (String::from("id"), Some(ColumnType::Text)),
+            (String::from("Name"), Some(ColumnType::Text)),
+            (String::from("Permit"), Some(ColumnType::Json)),
+            (String::from("Address"), Some(ColumnType::Json)),
+            (String::from("Closed"), None),
+        ];
+
+        let columns = scan_columns(test_reader()?)?;
+
+        assert_eq!(expected, columns);
+        Ok(())
+    }
+}
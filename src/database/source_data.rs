@@ -2,8 +2,11 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::PgPool;
+use std::collections::HashSet;
 
-use crate::bulk_loading::ColumnMetadata;
+use crate::bulk_loading::{
+    error::BulkDataResult, ArcGisAuth, ArcGisDataOptions, ColumnMetadata, SourceLoader,
+};
 
 use super::utilities::start_transaction;
 
@@ -24,6 +27,66 @@ pub struct SourceData {
 }
 
 impl SourceData {
+    /// Create an entry for each layer selected from a crawled service catalog using the layer's
+    /// schema. Table names that repeat within the selection or match an existing entry of the
+    /// load instance get a numbered suffix.
+    pub async fn from_arcgis_layers(
+        li_id: i64,
+        layer_urls: &[String],
+        auth: Option<&ArcGisAuth>,
+        pool: &PgPool,
+    ) -> BulkDataResult<Vec<Self>> {
+        let mut table_names: HashSet<String> = Self::read_many(li_id, pool)
+            .await?
+            .iter()
+            .map(|entry| entry.table_name().to_uppercase())
+            .collect();
+        let mut entries = Vec::with_capacity(layer_urls.len());
+        for layer_url in layer_urls {
+            let mut options = ArcGisDataOptions::new(layer_url)?;
+            if let Some(auth) = auth {
+                options = options.with_auth(auth.clone());
+            }
+            let (table_name, columns) = options.schema().await?.into_parts();
+            let table_name = table_name.to_uppercase();
+            let mut unique_name = table_name.clone();
+            let mut suffix = 1;
+            while !table_names.insert(unique_name.clone()) {
+                suffix += 1;
+                unique_name = format!("{}_{}", table_name, suffix);
+            }
+            let options = serde_json::to_value(&options)?;
+            entries.push(Self::new(li_id, options, unique_name, columns));
+        }
+        Ok(entries)
+    }
+
+    /// New entry that is not user generated, such as entries found by crawling a service catalog
+    pub fn new(
+        li_id: i64,
+        options: Value,
+        table_name: String,
+        columns: Vec<ColumnMetadata>,
+    ) -> Self {
+        Self {
+            sd_id: 0,
+            li_id,
+            load_source_id: 0,
+            user_generated: false,
+            options,
+            table_name,
+            columns,
+            to_load: true,
+            loaded_timestamp: None,
+            error_message: None,
+        }
+    }
+
+    #[inline]
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
     pub async fn create(mut data: Self, uid: i64, pool: &PgPool) -> Result<Self, sqlx::Error> {
         let mut transaction = start_transaction(&uid, pool).await?;
         let (sd_id, load_source_id): (i64, i16) =
@@ -42,6 +105,33 @@ impl SourceData {
         Ok(data)
     }
 
+    /// Create all entries within a single transaction so either all or none are created
+    pub async fn create_many(
+        entries: Vec<Self>,
+        uid: i64,
+        pool: &PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = start_transaction(&uid, pool).await?;
+        let mut created = Vec::with_capacity(entries.len());
+        for mut data in entries {
+            let (sd_id, load_source_id): (i64, i16) =
+                sqlx::query_as("select create_source_data_entry($1,$2,$3,$4,$5,$6)")
+                    .bind(uid)
+                    .bind(data.li_id)
+                    .bind(data.user_generated)
+                    .bind(&data.options)
+                    .bind(&data.table_name)
+                    .bind(&data.columns)
+                    .fetch_one(&mut transaction)
+                    .await?;
+            data.sd_id = sd_id;
+            data.load_source_id = load_source_id;
+            created.push(data);
+        }
+        transaction.commit().await?;
+        Ok(created)
+    }
+
     pub async fn read_single(sd_id: i64, pool: &PgPool) -> Result<Option<Self>, sqlx::Error> {
         let record: Option<SourceData> = sqlx::query_as("select get_source_data_entry($1)")
             .bind(sd_id)
@@ -1,8 +1,12 @@
 use rocket::{delete, get, post, put, serde::msgpack::MsgPack, State};
+use serde::Deserialize;
 use sqlx::postgres::PgPool;
 use workflow_engine::server::MsgPackApiResponse;
 
-use crate::database::{source_data::SourceData, users::User};
+use crate::{
+    bulk_loading::{crawl_catalog, ArcGisAuth, CatalogCrawl},
+    database::{source_data::SourceData, users::User},
+};
 
 #[post("/bulk-loading/source-data", format = "msgpack", data = "<source_data>")]
 pub async fn create_source_data(
@@ -60,3 +64,59 @@ pub async fn delete_source_data(
         Err(error) => MsgPackApiResponse::error(error),
     }
 }
+
+/// Service catalog to crawl for layers and tables
+#[derive(Deserialize)]
+pub struct ArcGisCatalogRequest {
+    url: String,
+    #[serde(default)]
+    auth: Option<ArcGisAuth>,
+}
+
+#[post("/bulk-loading/arcgis-catalog", format = "msgpack", data = "<catalog_request>")]
+pub async fn crawl_arcgis_catalog(
+    catalog_request: MsgPack<ArcGisCatalogRequest>,
+    user: User,
+) -> MsgPackApiResponse<CatalogCrawl> {
+    if !user.is_load() {
+        return MsgPackApiResponse::failure(String::from("User cannot crawl ArcGIS catalogs"));
+    }
+    let ArcGisCatalogRequest { url, auth } = catalog_request.0;
+    match crawl_catalog(&url, auth.as_ref()).await {
+        Ok(crawl) => MsgPackApiResponse::success(crawl),
+        Err(error) => MsgPackApiResponse::failure(format!("{}", error)),
+    }
+}
+
+/// Layers selected from a crawled service catalog to add to a load instance
+#[derive(Deserialize)]
+pub struct ArcGisCatalogSelection {
+    li_id: i64,
+    layer_urls: Vec<String>,
+    #[serde(default)]
+    auth: Option<ArcGisAuth>,
+}
+
+#[post("/bulk-loading/source-data/arcgis-catalog", format = "msgpack", data = "<selection>")]
+pub async fn create_arcgis_catalog_source_data(
+    selection: MsgPack<ArcGisCatalogSelection>,
+    pool: &State<PgPool>,
+    user: User,
+) -> MsgPackApiResponse<Vec<SourceData>> {
+    if !user.is_load() {
+        return MsgPackApiResponse::failure(String::from("User cannot create source data"));
+    }
+    let ArcGisCatalogSelection {
+        li_id,
+        layer_urls,
+        auth,
+    } = selection.0;
+    let entries =
+        match SourceData::from_arcgis_layers(li_id, &layer_urls, auth.as_ref(), pool).await {
+            Ok(entries) => entries,
+            Err(error) => return MsgPackApiResponse::failure(format!("{}", error)),
+        };
+    SourceData::create_many(entries, user.uid, pool)
+        .await
+        .into()
+}
@@ -5,8 +5,8 @@ mod users;
 
 use crate::database::utilities::create_db_pool;
 use bulk_loading::{
-    create_source_data, delete_source_data, read_many_source_data, read_single_source_data,
-    update_source_data,
+    crawl_arcgis_catalog, create_arcgis_catalog_source_data, create_source_data,
+    delete_source_data, read_many_source_data, read_single_source_data, update_source_data,
 };
 use data_sources::{
     create_data_source, create_data_source_contact, delete_data_source_contact, read_data_source,
@@ -34,6 +34,8 @@ pub async fn build_server() -> Result<Rocket<Build>, sqlx::Error> {
             read_many_source_data,
             update_source_data,
             delete_source_data,
+            crawl_arcgis_catalog,
+            create_arcgis_catalog_source_data,
             login,
             logout,
             create_user,
@@ -2,14 +2,25 @@ use sqlx::postgres::PgPool;
 use workflow_engine::{ApiReponse as WEApiResponse, TaskQueueRecord};
 
 use crate::{
-    bulk_loading::{error::BulkDataResult, DataLoader},
+    bulk_loading::{error::BulkDataResult, DataLoader, LoadSummary},
     database::source_data::SourceData,
 };
 
 const DB_SCHEMA: &str = "bulk_loading";
 
-async fn load_source_data(source_data: &SourceData, pool: &PgPool) -> BulkDataResult<u64> {
-    let loader = DataLoader::new(&source_data.options)?;
+async fn load_source_data(source_data: &SourceData, pool: &PgPool) -> BulkDataResult<LoadSummary> {
+    let mut total_count = 0;
+    let mut warnings = Vec::new();
+    let loader = DataLoader::new(&source_data.options)?.with_checkpoint(source_data.sd_id);
+    for loader in loader.split()? {
+        let (count, table_warnings) = load_table(loader, pool).await?.into_parts();
+        total_count += count;
+        warnings.extend(table_warnings);
+    }
+    Ok(LoadSummary::new(total_count, warnings))
+}
+
+async fn load_table(loader: DataLoader, pool: &PgPool) -> BulkDataResult<LoadSummary> {
     let schema = loader.schema().await?;
 
     sqlx::query(&format!(
@@ -44,10 +55,17 @@ pub async fn task_run_bulk_load(
         }
     };
     let mut errors = Vec::new();
+    let mut warnings = Vec::new();
     let mut results = Vec::new();
     for source_data in source_data_to_load {
         match load_source_data(&source_data, pool).await {
-            Ok(count) => results.push((source_data.sd_id, count)),
+            Ok(summary) => {
+                let (count, load_warnings) = summary.into_parts();
+                results.push((source_data.sd_id, count));
+                warnings.extend(load_warnings.into_iter().map(|warning| {
+                    format!("Warning for sd_id = {}. {}", source_data.sd_id, warning)
+                }));
+            }
             Err(error) => {
                 errors.push(format!(
                     "Error attempting to bulk load data for sd_id = {}.\n{}",
@@ -56,14 +74,14 @@ pub async fn task_run_bulk_load(
             }
         }
     }
+    let mut message = format!("Results: {:?}", results);
+    if !warnings.is_empty() {
+        message.push_str(&format!("\nWarnings: {:?}", warnings));
+    }
     if errors.is_empty() {
-        WEApiResponse::new(200, true, Some(format!("Results: {:?}", results)), None)
+        WEApiResponse::new(200, true, Some(message), None)
     } else {
-        WEApiResponse::new(
-            400,
-            false,
-            Some(format!("Results: {:?}\nErrors: {:?}", results, errors)),
-            None,
-        )
+        message.push_str(&format!("\nErrors: {:?}", errors));
+        WEApiResponse::new(400, false, Some(message), None)
     }
 }
@@ -0,0 +1,524 @@
+mod arcgis_mock;
+
+use arcgis_mock::{rain_gauge_sites, Fault, MockArcGisServer, MockAuth, MockLayer};
+use geoflow_rs::bulk_loading::{error::BulkDataResult, ArcGisDataOptions, SourceLoader};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::sync::mpsc::channel;
+
+/// Retry policy with short backoffs so retried queries do not slow the tests
+fn fast_retry() -> Value {
+    json!({
+        "max_attempts": 3,
+        "initial_backoff_ms": 1,
+        "max_backoff_ms": 5,
+        "timeout_seconds": 1,
+    })
+}
+
+fn options(server: &MockArcGisServer, extra: Value) -> BulkDataResult<ArcGisDataOptions> {
+    // Loopback hosts are rejected unless listed
+    std::env::set_var("GEOFLOW_ARCGIS_ALLOWED_HOSTS", "127.0.0.1");
+    let mut options = json!({
+        "url": server.layer_url(0),
+        "retry": fast_retry(),
+    });
+    if let (Some(options), Value::Object(extra)) = (options.as_object_mut(), extra) {
+        options.extend(extra);
+    }
+    Ok(serde_json::from_value(options)?)
+}
+
+/// Records spooled by the loader, failing with the first error sent
+async fn spool(loader: &ArcGisDataOptions) -> BulkDataResult<Vec<String>> {
+    let (mut tx, mut rx) = channel(1000);
+    let send_error = loader.spool_records(&mut tx).await;
+    drop(tx);
+    assert!(send_error.is_none());
+    let mut records = Vec::new();
+    while let Some(record) = rx.recv().await {
+        records.push(record?);
+    }
+    Ok(records)
+}
+
+/// Require tokens generated from the username and password of the `GEOFLOW_ARCGIS_SECRET_MOCK_*`
+/// variables, returning the auth options that reference them
+fn require_user_password(server: &MockArcGisServer, token_lifetime: Duration) -> Value {
+    std::env::set_var("GEOFLOW_ARCGIS_SECRET_MOCK_USERNAME", "mock-user");
+    std::env::set_var("GEOFLOW_ARCGIS_SECRET_MOCK_PASSWORD", "mock-password");
+    server.require_auth(MockAuth::UserPassword {
+        username: String::from("mock-user"),
+        password: String::from("mock-password"),
+        token_lifetime,
+    });
+    json!({
+        "type": "user_password",
+        "username_secret": "GEOFLOW_ARCGIS_SECRET_MOCK_USERNAME",
+        "password_secret": "GEOFLOW_ARCGIS_SECRET_MOCK_PASSWORD",
+    })
+}
+
+fn object_ids(records: &[String]) -> Vec<i64> {
+    records
+        .iter()
+        .filter_map(|r| r.split(',').next().and_then(|oid| oid.parse().ok()))
+        .collect()
+}
+
+fn sparse_layer() -> MockLayer {
+    sparse_layer_of(&[3, 4, 1_000, 50_000, 50_001, 900_000, 900_010])
+}
+
+fn sparse_layer_of(object_ids: &[i64]) -> MockLayer {
+    let mut layer = MockLayer::table("SparseSites")
+        .with_field("SITENAME", "esriFieldTypeString")
+        .with_max_record_count(2)
+        .without_pagination();
+    for &oid in object_ids {
+        layer = layer.with_feature(
+            json!({ "OBJECTID": oid, "SITENAME": format!("Site {}", oid) }),
+            None,
+        );
+    }
+    layer
+}
+
+/// Table of valves with a coded value domain on `STATUS` and a range domain on `PRESSURE`.
+/// Valve 4 has a status without a description and valves 2 and 4 are outside of the range.
+fn valve_inspections() -> MockLayer {
+    let mut layer = MockLayer::table("ValveInspections")
+        .with_field_json(json!({
+            "name": "STATUS",
+            "type": "esriFieldTypeSmallInteger",
+            "domain": {
+                "type": "codedValue",
+                "name": "ValveStatus",
+                "codedValues": [
+                    { "name": "Open", "code": 1 },
+                    { "name": "Closed", "code": 2 },
+                ],
+            },
+        }))
+        .with_field_json(json!({
+            "name": "PRESSURE",
+            "type": "esriFieldTypeInteger",
+            "domain": { "type": "range", "name": "Pressure", "range": [0, 150] },
+        }));
+    for (oid, status, pressure) in [(1, 1, 40), (2, 2, 200), (3, 1, 90), (4, 3, -5)] {
+        layer = layer.with_feature(
+            json!({ "OBJECTID": oid, "STATUS": status, "PRESSURE": pressure }),
+            None,
+        );
+    }
+    layer
+}
+
+#[tokio::test]
+async fn spool_records_should_page_with_offsets_when_service_supports_pagination(
+) -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_max_record_count(10)]).await?;
+    let loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!((1..=26).collect::<Vec<i64>>(), object_ids(&records));
+    let queries = server.page_queries();
+    assert_eq!(3, queries.len());
+    assert!(queries[2].contains("resultOffset=20"));
+    assert!(queries.iter().all(|q| q.contains("orderByFields=OBJECTID")));
+    assert!(queries[0].contains("f=geojson"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_read_esri_json_when_geojson_is_not_supported() -> BulkDataResult<()>
+{
+    let layer = rain_gauge_sites()
+        .with_max_record_count(10)
+        .with_query_formats("JSON");
+    let server = MockArcGisServer::start(vec![layer]).await?;
+    let loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(26, records.len());
+    assert!(server.page_queries()[0].contains("f=json"));
+    assert!(records[0].starts_with("1,Site 1,55101,"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_scrape_every_feature_when_object_ids_are_sparse(
+) -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![sparse_layer()]).await?;
+    let loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(
+        vec![3, 4, 1_000, 50_000, 50_001, 900_000, 900_010],
+        object_ids(&records)
+    );
+    let queries = server.page_queries();
+    assert_eq!(4, queries.len());
+    assert!(queries[1].ends_with("objectIds=1000%2C50000"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_fail_when_transfer_limit_cuts_page_short() -> BulkDataResult<()> {
+    let layer = rain_gauge_sites()
+        .with_max_record_count(10)
+        .with_transfer_limit(5)
+        .without_pagination();
+    let server = MockArcGisServer::start(vec![layer]).await?;
+    let loader = options(&server, json!({}))?;
+
+    let Err(error) = spool(&loader).await else {
+        panic!("Expected the truncated page to fail the scrape")
+    };
+
+    assert!(format!("{}", error).contains("returned 5 of 10 requested features"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_apply_filter_to_every_query_when_scraping_by_object_ids(
+) -> BulkDataResult<()> {
+    let layer = rain_gauge_sites()
+        .with_max_record_count(3)
+        .without_pagination();
+    let server = MockArcGisServer::start(vec![layer]).await?;
+    // Sites 11 to 26 are within the zip codes and sites 1 to 20 are within the envelope
+    let filter = json!({
+        "where": "ZIP > 55110",
+        "spatial": {
+            "geometry": {
+                "type": "envelope",
+                "xmin": -94.0,
+                "ymin": 45.0,
+                "xmax": -93.0,
+                "ymax": 45.205,
+            },
+        },
+    });
+    let loader = options(&server, json!({ "filter": filter }))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!((11..=20).collect::<Vec<i64>>(), object_ids(&records));
+    let queries = server.page_queries();
+    assert_eq!(4, queries.len());
+    assert!(queries.iter().all(|q| q.contains("ZIP") && q.contains("geometry=")));
+    assert!(queries[0].ends_with("objectIds=11%2C12%2C13"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_fail_on_count_mismatch_only_when_count_is_strict(
+) -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_reported_count(30)]).await?;
+    let loader = options(&server, json!({}))?;
+    let strict_loader = options(&server, json!({ "strict_count": true }))?;
+
+    let records = spool(&loader).await?;
+    let Err(error) = spool(&strict_loader).await else {
+        panic!("Expected the strict scrape to fail")
+    };
+
+    assert_eq!(26, records.len());
+    assert_eq!(
+        vec![String::from("Scraped 26 records but the service reported 30 records")],
+        loader.take_warnings()
+    );
+    assert!(format!("{}", error).contains("Scraped 26 records but the service reported 30"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_retry_when_service_returns_transient_errors() -> BulkDataResult<()>
+{
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_max_record_count(10)]).await?;
+    server.inject_fault(Fault::Status(503));
+    server.inject_fault(Fault::EsriError(500));
+    let loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!((1..=26).collect::<Vec<i64>>(), object_ids(&records));
+    assert_eq!(5, server.page_queries().len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_retry_when_metadata_requests_fail() -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites()]).await?;
+    server.inject_request_fault(Fault::Status(503));
+    server.inject_request_fault(Fault::EsriError(500));
+    let loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(26, records.len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_report_esri_error_when_metadata_request_fails() -> BulkDataResult<()>
+{
+    let server = MockArcGisServer::start(vec![rain_gauge_sites()]).await?;
+    server.inject_request_fault(Fault::EsriError(403));
+    let loader = options(&server, json!({}))?;
+
+    let Err(error) = spool(&loader).await else {
+        panic!("Expected the metadata request to fail")
+    };
+
+    assert!(format!("{}", error).contains("Injected fault"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_retry_when_query_times_out() -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites()]).await?;
+    server.inject_fault(Fault::Delay(Duration::from_secs(3)));
+    let loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(26, records.len());
+    assert_eq!(2, server.page_queries().len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_keep_object_id_order_when_early_page_is_delayed(
+) -> BulkDataResult<()> {
+    let layer = rain_gauge_sites()
+        .with_max_record_count(3)
+        .without_pagination();
+    let server = MockArcGisServer::start(vec![layer]).await?;
+    // The first page is answered after the pages fetched alongside it
+    server.inject_fault(Fault::Delay(Duration::from_millis(500)));
+    let loader = options(&server, json!({ "concurrency": 3 }))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!((1..=26).collect::<Vec<i64>>(), object_ids(&records));
+    assert_eq!(9, server.page_queries().len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_fail_when_service_returns_fatal_error() -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites()]).await?;
+    server.inject_fault(Fault::EsriError(400));
+    let loader = options(&server, json!({}))?;
+
+    let result = spool(&loader).await;
+
+    assert!(result.is_err());
+    assert_eq!(1, server.page_queries().len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_resume_from_checkpoint_when_scrape_failed() -> BulkDataResult<()> {
+    let checkpoint_dir = tempfile::tempdir()?;
+    let server = MockArcGisServer::start(vec![sparse_layer()]).await?;
+    let uninterrupted = spool(&options(&server, json!({}))?).await?;
+    let loader = options(&server, json!({}))?
+        .with_checkpoint_directory(checkpoint_dir.path().to_owned())
+        .with_checkpoint(1);
+    // First and second queries succeed then the third query fails
+    server.inject_fault(Fault::Delay(Duration::ZERO));
+    server.inject_fault(Fault::Delay(Duration::ZERO));
+    server.inject_fault(Fault::EsriError(400));
+    let queries_before = server.page_queries().len();
+
+    assert!(spool(&loader).await.is_err());
+    let records = spool(&loader).await?;
+
+    assert_eq!(uninterrupted, records);
+    // 3 queries of the failed scrape then the 2 queries remaining after the checkpoint
+    assert_eq!(5, server.page_queries().len() - queries_before);
+    // The checkpoint is kept until the loaded records are committed
+    assert!(checkpoint_dir.path().join("1").exists());
+    loader.complete_checkpoint()?;
+    assert!(!checkpoint_dir.path().join("1").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_resume_after_last_object_id_when_object_ids_changed(
+) -> BulkDataResult<()> {
+    let checkpoint_dir = tempfile::tempdir()?;
+    let server = MockArcGisServer::start(vec![sparse_layer()]).await?;
+    let loader = options(&server, json!({}))?
+        .with_checkpoint_directory(checkpoint_dir.path().to_owned())
+        .with_checkpoint(1);
+    // Ids 3 and 4 then 1000 and 50000 are scraped before the third query fails
+    server.inject_fault(Fault::Delay(Duration::ZERO));
+    server.inject_fault(Fault::Delay(Duration::ZERO));
+    server.inject_fault(Fault::EsriError(400));
+    assert!(spool(&loader).await.is_err());
+    // Deleting a scraped feature and adding another keeps the count but shifts the chunks of ids
+    server.replace_layer(
+        0,
+        sparse_layer_of(&[3, 1_000, 50_000, 50_001, 60_000, 900_000, 900_010]),
+    );
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(
+        vec![3, 4, 1_000, 50_000, 50_001, 60_000, 900_000, 900_010],
+        object_ids(&records)
+    );
+    let queries = server.page_queries();
+    assert!(queries[3].ends_with("objectIds=50001%2C60000"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_generate_one_token_when_token_outlives_scrape() -> BulkDataResult<()>
+{
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_max_record_count(10)]).await?;
+    let auth = require_user_password(&server, Duration::from_secs(3600));
+    let loader = options(&server, json!({ "auth": auth }))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!((1..=26).collect::<Vec<i64>>(), object_ids(&records));
+    assert_eq!(1, server.token_requests());
+    let queries = server.page_queries();
+    assert!(queries.iter().all(|q| q.contains("token=mock-token-1")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_refresh_token_when_token_expires_within_refresh_margin(
+) -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_max_record_count(10)]).await?;
+    let auth = require_user_password(&server, Duration::from_secs(30));
+    let loader = options(&server, json!({ "auth": auth }))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(26, records.len());
+    // Count and metadata requests then 3 pages, each sent with a new token
+    assert_eq!(5, server.token_requests());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_generate_new_token_when_service_rejects_token() -> BulkDataResult<()>
+{
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_max_record_count(10)]).await?;
+    let auth = require_user_password(&server, Duration::from_secs(3600));
+    server.inject_fault(Fault::Delay(Duration::ZERO));
+    server.inject_fault(Fault::RevokeTokens);
+    let loader = options(&server, json!({ "auth": auth }))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!((1..=26).collect::<Vec<i64>>(), object_ids(&records));
+    assert_eq!(2, server.token_requests());
+    let queries = server.page_queries();
+    assert_eq!(4, queries.len());
+    assert!(queries[1].contains("token=mock-token-1"));
+    assert!(queries[2].contains("token=mock-token-2"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_fail_when_static_token_is_rejected() -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites()]).await?;
+    require_user_password(&server, Duration::from_secs(3600));
+    std::env::set_var("GEOFLOW_ARCGIS_SECRET_MOCK_STATIC_TOKEN", "unknown-token");
+    let auth = json!({
+        "type": "token",
+        "token_secret": "GEOFLOW_ARCGIS_SECRET_MOCK_STATIC_TOKEN",
+    });
+    let loader = options(&server, json!({ "auth": auth }))?;
+
+    let Err(error) = spool(&loader).await else {
+        panic!("Expected the unknown token to be rejected")
+    };
+
+    assert!(format!("{}", error).contains("Invalid Token"));
+    assert_eq!(0, server.token_requests());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_send_api_key_header_when_auth_is_api_key() -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![rain_gauge_sites()]).await?;
+    server.require_auth(MockAuth::ApiKey(String::from("mock-api-key")));
+    std::env::set_var("GEOFLOW_ARCGIS_SECRET_MOCK_API_KEY", "mock-api-key");
+    std::env::set_var("GEOFLOW_ARCGIS_SECRET_MOCK_WRONG_API_KEY", "wrong-api-key");
+    let api_key_auth = |secret: &str| json!({ "type": "api_key", "api_key_secret": secret });
+    let loader = options(
+        &server,
+        json!({ "auth": api_key_auth("GEOFLOW_ARCGIS_SECRET_MOCK_API_KEY") }),
+    )?;
+    let wrong_key_loader = options(
+        &server,
+        json!({ "auth": api_key_auth("GEOFLOW_ARCGIS_SECRET_MOCK_WRONG_API_KEY") }),
+    )?;
+    let anonymous_loader = options(&server, json!({}))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(26, records.len());
+    assert!(spool(&wrong_key_loader).await.is_err());
+    let Err(error) = spool(&anonymous_loader).await else {
+        panic!("Expected the request without an API key to be rejected")
+    };
+    assert!(format!("{}", error).contains("Token Required"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_write_descriptions_and_warn_when_values_are_outside_range(
+) -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![valve_inspections()]).await?;
+    let loader = options(
+        &server,
+        json!({ "coded_value_output": "description", "validate_ranges": true }),
+    )?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(
+        vec![
+            "1,1,Open,40\n",
+            "2,2,Closed,200\n",
+            "3,1,Open,90\n",
+            "4,3,,-5\n"
+        ],
+        records
+    );
+    let warnings = loader.take_warnings();
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].starts_with("2 value(s) of field \"PRESSURE\""));
+    assert!(warnings[0].ends_with("range domain [0, 150], e.g. 200, -5"));
+    assert!(loader.take_warnings().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn spool_records_should_replace_codes_without_warnings_when_ranges_are_not_validated(
+) -> BulkDataResult<()> {
+    let server = MockArcGisServer::start(vec![valve_inspections()]).await?;
+    let loader = options(&server, json!({ "coded_value_output": "replace" }))?;
+
+    let records = spool(&loader).await?;
+
+    assert_eq!(
+        vec!["1,Open,40\n", "2,Closed,200\n", "3,Open,90\n", "4,3,-5\n"],
+        records
+    );
+    assert!(loader.take_warnings().is_empty());
+    Ok(())
+}
@@ -0,0 +1,700 @@
+//! Offline stand-in for an ArcGIS REST FeatureServer used by the ArcGIS loader tests.
+//!
+//! The server answers layer metadata (`?f=json`), `returnCountOnly`, `outStatistics`,
+//! `returnIdsOnly` and paged `query` requests in `geojson` and `json` formats for fixture layers.
+//! Where clauses are limited to `1=1` and comparisons of a field with a number or quoted string
+//! joined by `and`. Queries can also list the `objectIds` to return, as sent by OID scraping.
+//! Envelope filters are applied to point features and other spatial filters match every feature.
+//! Faults can be queued to fail or delay page queries or the other requests of a scrape. Servers
+//! can require a token generated at `tokens/generateToken` or an API key, answering unauthorized
+//! requests with the Esri 498 and 499 errors.
+#![allow(dead_code)]
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Url;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+const SERVICE_PATH: &str = "/arcgis/rest/services/Mock/FeatureServer";
+const TOKEN_PATH: &str = "/arcgis/tokens/generateToken";
+
+lazy_static! {
+    /// Separator of the terms of a where clause
+    static ref AND: Regex = Regex::new(r"(?i)\s+and\s+").unwrap();
+    /// Comparison of a field with a number or quoted string, e.g. `ZIP > 55110`
+    static ref COMPARISON: Regex =
+        Regex::new(r"^(\w+)\s*(>=|<=|<>|=|>|<)\s*('[^']*'|-?\d+(?:\.\d+)?)$").unwrap();
+}
+
+/// Feature of a fixture layer. Point coordinates are returned as is for any `outSR`
+pub struct MockFeature {
+    attributes: Map<String, Value>,
+    point: Option<(f64, f64)>,
+}
+
+/// Fixture layer served by a [MockArcGisServer]
+pub struct MockLayer {
+    name: String,
+    has_geometry: bool,
+    fields: Vec<Value>,
+    features: Vec<MockFeature>,
+    max_record_count: usize,
+    supports_pagination: bool,
+    query_formats: String,
+    reported_count: Option<usize>,
+    transfer_limit: Option<usize>,
+}
+
+impl MockLayer {
+    fn new(name: &str, has_geometry: bool) -> Self {
+        Self {
+            name: name.to_owned(),
+            has_geometry,
+            fields: vec![json!({ "name": "OBJECTID", "type": "esriFieldTypeOID" })],
+            features: Vec::new(),
+            max_record_count: 1000,
+            supports_pagination: true,
+            query_formats: String::from("JSON, geoJSON"),
+            reported_count: None,
+            transfer_limit: None,
+        }
+    }
+
+    /// Feature layer of points with an `OBJECTID` field
+    pub fn points(name: &str) -> Self {
+        Self::new(name, true)
+    }
+
+    /// Table with an `OBJECTID` field
+    pub fn table(name: &str) -> Self {
+        Self::new(name, false)
+    }
+
+    pub fn with_field(self, name: &str, field_type: &str) -> Self {
+        self.with_field_json(json!({ "name": name, "type": field_type }))
+    }
+
+    /// Add a field described by Esri field JSON, e.g. to include a domain
+    pub fn with_field_json(mut self, field: Value) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Add a feature. The attributes must include the `OBJECTID`
+    pub fn with_feature(mut self, attributes: Value, point: Option<(f64, f64)>) -> Self {
+        let Value::Object(attributes) = attributes else {
+            panic!("Feature attributes must be an object")
+        };
+        self.features.push(MockFeature { attributes, point });
+        self
+    }
+
+    pub fn with_max_record_count(mut self, max_record_count: usize) -> Self {
+        self.max_record_count = max_record_count;
+        self
+    }
+
+    /// Force clients to scrape the layer by object id ranges
+    pub fn without_pagination(mut self) -> Self {
+        self.supports_pagination = false;
+        self
+    }
+
+    /// Replace the `supportedQueryFormats` of the layer (e.g. `JSON` to disable geojson)
+    pub fn with_query_formats(mut self, query_formats: &str) -> Self {
+        self.query_formats = query_formats.to_owned();
+        self
+    }
+
+    /// Answer count queries with the count instead of the number of matching features
+    pub fn with_reported_count(mut self, count: usize) -> Self {
+        self.reported_count = Some(count);
+        self
+    }
+
+    /// Return at most `transfer_limit` features per page, even when the layer's advertised max
+    /// record count is larger
+    pub fn with_transfer_limit(mut self, transfer_limit: usize) -> Self {
+        self.transfer_limit = Some(transfer_limit);
+        self
+    }
+
+    fn oid(feature: &MockFeature) -> i64 {
+        feature.attributes["OBJECTID"].as_i64().unwrap_or_default()
+    }
+
+    fn metadata(&self) -> Value {
+        let mut metadata = json!({
+            "name": self.name,
+            "type": if self.has_geometry { "Feature Layer" } else { "Table" },
+            "maxRecordCount": self.max_record_count,
+            "fields": self.fields,
+            "objectIdField": "OBJECTID",
+            "supportedQueryFormats": self.query_formats,
+            "supportsPagination": self.supports_pagination,
+            "supportsStatistics": true,
+            "advancedQueryCapabilities": {
+                "supportsPagination": self.supports_pagination,
+                "supportsStatistics": true,
+            },
+            "hasZ": false,
+            "hasM": false,
+        });
+        if self.has_geometry {
+            metadata["geometryType"] = json!("esriGeometryPoint");
+        }
+        metadata
+    }
+
+    /// Features matching the where clause and spatial filter, ordered by object id. Fails when
+    /// the where clause is not supported.
+    fn matching_features(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Result<Vec<&MockFeature>, String> {
+        let comparisons = match param(params, "where") {
+            "" => vec![],
+            where_clause => AND
+                .split(where_clause)
+                .map(|term| term.trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace()))
+                .filter(|term| *term != "1=1")
+                .map(|term| {
+                    COMPARISON
+                        .captures(term)
+                        .ok_or_else(|| format!("Unsupported where clause term \"{}\"", term))
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+        };
+        let object_ids: Option<Vec<i64>> = match param(params, "objectIds") {
+            "" => None,
+            object_ids => Some(
+                object_ids
+                    .split(',')
+                    .map(|id| {
+                        id.trim()
+                            .parse()
+                            .map_err(|_| format!("Invalid object id \"{}\"", id))
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
+        };
+        let envelope: Option<Value> = serde_json::from_str(param(params, "geometry"))
+            .ok()
+            .filter(|g: &Value| g.get("xmin").is_some());
+        let mut features: Vec<&MockFeature> = self
+            .features
+            .iter()
+            .filter(|f| {
+                comparisons.iter().all(|c| {
+                    let value = f.attributes.get(&c[1]).unwrap_or(&Value::Null);
+                    compare(value, &c[2], &c[3])
+                })
+            })
+            .filter(|f| {
+                object_ids
+                    .as_ref()
+                    .map_or(true, |ids| ids.contains(&Self::oid(f)))
+            })
+            .filter(|f| match (&envelope, f.point) {
+                (Some(envelope), Some(point)) => in_envelope(envelope, point),
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .collect();
+        features.sort_by_key(|f| Self::oid(f));
+        Ok(features)
+    }
+
+    fn statistics(&self, features: &[&MockFeature], out_statistics: &str) -> Value {
+        let statistics: Vec<Value> = serde_json::from_str(out_statistics).unwrap_or_default();
+        let mut attributes = Map::new();
+        for statistic in statistics {
+            let field = statistic["onStatisticField"].as_str().unwrap_or_default();
+            let values = features
+                .iter()
+                .filter_map(|f| f.attributes.get(field).and_then(|v| v.as_i64()));
+            let value = match statistic["statisticType"].as_str() {
+                Some("max") => values.max(),
+                Some("min") => values.min(),
+                _ => None,
+            };
+            let name = statistic["outStatisticFieldName"]
+                .as_str()
+                .unwrap_or_default();
+            attributes.insert(name.to_owned(), json!(value));
+        }
+        json!({ "features": [{ "attributes": attributes }] })
+    }
+
+    /// Attributes of the feature in field order, limited to the out fields
+    fn feature_attributes(&self, feature: &MockFeature, out_fields: &str) -> Map<String, Value> {
+        let out_fields: Vec<&str> = out_fields.split(',').map(|f| f.trim()).collect();
+        self.fields
+            .iter()
+            .filter_map(|field| field["name"].as_str())
+            .filter(|name| {
+                out_fields.contains(&"*") || out_fields.iter().any(|f| f.eq_ignore_ascii_case(name))
+            })
+            .map(|name| {
+                let value = feature.attributes.get(name).cloned().unwrap_or(Value::Null);
+                (name.to_owned(), value)
+            })
+            .collect()
+    }
+
+    fn page(&self, features: &[&MockFeature], params: &HashMap<String, String>) -> Value {
+        let offset: usize = param(params, "resultOffset").parse().unwrap_or(0);
+        let count = param(params, "resultRecordCount")
+            .parse()
+            .unwrap_or(self.max_record_count)
+            .min(self.max_record_count)
+            .min(self.transfer_limit.unwrap_or(usize::MAX));
+        let out_fields = match param(params, "outFields") {
+            "" => "*",
+            out_fields => out_fields,
+        };
+        let page: Vec<&&MockFeature> = features.iter().skip(offset).take(count).collect();
+        let exceeded_transfer_limit = offset + page.len() < features.len();
+        if param(params, "f") == "geojson" {
+            let features: Vec<Value> = page
+                .iter()
+                .map(|f| {
+                    json!({
+                        "type": "Feature",
+                        "id": Self::oid(f),
+                        "geometry": f.point.map(|(x, y)| json!({
+                            "type": "Point",
+                            "coordinates": [x, y],
+                        })),
+                        "properties": self.feature_attributes(f, out_fields),
+                    })
+                })
+                .collect();
+            json!({
+                "type": "FeatureCollection",
+                "features": features,
+                "properties": { "exceededTransferLimit": exceeded_transfer_limit },
+            })
+        } else {
+            let features: Vec<Value> = page
+                .iter()
+                .map(|f| {
+                    let mut feature =
+                        json!({ "attributes": self.feature_attributes(f, out_fields) });
+                    if self.has_geometry {
+                        feature["geometry"] = match f.point {
+                            Some((x, y)) => json!({ "x": x, "y": y }),
+                            None => json!({ "x": null, "y": null }),
+                        };
+                    }
+                    feature
+                })
+                .collect();
+            json!({
+                "objectIdFieldName": "OBJECTID",
+                "fields": self.fields,
+                "features": features,
+                "exceededTransferLimit": exceeded_transfer_limit,
+            })
+        }
+    }
+
+    fn query(&self, params: &HashMap<String, String>) -> Value {
+        let features = match self.matching_features(params) {
+            Ok(features) => features,
+            Err(message) => return esri_error(400, &message),
+        };
+        if param(params, "returnCountOnly") == "true" {
+            return json!({ "count": self.reported_count.unwrap_or(features.len()) });
+        }
+        if param(params, "returnIdsOnly") == "true" {
+            let object_ids: Vec<i64> = features.iter().map(|f| Self::oid(f)).collect();
+            return json!({ "objectIdFieldName": "OBJECTID", "objectIds": object_ids });
+        }
+        if let Some(out_statistics) = params.get("outStatistics") {
+            return self.statistics(&features, out_statistics);
+        }
+        self.page(&features, params)
+    }
+}
+
+/// Compare an attribute with the literal of a where clause. Numbers are compared as numbers and
+/// other values as strings.
+fn compare(value: &Value, operator: &str, literal: &str) -> bool {
+    let ordering = match (value, literal.strip_prefix('\'')) {
+        (Value::Null, _) => return false,
+        (_, Some(text)) => {
+            let text = text.trim_end_matches('\'');
+            match value {
+                Value::String(value) => value.as_str().cmp(text),
+                value => value.to_string().as_str().cmp(text),
+            }
+        }
+        (_, None) => {
+            let (Some(value), Ok(number)) = (value.as_f64(), literal.parse::<f64>()) else {
+                return false
+            };
+            value.total_cmp(&number)
+        }
+    };
+    match operator {
+        "=" => ordering.is_eq(),
+        "<>" => ordering.is_ne(),
+        ">" => ordering.is_gt(),
+        ">=" => ordering.is_ge(),
+        "<" => ordering.is_lt(),
+        "<=" => ordering.is_le(),
+        _ => false,
+    }
+}
+
+fn in_envelope(envelope: &Value, (x, y): (f64, f64)) -> bool {
+    let bound = |name: &str| envelope[name].as_f64().unwrap_or(f64::NAN);
+    (bound("xmin")..=bound("xmax")).contains(&x) && (bound("ymin")..=bound("ymax")).contains(&y)
+}
+
+fn param<'p>(params: &'p HashMap<String, String>, name: &str) -> &'p str {
+    params.get(name).map(|v| v.as_str()).unwrap_or_default()
+}
+
+/// Failure applied to the next request answered by the server
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Respond with the HTTP status and an empty body
+    Status(u16),
+    /// Respond with a 200 status and an Esri error object with the code
+    EsriError(i64),
+    /// Wait before answering the query normally
+    Delay(Duration),
+    /// Revoke every generated token before checking the request's token
+    RevokeTokens,
+}
+
+/// Credentials required by a secured [MockArcGisServer]
+#[derive(Debug, Clone)]
+pub enum MockAuth {
+    /// Tokens generated from the username and password, valid for the lifetime
+    UserPassword {
+        username: String,
+        password: String,
+        token_lifetime: Duration,
+    },
+    /// API key sent in the `X-Esri-Authorization` header as a bearer token
+    ApiKey(String),
+}
+
+struct MockState {
+    layers: Vec<MockLayer>,
+    faults: VecDeque<Fault>,
+    /// Faults of metadata, count and object id requests
+    request_faults: VecDeque<Fault>,
+    page_queries: Vec<String>,
+    auth: Option<MockAuth>,
+    /// Generated tokens and the instant they expire
+    tokens: HashMap<String, Instant>,
+    token_requests: usize,
+}
+
+impl MockState {
+    /// Esri error of a request without valid credentials. [None] if the request is authorized
+    fn auth_error(&self, request: &MockRequest, params: &HashMap<String, String>) -> Option<Value> {
+        match self.auth.as_ref()? {
+            MockAuth::UserPassword { .. } => match params.get("token") {
+                Some(token) => match self.tokens.get(token) {
+                    Some(expires) if Instant::now() < *expires => None,
+                    _ => Some(esri_error(498, "Invalid Token")),
+                },
+                None => Some(esri_error(499, "Token Required")),
+            },
+            MockAuth::ApiKey(api_key) => match request.headers.get("x-esri-authorization") {
+                Some(value) if *value == format!("Bearer {}", api_key) => None,
+                Some(_) => Some(esri_error(498, "Invalid Token")),
+                None => Some(esri_error(499, "Token Required")),
+            },
+        }
+    }
+
+    /// Answer a `generateToken` request with a new token when the form credentials match
+    fn generate_token(&mut self, request: &MockRequest) -> Value {
+        let (username, password, token_lifetime) = match &self.auth {
+            Some(MockAuth::UserPassword {
+                username,
+                password,
+                token_lifetime,
+            }) => (username, password, *token_lifetime),
+            _ => return esri_error(400, "Token generation is not enabled"),
+        };
+        let form: HashMap<String, String> = url::form_urlencoded::parse(request.body.as_bytes())
+            .into_owned()
+            .collect();
+        if param(&form, "username") != username || param(&form, "password") != password {
+            return esri_error(400, "Unable to generate token.");
+        }
+        self.token_requests += 1;
+        let token = format!("mock-token-{}", self.token_requests);
+        let expires = SystemTime::now() + token_lifetime;
+        let expires = expires.duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.tokens
+            .insert(token.clone(), Instant::now() + token_lifetime);
+        json!({ "token": token, "expires": expires.as_millis() as i64, "ssl": false })
+    }
+}
+
+/// Request received by the server
+struct MockRequest {
+    target: String,
+    /// Headers with lower case names
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+enum Route {
+    Metadata(usize),
+    Query(usize),
+    GenerateToken,
+    NotFound,
+}
+
+impl Route {
+    fn from_path(path: &str) -> Self {
+        if path == TOKEN_PATH {
+            return Self::GenerateToken;
+        }
+        let Some(rest) = path.strip_prefix(SERVICE_PATH) else {
+            return Self::NotFound
+        };
+        let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+        match segments.as_slice() {
+            [id] => id.parse().map(Self::Metadata).unwrap_or(Self::NotFound),
+            [id, "query"] => id.parse().map(Self::Query).unwrap_or(Self::NotFound),
+            _ => Self::NotFound,
+        }
+    }
+}
+
+/// Embedded HTTP server answering ArcGIS REST requests for fixture layers. The server stops when
+/// dropped.
+pub struct MockArcGisServer {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockArcGisServer {
+    /// Start a server on a free local port. Layers are served with their index as the layer id
+    pub async fn start(layers: Vec<MockLayer>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState {
+            layers,
+            faults: VecDeque::new(),
+            request_faults: VecDeque::new(),
+            page_queries: Vec::new(),
+            auth: None,
+            tokens: HashMap::new(),
+            token_requests: 0,
+        }));
+        let server_state = state.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(stream, server_state.clone()));
+            }
+        });
+        Ok(Self {
+            address,
+            state,
+            handle,
+        })
+    }
+
+    pub fn service_url(&self) -> String {
+        format!("http://{}{}", self.address, SERVICE_PATH)
+    }
+
+    pub fn layer_url(&self, layer_id: usize) -> String {
+        format!("{}/{}", self.service_url(), layer_id)
+    }
+
+    /// Queue a fault for the next page query. Faults are applied in the order they are queued
+    pub fn inject_fault(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    /// Queue a fault for the next request that is not a page query, such as a metadata request
+    pub fn inject_request_fault(&self, fault: Fault) {
+        self.state.lock().unwrap().request_faults.push_back(fault);
+    }
+
+    /// Replace the features and metadata of a layer, as when a service is edited between scrapes
+    pub fn replace_layer(&self, layer_id: usize, layer: MockLayer) {
+        self.state.lock().unwrap().layers[layer_id] = layer;
+    }
+
+    /// Query strings of the page queries received, including queries answered with a fault
+    pub fn page_queries(&self) -> Vec<String> {
+        self.state.lock().unwrap().page_queries.clone()
+    }
+
+    /// Reject metadata and query requests without the credentials
+    pub fn require_auth(&self, auth: MockAuth) {
+        self.state.lock().unwrap().auth = Some(auth);
+    }
+
+    /// Number of tokens generated by the server
+    pub fn token_requests(&self) -> usize {
+        self.state.lock().unwrap().token_requests
+    }
+}
+
+impl Drop for MockArcGisServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+fn is_page_query(params: &HashMap<String, String>) -> bool {
+    param(params, "returnCountOnly") != "true"
+        && param(params, "returnIdsOnly") != "true"
+        && !params.contains_key("outStatistics")
+}
+
+fn esri_error(code: i64, message: &str) -> Value {
+    json!({ "error": { "code": code, "message": message } })
+}
+
+/// Status and JSON body of the response to the request
+async fn respond(request: &MockRequest, state: &Mutex<MockState>) -> (u16, Value) {
+    let Ok(url) = Url::parse(&format!("http://localhost{}", request.target)) else {
+        return (400, esri_error(400, "Invalid request"))
+    };
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let route = Route::from_path(url.path());
+    if let Route::GenerateToken = route {
+        return (200, state.lock().unwrap().generate_token(request));
+    }
+    let fault = {
+        let mut state = state.lock().unwrap();
+        if matches!(route, Route::Query(_)) && is_page_query(&params) {
+            state
+                .page_queries
+                .push(url.query().unwrap_or_default().to_owned());
+            state.faults.pop_front()
+        } else {
+            state.request_faults.pop_front()
+        }
+    };
+    match fault {
+        Some(Fault::Status(status)) => return (status, Value::Null),
+        Some(Fault::EsriError(code)) => return (200, esri_error(code, "Injected fault")),
+        Some(Fault::Delay(delay)) => tokio::time::sleep(delay).await,
+        Some(Fault::RevokeTokens) => state.lock().unwrap().tokens.clear(),
+        None => {}
+    }
+    let state = state.lock().unwrap();
+    if let Some(error) = state.auth_error(request, &params) {
+        return (200, error);
+    }
+    let layer = match route {
+        Route::Metadata(id) | Route::Query(id) => state.layers.get(id),
+        Route::GenerateToken | Route::NotFound => None,
+    };
+    let Some(layer) = layer else {
+        return (200, esri_error(400, "Invalid URL"))
+    };
+    match route {
+        Route::Metadata(_) => (200, layer.metadata()),
+        _ => (200, layer.query(&params)),
+    }
+}
+
+/// Read the request line, headers and body of a request. [None] if the connection closes first
+async fn read_request(stream: &mut TcpStream) -> Option<MockRequest> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    let header_end = loop {
+        if let Some(index) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break index + 4;
+        }
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let target = lines.next()?.split_whitespace().nth(1).unwrap_or("/");
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_owned()))
+        .collect();
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    while request.len() < header_end + content_length {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+    Some(MockRequest {
+        target: target.to_owned(),
+        headers,
+        body: String::from_utf8_lossy(&request[header_end..]).into_owned(),
+    })
+}
+
+async fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return
+    };
+    let (status, body) = respond(&request, &state).await;
+    let body = if body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Point layer of 26 rain gauge sites with string, integer, date, double and global id fields
+pub fn rain_gauge_sites() -> MockLayer {
+    let mut layer = MockLayer::points("RainGaugeSites")
+        .with_field("SITENAME", "esriFieldTypeString")
+        .with_field("ZIP", "esriFieldTypeInteger")
+        .with_field("GPSDATE", "esriFieldTypeDate")
+        .with_field("GPSACCURACY", "esriFieldTypeDouble")
+        .with_field("GLOBALID", "esriFieldTypeGlobalID");
+    for oid in 1..=26 {
+        layer = layer.with_feature(
+            json!({
+                "OBJECTID": oid,
+                "SITENAME": format!("Site {}", oid),
+                "ZIP": 55100 + oid,
+                "GPSDATE": 1_600_000_000_000_i64 + oid * 86_400_000,
+                "GPSACCURACY": oid as f64 / 4.0,
+                "GLOBALID": format!("{{00000000-0000-0000-0000-{:012}}}", oid),
+            }),
+            Some((-93.0 - oid as f64 / 100.0, 45.0 + oid as f64 / 100.0)),
+        );
+    }
+    layer
+}
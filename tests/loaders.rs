@@ -1,7 +1,16 @@
+mod arcgis_mock;
+
+use arcgis_mock::{rain_gauge_sites, MockArcGisServer};
+use async_trait::async_trait;
 use geoflow_rs::{
-    bulk_loading::{DataLoader, ColumnType},
+    bulk_loading::{
+        error::BulkDataResult, ColumnType, CopyOptions, DataLoader, DataOptions,
+        GeometryDimension, LoaderRegistration, LoaderRegistry, RecordSpoolChannel,
+        RecordSpoolResult, Schema, SourceLoader,
+    },
     database::utilities::create_db_pool,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 const DB_SCHEMA: &str = "bulk_loading";
@@ -82,7 +91,7 @@ async fn delimited_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
     
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(299_u64, records_loaded);
 
@@ -195,7 +204,7 @@ async fn excel_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(2000_u64, records_loaded);
 
@@ -207,11 +216,11 @@ async fn shapefile_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     let expected_table_name = "shape_data_test";
     let expected_column_names = [
         ("item_id", ColumnType::Text),
-        ("ai_id", ColumnType::Number),
-        ("int_doc_id", ColumnType::Number),
+        ("ai_id", ColumnType::BigInt),
+        ("int_doc_id", ColumnType::BigInt),
         ("si_type", ColumnType::Text),
         ("si_cat", ColumnType::Text),
-        ("si_id", ColumnType::Number),
+        ("si_id", ColumnType::BigInt),
         ("si_cat_des", ColumnType::Text),
         ("si_type_de", ColumnType::Text),
         ("ai_name", ColumnType::Text),
@@ -278,6 +287,13 @@ async fn shapefile_data_loading() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(&ex_field.1, field.column_type());
     }
 
+    let geometry_metadata = fields
+        .iter()
+        .find(|f| f.name() == "geometry")
+        .and_then(|f| f.geometry_metadata())
+        .expect("geometry metadata");
+    assert_eq!(Some(4326), geometry_metadata.srid());
+
     let pool = create_db_pool().await?;
     sqlx::query(&format!(
         "drop table if exists {}.{}",
@@ -290,7 +306,7 @@ async fn shapefile_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(1244_u64, records_loaded);
 
@@ -357,6 +373,13 @@ async fn geojson_data_loading() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
     }
 
+    let geometry_metadata = fields
+        .iter()
+        .find(|f| f.name() == "geometry")
+        .and_then(|f| f.geometry_metadata())
+        .expect("geometry metadata");
+    assert_eq!(Some(4326), geometry_metadata.srid());
+
     let pool = create_db_pool().await?;
     sqlx::query(&format!(
         "drop table if exists {}.{}",
@@ -369,13 +392,58 @@ async fn geojson_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(26_u64, records_loaded);
 
     Ok(())
 }
 
+#[tokio::test]
+async fn geojson_z_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_table_name = "geojson_z_data_test";
+    let expected_column_names = [
+        ("asset_id", ColumnType::Text),
+        ("asset_type", ColumnType::Text),
+        ("rim_elev", ColumnType::Number),
+        ("geometry", ColumnType::Geometry),
+    ];
+
+    let loader = DataLoader::new(&json!({
+        "file_path": "tests/geojson z data test.geojson",
+    }))?;
+    let schema = loader.schema().await?;
+
+    assert_eq!(expected_table_name, schema.table_name());
+
+    let fields = schema.columns();
+    assert_eq!(expected_column_names.len(), fields.len());
+    for (ex_field, field) in expected_column_names.iter().zip(fields) {
+        assert_eq!(ex_field.0, field.name());
+        assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
+    }
+    let geometry_metadata = fields[3].geometry_metadata().expect("geometry metadata");
+    assert_eq!(&GeometryDimension::Xyz, geometry_metadata.dimension());
+
+    let pool = create_db_pool().await?;
+    sqlx::query(&format!(
+        "drop table if exists {}.{}",
+        DB_SCHEMA,
+        schema.table_name()
+    ))
+    .execute(&pool)
+    .await?;
+    let create_statement = schema.create_statement(DB_SCHEMA);
+    sqlx::query(&create_statement).execute(&pool).await?;
+
+    let copy_options = schema.copy_options(DB_SCHEMA);
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
+
+    assert_eq!(4_u64, records_loaded);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn parquet_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     //https://arcgis.metc.state.mn.us/server/rest/services/ESWastewater/RainGaugeSites/FeatureServer
@@ -426,6 +494,13 @@ async fn parquet_data_loading() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
     }
 
+    let geometry_metadata = fields
+        .iter()
+        .find(|f| f.name() == "geometry")
+        .and_then(|f| f.geometry_metadata())
+        .expect("geometry metadata");
+    assert_eq!(Some(4326), geometry_metadata.srid());
+
     let pool = create_db_pool().await?;
     sqlx::query(&format!(
         "drop table if exists {}.{}",
@@ -438,7 +513,7 @@ async fn parquet_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(10000_u64, records_loaded);
 
@@ -519,7 +594,7 @@ async fn ipc_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(299_u64, records_loaded);
 
@@ -528,46 +603,22 @@ async fn ipc_data_loading() -> Result<(), Box<dyn std::error::Error>> {
 
 #[tokio::test]
 async fn arcgis_data_loading() -> Result<(), Box<dyn std::error::Error>> {
-    //https://arcgis.metc.state.mn.us/server/rest/services/ESWastewater/RainGaugeSites/FeatureServer
+    // Loopback hosts are rejected unless listed
+    std::env::set_var("GEOFLOW_ARCGIS_ALLOWED_HOSTS", "127.0.0.1");
+    let server = MockArcGisServer::start(vec![rain_gauge_sites().with_max_record_count(10)]).await?;
     let expected_table_name = "raingaugesites";
     let expected_column_names = [
         ("objectid", ColumnType::Integer),
-        ("uniqueid", ColumnType::Integer),
-        ("featurelabel", ColumnType::Text),
         ("sitename", ColumnType::Text),
-        ("featurestatus", ColumnType::Text),
-        ("featureowner", ColumnType::Text),
-        ("wwtp", ColumnType::Text),
-        ("locationdescription", ColumnType::Text),
-        ("address", ColumnType::Text),
-        ("ctu_name", ColumnType::Text),
-        ("co_name", ColumnType::Text),
         ("zip", ColumnType::Integer),
-        ("notes", ColumnType::Text),
         ("gpsdate", ColumnType::Timestamp),
         ("gpsaccuracy", ColumnType::DoublePrecision),
-        ("gpsdatasource", ColumnType::Text),
-        ("gpsnorthing", ColumnType::DoublePrecision),
-        ("gpseasting", ColumnType::DoublePrecision),
-        ("yearopened", ColumnType::Integer),
-        ("yearclosed", ColumnType::Integer),
-        ("datasource", ColumnType::Text),
-        ("datasourcedate", ColumnType::Timestamp),
-        ("assetid", ColumnType::Text),
-        ("parentid", ColumnType::Text),
-        ("eamasseturl", ColumnType::Text),
-        ("majorwatershed", ColumnType::Text),
-        ("secondarywatershed", ColumnType::Text),
-        ("created_user", ColumnType::Text),
-        ("created_date", ColumnType::Timestamp),
-        ("last_edited_user", ColumnType::Text),
-        ("last_edited_date", ColumnType::Timestamp),
         ("globalid", ColumnType::UUID),
         ("geometry", ColumnType::Geometry),
     ];
 
     let loader = DataLoader::new(&json!({
-        "url": "https://arcgis.metc.state.mn.us/server/rest/services/ESWastewater/RainGaugeSites/FeatureServer/0",
+        "url": server.layer_url(0),
     }))?;
     let schema = loader.schema().await?;
 
@@ -592,7 +643,7 @@ async fn arcgis_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query(&create_statement).execute(&pool).await?;
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(26_u64, records_loaded);
 
@@ -616,7 +667,7 @@ async fn avro_data_loading() -> Result<(), Box<dyn std::error::Error>> {
         ("enum", ColumnType::Text),
         ("record", ColumnType::Json),
         ("fixed", ColumnType::SmallIntArray),
-        ("decimal", ColumnType::SmallIntArray),
+        ("decimal", ColumnType::Number),
         ("uuid", ColumnType::UUID),
         ("date", ColumnType::Date),
         ("time_millis", ColumnType::Time),
@@ -657,9 +708,333 @@ async fn avro_data_loading() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let copy_options = schema.copy_options(DB_SCHEMA);
-    let records_loaded = loader.load_data(copy_options, &pool).await?;
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
 
     assert_eq!(20_u64, records_loaded);
 
     Ok(())
 }
+
+#[tokio::test]
+async fn xml_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_table_name = "xml_data_test";
+    let expected_column_names = [
+        ("id", ColumnType::Text),
+        ("status", ColumnType::Text),
+        ("name", ColumnType::Text),
+        ("address", ColumnType::Text),
+        ("city", ColumnType::Text),
+        ("permit", ColumnType::Json),
+    ];
+
+    let loader = DataLoader::new(&json!({
+        "file_path": "tests/xml data test.xml",
+        "row_path": "/Facilities/Facility",
+    }))?;
+    let schema = loader.schema().await?;
+
+    assert_eq!(expected_table_name, schema.table_name());
+
+    let fields = schema.columns();
+    assert_eq!(expected_column_names.len(), fields.len());
+    for (ex_field, field) in expected_column_names.iter().zip(fields) {
+        assert_eq!(ex_field.0, field.name());
+        assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
+    }
+
+    let pool = create_db_pool().await?;
+    sqlx::query(&format!(
+        "drop table if exists {}.{}",
+        DB_SCHEMA,
+        schema.table_name()
+    ))
+    .execute(&pool)
+    .await?;
+    let create_statement = schema.create_statement(DB_SCHEMA);
+    sqlx::query(&create_statement).execute(&pool).await?;
+
+    let copy_options = schema.copy_options(DB_SCHEMA);
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
+
+    assert_eq!(5_u64, records_loaded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fixed_width_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_table_name = "fixed_width_data_test";
+    let expected_column_names = [
+        ("registrant_id", ColumnType::Text),
+        ("registrant_name", ColumnType::Text),
+        ("city", ColumnType::Text),
+        ("fee_amount", ColumnType::Number),
+        ("status", ColumnType::Text),
+    ];
+
+    let loader = DataLoader::new(&json!({
+        "format": "fixed_width",
+        "file_path": "tests/fixed width data test.txt",
+        "columns": [
+            { "name": "registrant_id", "start": 1, "length": 5 },
+            { "name": "registrant_name", "start": 6, "length": 30 },
+            { "name": "city", "start": 36, "length": 15 },
+            { "name": "fee_amount", "start": 51, "length": 8, "implied_decimal": 2 },
+            { "name": "status", "start": 59, "length": 1 },
+        ],
+    }))?;
+    let schema = loader.schema().await?;
+
+    assert_eq!(expected_table_name, schema.table_name());
+
+    let fields = schema.columns();
+    assert_eq!(expected_column_names.len(), fields.len());
+    for (ex_field, field) in expected_column_names.iter().zip(fields) {
+        assert_eq!(ex_field.0, field.name());
+        assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
+    }
+
+    let pool = create_db_pool().await?;
+    sqlx::query(&format!(
+        "drop table if exists {}.{}",
+        DB_SCHEMA,
+        schema.table_name()
+    ))
+    .execute(&pool)
+    .await?;
+    let create_statement = schema.create_statement(DB_SCHEMA);
+    sqlx::query(&create_statement).execute(&pool).await?;
+
+    let copy_options = schema.copy_options(DB_SCHEMA);
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
+
+    assert_eq!(5_u64, records_loaded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn excel_sheet_pattern_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_table_names = ["excel_data_test_tblust_db"];
+
+    let loaders = DataLoader::new(&json!({
+        "file_path": "tests/excel data test.xlsx",
+        "sheet_pattern": "^tbl",
+    }))?
+    .split()?;
+
+    assert_eq!(expected_table_names.len(), loaders.len());
+
+    let pool = create_db_pool().await?;
+    for (expected_table_name, loader) in expected_table_names.iter().zip(loaders) {
+        let schema = loader.schema().await?;
+
+        assert_eq!(*expected_table_name, schema.table_name());
+        assert_eq!(73, schema.columns().len());
+
+        sqlx::query(&format!(
+            "drop table if exists {}.{}",
+            DB_SCHEMA,
+            schema.table_name()
+        ))
+        .execute(&pool)
+        .await?;
+        let create_statement = schema.create_statement(DB_SCHEMA);
+        sqlx::query(&create_statement).execute(&pool).await?;
+
+        let copy_options = schema.copy_options(DB_SCHEMA);
+        let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
+
+        assert_eq!(2000_u64, records_loaded);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dbf_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_table_name = "dbf_data_test";
+    let expected_column_names = [
+        ("code", ColumnType::Text),
+        ("descr", ColumnType::Text),
+        ("sort_ord", ColumnType::SmallInt),
+        ("rate", ColumnType::Number),
+        ("active", ColumnType::Boolean),
+        ("updated", ColumnType::Date),
+    ];
+
+    let loader = DataLoader::new(&json!({
+        "file_path": "tests/dbf data test.dbf",
+    }))?;
+    let schema = loader.schema().await?;
+
+    assert_eq!(expected_table_name, schema.table_name());
+
+    let fields = schema.columns();
+    assert_eq!(expected_column_names.len(), fields.len());
+    for (ex_field, field) in expected_column_names.iter().zip(fields) {
+        assert_eq!(ex_field.0, field.name());
+        assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
+    }
+
+    let pool = create_db_pool().await?;
+    sqlx::query(&format!(
+        "drop table if exists {}.{}",
+        DB_SCHEMA,
+        schema.table_name()
+    ))
+    .execute(&pool)
+    .await?;
+    let create_statement = schema.create_statement(DB_SCHEMA);
+    sqlx::query(&create_statement).execute(&pool).await?;
+
+    let copy_options = schema.copy_options(DB_SCHEMA);
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
+
+    assert_eq!(5_u64, records_loaded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_shapefile_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_table_name = "empty_shape_data_test";
+    let expected_column_names = [
+        ("site_id", ColumnType::BigInt),
+        ("site_name", ColumnType::Text),
+        ("geometry", ColumnType::Geometry),
+    ];
+
+    let loader = DataLoader::new(&json!({
+        "file_path": "tests/empty-shape-data-test/empty_shape_data_test.shp",
+    }))?;
+    let schema = loader.schema().await?;
+
+    assert_eq!(expected_table_name, schema.table_name());
+
+    let fields = schema.columns();
+    assert_eq!(expected_column_names.len(), fields.len());
+    for (ex_field, field) in expected_column_names.iter().zip(fields) {
+        assert_eq!(ex_field.0, field.name());
+        assert_eq!(&ex_field.1, field.column_type(), "field = {}", ex_field.0);
+    }
+
+    let pool = create_db_pool().await?;
+    sqlx::query(&format!(
+        "drop table if exists {}.{}",
+        DB_SCHEMA,
+        schema.table_name()
+    ))
+    .execute(&pool)
+    .await?;
+    let create_statement = schema.create_statement(DB_SCHEMA);
+    sqlx::query(&create_statement).execute(&pool).await?;
+
+    let copy_options = schema.copy_options(DB_SCHEMA);
+    let records_loaded = loader.load_data(copy_options, &pool).await?.record_count();
+
+    assert_eq!(0_u64, records_loaded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sniffed_format_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let sources = [
+        ("tests/parquet data test.parquet", "parquet download", "parquet"),
+        ("tests/avro data test.avro", "avro download", "avro"),
+        ("tests/geojson data test.geojson", "geojson download.dat", "geojson"),
+        ("tests/geojson data test.geojson", "geojson download.txt", "geojson"),
+        ("tests/parquet data test.parquet", "parquet download.csv", "parquet"),
+        ("tests/delimited data test.csv", "delimited download.txt", "delimited"),
+        ("tests/excel data test.xlsx", "excel download", "excel"),
+        ("tests/excel data test.xlsx", "excel download.csv", "excel"),
+    ];
+    for (source, file_name, expected_format) in sources {
+        let file_path = temp_dir.path().join(file_name);
+        std::fs::copy(source, &file_path)?;
+
+        let loader = DataLoader::new(&json!({
+            "file_path": file_path,
+        }))?;
+
+        assert_eq!(expected_format, loader.format(), "file = {}", file_name);
+    }
+
+    let excel_path = temp_dir.path().join("excel download");
+    let loader = DataLoader::new(&json!({
+        "file_path": excel_path,
+        "sheet_name": "tblUST_DB",
+    }))?;
+    assert!(!loader.schema().await?.columns().is_empty());
+
+    let archive_path = temp_dir.path().join("archive download");
+    let mut archive = zip::ZipWriter::new(std::fs::File::create(&archive_path)?);
+    archive.start_file("readme.txt", zip::write::FileOptions::default())?;
+    std::io::Write::write_all(&mut archive, b"not a workbook")?;
+    archive.finish()?;
+    assert!(DataLoader::new(&json!({ "file_path": archive_path })).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn explicit_format_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let loader = DataLoader::new(&json!({
+        "file_path": "tests/geojson data test.geojson",
+        "format": "geojson",
+    }))?;
+    assert_eq!("geojson", loader.format());
+
+    let Err(error) = DataLoader::new(&json!({
+        "file_path": "tests/geojson data test.geojson",
+        "format": "kml",
+    })) else {
+        return Err("Expected an unknown format to fail".into())
+    };
+    assert!(format!("{}", error).contains("geojson, ipc, parquet"));
+
+    Ok(())
+}
+
+#[derive(Deserialize, Serialize)]
+struct AgencyExportOptions {
+    file_path: String,
+}
+
+impl DataOptions for AgencyExportOptions {}
+
+#[async_trait]
+impl SourceLoader for AgencyExportOptions {
+    async fn schema(&self) -> BulkDataResult<Schema> {
+        Schema::from_iter("agency_export", [("id", ColumnType::Integer)].into_iter())
+    }
+
+    async fn spool_records(&self, _record_channel: &mut RecordSpoolChannel) -> RecordSpoolResult {
+        None
+    }
+
+    fn copy_statement(&self, copy_options: &CopyOptions) -> String {
+        copy_options.copy_statement(self)
+    }
+}
+
+#[tokio::test]
+async fn registered_format_data_loading() -> Result<(), Box<dyn std::error::Error>> {
+    let mut registry = LoaderRegistry::default();
+    registry.register(
+        LoaderRegistration::new::<AgencyExportOptions>("agency_export").with_extensions(&["agx"]),
+    )?;
+
+    let loader = DataLoader::from_registry(&json!({ "file_path": "export.agx" }), &registry)?;
+    let schema = loader.schema().await?;
+
+    assert_eq!("agency_export", loader.format());
+    assert_eq!("agency_export", schema.table_name());
+    assert!(registry
+        .register(LoaderRegistration::new::<AgencyExportOptions>("agency_export"))
+        .is_err());
+
+    Ok(())
+}